@@ -0,0 +1,13 @@
+// Path: aln-orchestrator/src/bin/aln-orchestrator-notify.rs
+use aln_orchestrator::exit::Exit;
+use std::process::ExitCode;
+
+fn main() -> ExitCode {
+    match aln_orchestrator::notify::run(&aln_orchestrator::repo_root_from_env()) {
+        Ok(()) => Exit::Ok.into(),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Exit::from(&e).into()
+        }
+    }
+}
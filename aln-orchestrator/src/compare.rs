@@ -0,0 +1,131 @@
+// Path: aln-orchestrator/src/compare.rs
+//! `--compare`: classify how fragment statuses moved between a baseline
+//! report and the current run, so PR gates only fail on regressions.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{FragmentResult, ValidationReport};
+
+/// One fragment's status in the baseline (`before`) and current run
+/// (`after`); `None` when the fragment is absent from that report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentChange {
+    pub spec_source: String,
+    pub id: String,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Differences between a baseline report and the current run. Moves between
+/// passing statuses (`ok` and `ok_cached`) are not changes.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReportDiff {
+    /// Path of the baseline report.
+    pub baseline: String,
+    /// Fragments that passed in the baseline and no longer do.
+    pub regressed: Vec<FragmentChange>,
+    /// Any other status change, e.g. `hash_mismatch` -> `ok`.
+    pub changed: Vec<FragmentChange>,
+    pub added: Vec<FragmentChange>,
+    pub removed: Vec<FragmentChange>,
+}
+
+impl ReportDiff {
+    pub fn has_regressions(&self) -> bool {
+        !self.regressed.is_empty()
+    }
+}
+
+/// Compare fragments keyed by `(spec_source, id)`; each list is sorted by
+/// that key.
+pub(crate) fn diff_reports(
+    baseline_path: &str,
+    baseline: &ValidationReport,
+    current: &ValidationReport,
+) -> ReportDiff {
+    fn keyed(report: &ValidationReport) -> BTreeMap<(&str, &str), &FragmentResult> {
+        report
+            .fragments
+            .iter()
+            .map(|f| ((f.spec_source.as_str(), f.id.as_str()), f))
+            .collect()
+    }
+
+    let before = keyed(baseline);
+    let after = keyed(current);
+    let mut diff = ReportDiff {
+        baseline: baseline_path.to_string(),
+        ..ReportDiff::default()
+    };
+
+    let mut keys: Vec<_> = before.keys().chain(after.keys()).copied().collect();
+    keys.sort_unstable();
+    keys.dedup();
+    for key in keys {
+        let (old, new) = (before.get(&key), after.get(&key));
+        let change = FragmentChange {
+            spec_source: key.0.to_string(),
+            id: key.1.to_string(),
+            before: old.map(|f| f.status.clone()),
+            after: new.map(|f| f.status.clone()),
+        };
+        match (old, new) {
+            (None, Some(_)) => diff.added.push(change),
+            (Some(_), None) => diff.removed.push(change),
+            (Some(old), Some(new)) => {
+                if old.passed() && !new.passed() {
+                    diff.regressed.push(change);
+                } else if old.passed() != new.passed()
+                    || (!old.passed() && old.status != new.status)
+                {
+                    diff.changed.push(change);
+                }
+            }
+            (None, None) => unreachable!("key comes from one of the reports"),
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fragment_result, report_with};
+
+    #[test]
+    fn diff_classifies_regressions_additions_and_removals() {
+        let baseline = report_with(vec![
+            fragment_result("a", "ok"),
+            fragment_result("c", "ok"),
+            fragment_result("d", "hash_mismatch"),
+            fragment_result("e", "ok"),
+        ]);
+        let current = report_with(vec![
+            fragment_result("a", "hash_mismatch"),
+            fragment_result("b", "ok"),
+            fragment_result("d", "ok"),
+            fragment_result("e", "ok_cached"),
+        ]);
+
+        let diff = diff_reports("base.json", &baseline, &current);
+        let ids =
+            |changes: &[FragmentChange]| changes.iter().map(|c| c.id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&diff.regressed), vec!["a"]);
+        assert_eq!(
+            (
+                diff.regressed[0].before.as_deref(),
+                diff.regressed[0].after.as_deref()
+            ),
+            (Some("ok"), Some("hash_mismatch"))
+        );
+        assert_eq!(ids(&diff.added), vec!["b"]);
+        assert_eq!(diff.added[0].before, None);
+        assert_eq!(ids(&diff.removed), vec!["c"]);
+        assert_eq!(ids(&diff.changed), vec!["d"]);
+        assert!(diff.has_regressions());
+
+        let clean = diff_reports("base.json", &current, &current);
+        assert!(!clean.has_regressions());
+        assert!(clean.added.is_empty() && clean.removed.is_empty() && clean.changed.is_empty());
+    }
+}
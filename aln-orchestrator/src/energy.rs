@@ -0,0 +1,191 @@
+// Path: aln-orchestrator/src/energy.rs
+//! `--energy-ledger`: total the AU.ET and CSP charged per UTC day in a
+//! ledger-core energy ledger and check each day against the spec's
+//! `[energy]` caps.
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::{EnergySection, OrchestratorError};
+
+/// The fields of a ledger-core `EnergyEvent` that usage is computed from.
+#[derive(Debug, Deserialize)]
+struct LedgerEvent {
+    au_et_delta: f64,
+    csp_delta: f64,
+    /// RFC 3339, e.g. `2025-01-01T00:00:00Z`; the day is its date in UTC.
+    timestamp: String,
+}
+
+/// A serialized `LedgerState`, or just its `events`.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LedgerFile {
+    State { events: Vec<LedgerEvent> },
+    Events(Vec<LedgerEvent>),
+}
+
+/// Energy charged on one day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DailyEnergy {
+    pub(crate) day: String,
+    pub(crate) auet: f64,
+    pub(crate) csp: f64,
+    /// `ok` or `energy_cap_exceeded`.
+    pub(crate) status: String,
+}
+
+/// Daily totals from an energy ledger, next to the caps in `energy_bounds`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct EnergyUsage {
+    /// Path of the ledger, as given.
+    pub(crate) ledger: String,
+    /// `ok`, or `energy_cap_exceeded` when any day is over a cap.
+    pub(crate) status: String,
+    pub(crate) peak_auet_per_day: f64,
+    pub(crate) peak_csp_per_day: f64,
+    /// One entry per day with events, oldest first.
+    pub(crate) days: Vec<DailyEnergy>,
+}
+
+impl EnergyUsage {
+    pub(crate) fn passed(&self) -> bool {
+        self.status == "ok"
+    }
+}
+
+const CAP_EXCEEDED: &str = "energy_cap_exceeded";
+
+/// Read the ledger at `path` and total each day's charges. Only positive
+/// deltas count towards a day's total: refunds and admin corrections do not
+/// buy back headroom under a per-day cap.
+pub(crate) fn load_usage(
+    path: &Path,
+    bounds: &EnergySection,
+) -> Result<EnergyUsage, OrchestratorError> {
+    let text = fs::read_to_string(path)?;
+    let events = match serde_json::from_str(&text).map_err(io::Error::from)? {
+        LedgerFile::State { events } | LedgerFile::Events(events) => events,
+    };
+    Ok(daily_usage(&path.display().to_string(), &events, bounds)?)
+}
+
+fn daily_usage(
+    ledger: &str,
+    events: &[LedgerEvent],
+    bounds: &EnergySection,
+) -> Result<EnergyUsage, io::Error> {
+    let mut totals: BTreeMap<NaiveDate, (f64, f64)> = BTreeMap::new();
+    for ev in events {
+        let day = DateTime::parse_from_rfc3339(&ev.timestamp)
+            .map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "energy ledger event has a malformed timestamp {:?}: {}",
+                        ev.timestamp, e
+                    ),
+                )
+            })?
+            .with_timezone(&Utc)
+            .date_naive();
+        let total = totals.entry(day).or_default();
+        total.0 += ev.au_et_delta.max(0.0);
+        total.1 += ev.csp_delta.max(0.0);
+    }
+
+    let max_auet = bounds.max_auet_per_day as f64;
+    let max_csp = bounds.max_csp_per_day as f64;
+    let days: Vec<DailyEnergy> = totals
+        .into_iter()
+        .map(|(day, (auet, csp))| DailyEnergy {
+            day: day.to_string(),
+            auet,
+            csp,
+            status: if auet > max_auet || csp > max_csp {
+                CAP_EXCEEDED
+            } else {
+                "ok"
+            }
+            .to_string(),
+        })
+        .collect();
+    let status = if days.iter().any(|d| d.status != "ok") {
+        CAP_EXCEEDED
+    } else {
+        "ok"
+    };
+    Ok(EnergyUsage {
+        ledger: ledger.to_string(),
+        status: status.to_string(),
+        peak_auet_per_day: days.iter().map(|d| d.auet).fold(0.0, f64::max),
+        peak_csp_per_day: days.iter().map(|d| d.csp).fold(0.0, f64::max),
+        days,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(timestamp: &str, auet: f64, csp: f64) -> LedgerEvent {
+        LedgerEvent {
+            au_et_delta: auet,
+            csp_delta: csp,
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    #[test]
+    fn days_over_either_cap_fail_and_refunds_do_not_count() {
+        let bounds = EnergySection {
+            max_auet_per_day: 100,
+            max_csp_per_day: 10,
+        };
+        let events = [
+            event("2025-01-02T09:00:00Z", 60.0, 4.0),
+            event("2025-01-01T23:59:59Z", 100.0, 2.0),
+            event("2025-01-02T10:00:00Z", 50.0, 1.0),
+            event("2025-01-02T11:00:00Z", -30.0, -5.0),
+            event("2025-01-03T00:00:00Z", 1.0, 11.0),
+        ];
+        let usage = daily_usage("ledger.json", &events, &bounds).unwrap();
+        assert_eq!(usage.status, CAP_EXCEEDED);
+        assert!(!usage.passed());
+        assert_eq!(usage.peak_auet_per_day, 110.0);
+        assert_eq!(usage.peak_csp_per_day, 11.0);
+        let summary: Vec<_> = usage
+            .days
+            .iter()
+            .map(|d| (d.day.as_str(), d.auet, d.csp, d.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("2025-01-01", 100.0, 2.0, "ok"),
+                ("2025-01-02", 110.0, 5.0, CAP_EXCEEDED),
+                ("2025-01-03", 1.0, 11.0, CAP_EXCEEDED),
+            ]
+        );
+
+        let within = daily_usage("ledger.json", &events[..1], &bounds).unwrap();
+        assert!(within.passed());
+
+        // Days are UTC days, whatever offset the event was stamped with.
+        let offsets = [
+            event("2025-01-01T23:30:00-02:00", 60.0, 1.0),
+            event("2025-01-02T00:30:00+02:00", 50.0, 1.0),
+        ];
+        let usage = daily_usage("ledger.json", &offsets, &bounds).unwrap();
+        let days: Vec<_> = usage
+            .days
+            .iter()
+            .map(|d| (d.day.as_str(), d.auet))
+            .collect();
+        assert_eq!(days, [("2025-01-01", 50.0), ("2025-01-02", 60.0)]);
+        assert!(daily_usage("ledger.json", &[event("yesterday", 1.0, 1.0)], &bounds).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+// Path: aln-orchestrator/src/exit.rs
+//! Process exit codes shared by `aln-orchestrator`, `javaspectre` and the
+//! standalone tool binaries, so CI can tell a failed check from a broken run.
+//!
+//! | code | meaning                                                       |
+//! |------|---------------------------------------------------------------|
+//! | 0    | success                                                       |
+//! | 1    | validation failure: the run completed and found problems      |
+//! | 2    | usage error: bad flags or arguments (clap's own default)      |
+//! | 3    | IO error: an input or output file could not be read/written   |
+//! | 4    | data error: an input was read but is malformed                |
+//! | 5    | internal error: anything not covered above                    |
+use crate::OrchestratorError;
+use std::io;
+use std::process::ExitCode;
+
+/// One-line summary of the codes, for `--help` epilogues.
+pub const EXIT_CODES_HELP: &str = "Exit codes: 0 ok, 1 validation failure, 2 usage error, \
+                                   3 IO error, 4 malformed input, 5 internal error";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Exit {
+    Ok = 0,
+    ValidationFailed = 1,
+    Usage = 2,
+    Io = 3,
+    Data = 4,
+    Internal = 5,
+}
+
+impl Exit {
+    pub fn code(self) -> u8 {
+        self as u8
+    }
+
+    /// Exit for a run that completed; `passed` is the run's verdict.
+    pub fn from_outcome(passed: bool) -> Self {
+        if passed {
+            Exit::Ok
+        } else {
+            Exit::ValidationFailed
+        }
+    }
+
+    /// Exit for a complete `run`-style result, verdict or error.
+    pub fn from_result(result: &Result<bool, OrchestratorError>) -> Self {
+        match result {
+            Ok(passed) => Exit::from_outcome(*passed),
+            Err(e) => Exit::from(e),
+        }
+    }
+}
+
+impl From<&OrchestratorError> for Exit {
+    fn from(e: &OrchestratorError) -> Self {
+        match e {
+            OrchestratorError::Io(e) => Exit::from(e),
+            OrchestratorError::Toml(_)
+            | OrchestratorError::Manifest(_)
+            | OrchestratorError::InvalidEnergyBounds { .. } => Exit::Data,
+            OrchestratorError::RepoRoot { .. } => Exit::Usage,
+            OrchestratorError::Signature(_) | OrchestratorError::InvalidSeal { .. } => {
+                Exit::ValidationFailed
+            }
+        }
+    }
+}
+
+impl From<&io::Error> for Exit {
+    fn from(e: &io::Error) -> Self {
+        // Decoders (UTF-8, serde_json via `io::Error::from`) report bad
+        // content as InvalidData; everything else is the file system's fault.
+        match e.kind() {
+            io::ErrorKind::InvalidData => Exit::Data,
+            _ => Exit::Io,
+        }
+    }
+}
+
+impl From<Exit> for ExitCode {
+    fn from(exit: Exit) -> Self {
+        ExitCode::from(exit.code())
+    }
+}
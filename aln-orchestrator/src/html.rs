@@ -0,0 +1,124 @@
+// Path: aln-orchestrator/src/html.rs
+//! Self-contained HTML rendering of a `ValidationReport` for `--html`.
+//! Everything (CSS and the few lines of table-sorting script) is inline so the
+//! page can be attached to a ticket or mailed as a single file.
+use std::fmt::Write;
+
+use crate::ValidationReport;
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+.banner{padding:1em;border-radius:4px;font-weight:bold;margin-bottom:1em}\
+.pass{background:#d7f5dd;border:1px solid #2e7d32}\
+.fail{background:#fbdada;border:1px solid #c62828}\
+table{border-collapse:collapse;width:100%;margin-bottom:1em}\
+th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#f0f0f0;cursor:pointer}\
+tr.fail td.status{color:#c62828;font-weight:bold}";
+
+const SORT_SCRIPT: &str = "document.querySelectorAll('table.sortable th').forEach(function(th,i){\
+th.addEventListener('click',function(){var b=th.closest('table').tBodies[0];\
+var asc=th.dataset.asc!=='1';th.dataset.asc=asc?'1':'0';\
+Array.from(b.rows).sort(function(x,y){var c=x.cells[i].textContent.localeCompare(y.cells[i].textContent);\
+return asc?c:-c;}).forEach(function(r){b.appendChild(r);});});});";
+
+/// Escape text for use in element content and double-quoted attributes.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Render `report` as a standalone HTML page: a pass/fail banner carrying
+/// the report's `Display` counts, a click-to-sort fragment table and the
+/// energy bounds.
+pub(crate) fn render_report(report: &ValidationReport) -> String {
+    let verdict = if report.passed() { "pass" } else { "fail" };
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <title>Compliance report: {blueprint}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n\
+         <h1>{blueprint} {version}</h1>\n<div class=\"banner {verdict}\">{label}: {counts}</div>\n",
+        blueprint = escape(&report.blueprint),
+        version = escape(&report.version),
+        label = verdict.to_uppercase(),
+        counts = escape(&report.to_string()),
+    );
+
+    html.push_str(
+        "<table class=\"sortable\">\n<thead><tr><th>Spec</th><th>Id</th><th>Path</th>\
+         <th>Status</th><th>Detail</th></tr></thead>\n<tbody>\n",
+    );
+    for frag in &report.fragments {
+        let class = if frag.passed() { "pass" } else { "fail" };
+        let _ = writeln!(
+            html,
+            "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td class=\"status\">{}</td><td>{}</td></tr>",
+            class,
+            escape(&frag.spec_source),
+            escape(&frag.id),
+            escape(&frag.path),
+            escape(&frag.status),
+            escape(frag.detail.as_deref().unwrap_or("")),
+        );
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    let _ = write!(
+        html,
+        "<h2>Energy bounds</h2>\n<table>\n<tr><th>max_auet_per_day</th><td>{}</td></tr>\n\
+         <tr><th>max_csp_per_day</th><td>{}</td></tr>\n",
+        report.energy_bounds.max_auet_per_day, report.energy_bounds.max_csp_per_day,
+    );
+    if let Some(usage) = &report.energy_usage {
+        let _ = write!(
+            html,
+            "<tr><th>peak_auet_per_day</th><td>{}</td></tr>\n\
+             <tr><th>peak_csp_per_day</th><td>{}</td></tr>\n\
+             <tr><th>status</th><td class=\"status\">{}</td></tr>\n",
+            usage.peak_auet_per_day,
+            usage.peak_csp_per_day,
+            escape(&usage.status),
+        );
+    }
+    let _ = write!(
+        html,
+        "</table>\n<script>{SORT_SCRIPT}</script>\n</body>\n</html>\n"
+    );
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{fragment_result, report_with};
+
+    #[test]
+    fn html_lists_fragments_and_marks_pass_or_fail() {
+        let failing = report_with(vec![
+            fragment_result("alpha", "ok"),
+            fragment_result("<beta>", "hash_mismatch"),
+        ]);
+        let html = render_report(&failing);
+        assert!(html.contains("<td>alpha</td>"));
+        assert!(html.contains("<td>&lt;beta&gt;</td>"));
+        assert!(html.contains("class=\"banner fail\">FAIL: 2 fragments: 1 ok, 1 failed"));
+        assert!(html.contains("<td>1000</td>"));
+        assert!(!html.contains("<script src") && !html.contains("<link"));
+
+        let passing = report_with(vec![
+            fragment_result("alpha", "ok"),
+            fragment_result("gamma", "ok_cached"),
+        ]);
+        assert!(render_report(&passing).contains("class=\"banner pass\""));
+    }
+}
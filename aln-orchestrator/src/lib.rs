@@ -0,0 +1,2674 @@
+// Path: aln-orchestrator/src/lib.rs
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use thiserror::Error;
+
+mod compare;
+mod energy;
+pub mod exit;
+mod html;
+pub mod notify;
+mod signing;
+
+pub use compare::{FragmentChange, ReportDiff};
+
+/// Flags for a validation run, shared by the `aln-orchestrator` binary and
+/// `javaspectre validate`.
+#[derive(Debug, Default, clap::Args)]
+pub struct ValidateArgs {
+    /// Repository to validate. Takes precedence over `$GITHUB_WORKSPACE`,
+    /// which takes precedence over the current directory.
+    #[arg(long, value_name = "PATH")]
+    pub repo_root: Option<PathBuf>,
+    /// Sign the report hash with the Ed25519 secret key at this path, writing
+    /// `compliance_report.sig` next to the report.
+    #[arg(long, value_name = "ED25519_KEY_PATH")]
+    pub sign: Option<PathBuf>,
+    /// Verify an existing report and its signature against this public key
+    /// instead of running validation.
+    #[arg(long, value_name = "PUBKEY", conflicts_with = "sign")]
+    pub verify: Option<PathBuf>,
+    /// Only re-hash fragments whose fragment or seal file changed since this
+    /// git ref; unchanged fragments that were ok in the previous report are
+    /// reported as `ok_cached`. Falls back to full validation when git or the
+    /// previous report is unavailable.
+    #[arg(long, value_name = "GIT_REF")]
+    pub changed_since: Option<String>,
+    /// Where to write the JSON report (parent directories are created).
+    /// Defaults to `<repo root>/compliance_report.json`.
+    #[arg(long, value_name = "PATH")]
+    pub report_out: Option<PathBuf>,
+    /// Walk this directory (relative to the repo root) and list files that no
+    /// fragment `path` or `seal` references under the report's `coverage`.
+    #[arg(long, value_name = "DIR")]
+    pub coverage: Option<PathBuf>,
+    /// Also render the report as a self-contained HTML page at this path.
+    #[arg(long, value_name = "PATH")]
+    pub html: Option<PathBuf>,
+    /// Diff this run against a baseline report, recording the result under
+    /// the report's `comparison`. The run then fails only on regressions
+    /// (fragments that passed in the baseline and no longer do), plus any
+    /// fragment left failing by `--fix` or file left unsealed under
+    /// `--strict`.
+    #[arg(long, value_name = "BASELINE_REPORT")]
+    pub compare: Option<PathBuf>,
+    /// Require every seal to be lowercase hex of its algorithm's digest length
+    /// (64 characters for SHA-256) and match the digest byte for byte,
+    /// instead of comparing case-insensitively.
+    #[arg(long)]
+    pub strict_seals: bool,
+    /// Treat anything short of a clean run as a failure: implies
+    /// `--strict-seals`, and files left unsealed under `--coverage` fail the
+    /// run.
+    #[arg(long)]
+    pub strict: bool,
+    /// Hash fragments on this many threads. Results are reported in spec
+    /// order regardless.
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    pub jobs: u16,
+    /// Print the seals `--fix` would rewrite, with old -> new hashes, and
+    /// exit 0 without touching any file (including the report).
+    #[arg(long, conflicts_with_all = ["verify", "sign"])]
+    pub plan_seals: bool,
+    /// Rewrite the seal of every `hash_mismatch` fragment with its current
+    /// digest and report those fragments as `ok`. The run still fails if any
+    /// other fragment does.
+    #[arg(long, conflicts_with_all = ["verify", "plan_seals"])]
+    pub fix: bool,
+    /// Total the AU.ET and CSP charged per day in this ledger-core energy
+    /// ledger (a serialized `LedgerState` or a JSON array of its events) and
+    /// fail the run when any day exceeds the spec's `[energy]` caps.
+    #[arg(long, value_name = "LEDGER_JSON")]
+    pub energy_ledger: Option<PathBuf>,
+}
+
+/// How `run` prints its results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Report marker plus one `path [id]: status` line per fragment.
+    #[default]
+    Text,
+    /// The full validation report as pretty-printed JSON.
+    Json,
+}
+
+/// Knobs for a single validation run.
+#[derive(Debug, Default)]
+struct ValidateOptions {
+    changed_since: Option<String>,
+    report_out: Option<PathBuf>,
+    coverage: Option<PathBuf>,
+    compare: Option<PathBuf>,
+    energy_ledger: Option<PathBuf>,
+    strict_seals: bool,
+    /// Fail the run when `coverage` finds unsealed files.
+    require_coverage: bool,
+    /// Worker threads for fragment hashing; 0 and 1 both mean sequential.
+    jobs: usize,
+    /// Rewrite the seals of `hash_mismatch` fragments (see `fix_seals`).
+    fix_seals: bool,
+}
+
+impl ValidateOptions {
+    fn report_path(&self, repo_root: &Path) -> PathBuf {
+        self.report_out
+            .clone()
+            .unwrap_or_else(|| repo_root.join("compliance_report.json"))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct FragmentSpec {
+    id: String,
+    path: String,
+    seal: String,
+    #[serde(default)]
+    hasher: HasherKind,
+    /// Digest algorithm the seal was written with: `sha256` (the default),
+    /// `sha512` or `blake3`. Other names are reported per fragment as
+    /// `unsupported_algorithm` rather than failing the spec.
+    #[serde(default = "default_algorithm")]
+    algorithm: String,
+}
+
+fn default_algorithm() -> String {
+    SealAlgorithm::Sha256.name().to_string()
+}
+
+/// Digest a fragment's (possibly normalized) bytes are sealed with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SealAlgorithm {
+    Sha256,
+    Sha512,
+    Blake3,
+}
+
+impl SealAlgorithm {
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "sha256" => Some(SealAlgorithm::Sha256),
+            "sha512" => Some(SealAlgorithm::Sha512),
+            "blake3" => Some(SealAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SealAlgorithm::Sha256 => "sha256",
+            SealAlgorithm::Sha512 => "sha512",
+            SealAlgorithm::Blake3 => "blake3",
+        }
+    }
+
+    /// Length of the lowercase hex digest.
+    fn hex_len(self) -> usize {
+        match self {
+            SealAlgorithm::Sha256 | SealAlgorithm::Blake3 => 64,
+            SealAlgorithm::Sha512 => 128,
+        }
+    }
+
+    fn digest(self, bytes: &[u8]) -> String {
+        match self {
+            SealAlgorithm::Sha256 => hex::encode(Sha256::digest(bytes)),
+            SealAlgorithm::Sha512 => hex::encode(Sha512::digest(bytes)),
+            SealAlgorithm::Blake3 => blake3::hash(bytes).to_hex().to_string(),
+        }
+    }
+}
+
+/// Selects which bytes of a fragment are hashed with its `algorithm` before
+/// comparison with its seal.
+///
+/// Changing the hasher of an existing fragment changes its digest, so the
+/// fragment's seal file must be regenerated in the same change.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum HasherKind {
+    /// The raw file bytes, hashed with the fragment's `algorithm` (the
+    /// historical behavior).
+    #[default]
+    Raw,
+    /// The text after normalizing line endings to LF and stripping trailing
+    /// whitespace from every line, hashed with the fragment's `algorithm`.
+    NormalizedText,
+}
+
+/// Read access to fragment and seal files. Validation goes through this
+/// rather than `std::fs` so tests can simulate missing files and IO errors.
+pub trait FragmentFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+    fn exists(&self, path: &Path) -> bool;
+}
+
+/// The local filesystem.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FragmentFs for RealFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+}
+
+fn invalid_utf8(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{} is not valid UTF-8", path.display()),
+    )
+}
+
+impl HasherKind {
+    fn hasher(self) -> &'static dyn FragmentHasher {
+        match self {
+            HasherKind::Raw => &RawBytesHasher,
+            HasherKind::NormalizedText => &NormalizedTextHasher,
+        }
+    }
+}
+
+/// Computes the hex digest recorded in a fragment's seal file.
+trait FragmentHasher {
+    fn hash(
+        &self,
+        fs: &dyn FragmentFs,
+        path: &Path,
+        algorithm: SealAlgorithm,
+    ) -> io::Result<String>;
+}
+
+/// Hashes the file exactly as stored on disk.
+struct RawBytesHasher;
+
+impl FragmentHasher for RawBytesHasher {
+    fn hash(
+        &self,
+        fs: &dyn FragmentFs,
+        path: &Path,
+        algorithm: SealAlgorithm,
+    ) -> io::Result<String> {
+        Ok(algorithm.digest(&fs.read(path)?))
+    }
+}
+
+/// Hashes UTF-8 text after normalization so cosmetic diffs (CRLF vs LF,
+/// trailing spaces) don't break seals.
+struct NormalizedTextHasher;
+
+impl FragmentHasher for NormalizedTextHasher {
+    fn hash(
+        &self,
+        fs: &dyn FragmentFs,
+        path: &Path,
+        algorithm: SealAlgorithm,
+    ) -> io::Result<String> {
+        let bytes = fs.read(path)?;
+        let text = std::str::from_utf8(&bytes).map_err(|_| invalid_utf8(path))?;
+        Ok(algorithm.digest(normalize_text(text).as_bytes()))
+    }
+}
+
+fn normalize_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for line in text.lines() {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+#[derive(Debug, Deserialize)]
+struct PipelineNode {
+    id: String,
+    requires: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct OrchestrationSection {
+    contracts: Vec<OrchestrationContract>,
+    pipelines: OrchestrationPipelines,
+}
+
+/// Why a spec's `[orchestration.pipelines]` graph is not a DAG.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+enum PipelineGraphError {
+    #[error("pipeline node {node:?} requires unknown node {missing:?}")]
+    UnknownDependency { node: String, missing: String },
+    /// The nodes on the cycle in `requires` order, first node repeated last.
+    #[error("pipeline graph has a cycle: {}", .0.join(" -> "))]
+    Cycle(Vec<String>),
+}
+
+/// Check that every `requires` entry names a node in `graph` or one of
+/// `fragment_ids` (fragments are leaves with no requirements of their own),
+/// and that the `requires` edges have no cycle, including a node requiring
+/// itself. Nodes are visited in spec order, so the reported cycle is stable.
+fn validate_pipeline_graph(
+    graph: &[PipelineNode],
+    fragment_ids: &HashSet<&str>,
+) -> Result<(), PipelineGraphError> {
+    let index: HashMap<&str, usize> = graph
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), i))
+        .collect();
+    let mut edges = Vec::with_capacity(graph.len());
+    for node in graph {
+        let mut deps = Vec::with_capacity(node.requires.len());
+        for dep in &node.requires {
+            match index.get(dep.as_str()) {
+                Some(&i) => deps.push(i),
+                None if fragment_ids.contains(dep.as_str()) => {}
+                None => {
+                    return Err(PipelineGraphError::UnknownDependency {
+                        node: node.id.clone(),
+                        missing: dep.clone(),
+                    })
+                }
+            }
+        }
+        edges.push(deps);
+    }
+
+    #[derive(Clone, Copy, PartialEq)]
+    enum Mark {
+        Unvisited,
+        OnStack,
+        Done,
+    }
+    fn visit(
+        at: usize,
+        edges: &[Vec<usize>],
+        marks: &mut [Mark],
+        stack: &mut Vec<usize>,
+    ) -> Option<Vec<usize>> {
+        marks[at] = Mark::OnStack;
+        stack.push(at);
+        for &next in &edges[at] {
+            match marks[next] {
+                Mark::OnStack => {
+                    let start = stack.iter().position(|&n| n == next).expect("on stack");
+                    let mut cycle = stack[start..].to_vec();
+                    cycle.push(next);
+                    return Some(cycle);
+                }
+                Mark::Unvisited => {
+                    if let Some(cycle) = visit(next, edges, marks, stack) {
+                        return Some(cycle);
+                    }
+                }
+                Mark::Done => {}
+            }
+        }
+        stack.pop();
+        marks[at] = Mark::Done;
+        None
+    }
+
+    let mut marks = vec![Mark::Unvisited; graph.len()];
+    for start in 0..graph.len() {
+        if marks[start] != Mark::Unvisited {
+            continue;
+        }
+        if let Some(cycle) = visit(start, &edges, &mut marks, &mut Vec::new()) {
+            return Err(PipelineGraphError::Cycle(
+                cycle.into_iter().map(|i| graph[i].id.clone()).collect(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Node ids of a valid pipeline graph in dependency order (Kahn's
+/// algorithm): every node comes after the nodes it `requires`, and among
+/// nodes that are ready at the same time the lexicographically smallest id
+/// goes first, so the order is deterministic. Requirements on fragment ids
+/// are already satisfied and impose no ordering. Call only after
+/// `validate_pipeline_graph` accepted `graph`.
+fn pipeline_execution_order(graph: &[PipelineNode]) -> Vec<String> {
+    let index: HashMap<&str, usize> = graph
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), i))
+        .collect();
+    let mut pending = vec![0usize; graph.len()];
+    let mut dependents = vec![Vec::new(); graph.len()];
+    for (i, node) in graph.iter().enumerate() {
+        for dep in &node.requires {
+            if let Some(&d) = index.get(dep.as_str()) {
+                pending[i] += 1;
+                dependents[d].push(i);
+            }
+        }
+    }
+
+    let mut ready: BTreeSet<(&str, usize)> = pending
+        .iter()
+        .enumerate()
+        .filter(|(_, &n)| n == 0)
+        .map(|(i, _)| (graph[i].id.as_str(), i))
+        .collect();
+    let mut order = Vec::with_capacity(graph.len());
+    while let Some((id, at)) = ready.pop_first() {
+        order.push(id.to_string());
+        for &next in &dependents[at] {
+            pending[next] -= 1;
+            if pending[next] == 0 {
+                ready.insert((graph[next].id.as_str(), next));
+            }
+        }
+    }
+    order
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct OrchestrationContract {
+    id: String,
+    repo: String,
+    org: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OrchestrationPipelines {
+    graph: Vec<PipelineNode>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct EnergySection {
+    max_auet_per_day: u64,
+    max_csp_per_day: u64,
+}
+
+/// What a spec's fragment `path`/`seal` entries are relative to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum PathBase {
+    /// The repository root (the historical behavior).
+    #[default]
+    RepoRoot,
+    /// The directory containing the spec file.
+    SpecDir,
+}
+
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+struct ComplianceSpec {
+    #[serde(default)]
+    path_base: PathBase,
+    version: String,
+    language: String,
+    blueprint: String,
+    fragments: FragmentsWrapper,
+    orchestration: OrchestrationSection,
+    energy: EnergySection,
+}
+
+#[derive(Debug, Deserialize)]
+struct FragmentsWrapper {
+    items: Vec<FragmentSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpecManifest {
+    specs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FragmentResult {
+    /// Repo-relative path of the spec that declared this fragment.
+    #[serde(default)]
+    spec_source: String,
+    id: String,
+    path: String,
+    seal: String,
+    /// Seal digest algorithm as named in the spec. Reports written before
+    /// algorithms were configurable are all `sha256`.
+    #[serde(default = "default_algorithm")]
+    algorithm: String,
+    status: String,
+    expected: Option<String>,
+    actual: Option<String>,
+    detail: Option<String>,
+}
+
+impl FragmentResult {
+    fn passed(&self) -> bool {
+        self.status == "ok" || self.status == "ok_cached"
+    }
+}
+
+/// Files under a `--coverage` directory and which of them no fragment seals.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct CoverageSection {
+    root: String,
+    total_files: usize,
+    uncovered: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidationReport {
+    fragments: Vec<FragmentResult>,
+    blueprint: String,
+    version: String,
+    energy_bounds: EnergySection,
+    /// Daily totals from `--energy-ledger`, checked against `energy_bounds`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    energy_usage: Option<energy::EnergyUsage>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    coverage: Option<CoverageSection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comparison: Option<ReportDiff>,
+    /// `ok`, or `<spec>: <error>` for the first spec whose pipeline graph has
+    /// an unknown dependency or a cycle.
+    #[serde(default = "pipeline_ok")]
+    pipeline_status: String,
+    /// Pipeline node ids in the order they can run, spec by spec (see
+    /// `pipeline_execution_order`); empty when `pipeline_status` is not `ok`.
+    #[serde(default)]
+    execution_order: Vec<String>,
+    /// Seals rewritten by `--fix`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    fixed_seals: Vec<SealChange>,
+}
+
+fn pipeline_ok() -> String {
+    "ok".to_string()
+}
+
+impl ValidationReport {
+    /// Whether every fragment is `ok` or `ok_cached`, the pipeline graph is
+    /// valid and no day in the energy ledger (if any) exceeds a cap.
+    pub fn passed(&self) -> bool {
+        self.pipeline_status == "ok"
+            && self
+                .energy_usage
+                .as_ref()
+                .is_none_or(energy::EnergyUsage::passed)
+            && self.fragments.iter().all(FragmentResult::passed)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ok = self.fragments.iter().filter(|r| r.passed()).count();
+        write!(
+            f,
+            "{} fragments: {} ok, {} failed",
+            self.fragments.len(),
+            ok,
+            self.fragments.len() - ok
+        )
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum OrchestratorError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("TOML parse error in {0}")]
+    Toml(TomlDiagnostic),
+    #[error("Signature error: {0}")]
+    Signature(String),
+    #[error("Spec manifest error: {0}")]
+    Manifest(String),
+    #[error("Invalid repo root {path}: {reason}")]
+    RepoRoot { path: PathBuf, reason: &'static str },
+    #[error("Invalid energy bounds in {spec}: {field} = {value}, expected 1..={max}")]
+    InvalidEnergyBounds {
+        spec: String,
+        field: &'static str,
+        value: u64,
+        max: u64,
+    },
+    #[error("Invalid seal {path}: {reason}")]
+    InvalidSeal { path: PathBuf, reason: String },
+}
+
+/// A TOML parse failure located in its source file. Renders as
+/// `path:line:column: message` followed by the offending line and a caret
+/// when toml reports a span, or `path: message` when it does not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlDiagnostic {
+    pub path: PathBuf,
+    /// 1-based line and (character) column of the start of the error span.
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub message: String,
+    /// The source line containing the error, without its line ending.
+    pub snippet: Option<String>,
+}
+
+impl TomlDiagnostic {
+    fn new(path: &Path, text: &str, err: &toml::de::Error) -> Self {
+        let mut diag = TomlDiagnostic {
+            path: path.to_path_buf(),
+            line: None,
+            column: None,
+            // toml splits e.g. "invalid inline table" / "expected `}`" over
+            // lines; keep the header on one line for CI log matchers.
+            message: err.message().trim().lines().collect::<Vec<_>>().join("; "),
+            snippet: None,
+        };
+        if let Some(span) = err.span() {
+            let start = span.start.min(text.len());
+            let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = text[start..].find('\n').map_or(text.len(), |i| start + i);
+            diag.line = Some(text[..start].matches('\n').count() + 1);
+            diag.column = Some(text[line_start..start].chars().count() + 1);
+            diag.snippet = Some(
+                text[line_start..line_end]
+                    .trim_end_matches('\r')
+                    .to_string(),
+            );
+        }
+        diag
+    }
+}
+
+impl fmt::Display for TomlDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => {
+                write!(
+                    f,
+                    "{}:{}:{}: {}",
+                    self.path.display(),
+                    line,
+                    column,
+                    self.message
+                )?;
+                if let Some(snippet) = &self.snippet {
+                    let gutter = line.to_string();
+                    write!(f, "\n {} | {}", gutter, snippet)?;
+                    write!(
+                        f,
+                        "\n {} | {}^",
+                        " ".repeat(gutter.len()),
+                        " ".repeat(column - 1)
+                    )?;
+                }
+                Ok(())
+            }
+            _ => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Parse `text`, read from repo-relative `path`, as TOML, locating any error.
+fn parse_toml<T: serde::de::DeserializeOwned>(
+    path: &Path,
+    text: &str,
+) -> Result<T, OrchestratorError> {
+    toml::from_str(text).map_err(|e| OrchestratorError::Toml(TomlDiagnostic::new(path, text, &e)))
+}
+
+fn load_seal(fs: &dyn FragmentFs, path: &Path) -> io::Result<String> {
+    let bytes = fs.read(path)?;
+    let text = String::from_utf8(bytes).map_err(|_| invalid_utf8(path))?;
+    if let Some(idx) = text.find('=') {
+        Ok(text[idx + 1..].trim().to_string())
+    } else {
+        Ok(text.trim().to_string())
+    }
+}
+
+/// `--strict-seals`: a seal must be exactly `algorithm.hex_len()` lowercase
+/// hex digits. `seal` is the value `load_seal` read, so only surrounding
+/// whitespace (such as the file's trailing newline) has been dropped.
+fn check_strict_seal(
+    path: &Path,
+    seal: &str,
+    algorithm: SealAlgorithm,
+) -> Result<(), OrchestratorError> {
+    let reason = if seal.len() != algorithm.hex_len() {
+        format!(
+            "expected {} hex characters, found {}",
+            algorithm.hex_len(),
+            seal.len()
+        )
+    } else if let Some(c) = seal.chars().find(|c| !matches!(c, '0'..='9' | 'a'..='f')) {
+        format!("unexpected character {:?}; seals must be lowercase hex", c)
+    } else {
+        return Ok(());
+    };
+    Err(OrchestratorError::InvalidSeal {
+        path: path.to_path_buf(),
+        reason,
+    })
+}
+
+const DEFAULT_SPEC_PATH: &str = ".aln/compliance/COMPLIANCE_SPEC.aln";
+const SPEC_MANIFEST_PATH: &str = ".aln/compliance/SPEC_MANIFEST.aln";
+
+/// Repo-relative spec paths to validate: the entries of
+/// `SPEC_MANIFEST.aln` (`specs = [...]`) when present, otherwise the single
+/// root `COMPLIANCE_SPEC.aln`.
+fn spec_sources(repo_root: &Path) -> Result<Vec<String>, OrchestratorError> {
+    let manifest_path = repo_root.join(SPEC_MANIFEST_PATH);
+    if !manifest_path.exists() {
+        return Ok(vec![DEFAULT_SPEC_PATH.to_string()]);
+    }
+    let manifest: SpecManifest = parse_toml(
+        Path::new(SPEC_MANIFEST_PATH),
+        &fs::read_to_string(&manifest_path)?,
+    )?;
+    if manifest.specs.is_empty() {
+        return Err(OrchestratorError::Manifest(format!(
+            "{} lists no specs",
+            SPEC_MANIFEST_PATH
+        )));
+    }
+    let mut seen = HashSet::new();
+    for spec in &manifest.specs {
+        if !seen.insert(spec.as_str()) {
+            return Err(OrchestratorError::Manifest(format!(
+                "{} lists {} more than once",
+                SPEC_MANIFEST_PATH, spec
+            )));
+        }
+    }
+    Ok(manifest.specs)
+}
+
+/// Ceilings for the `[energy]` section, matching the vnode graph's
+/// `MAX_TOTAL_AUET`/`MAX_TOTAL_CSP` caps.
+const MAX_AUET_PER_DAY: u64 = 1_000_000_000_000;
+const MAX_CSP_PER_DAY: u64 = 1_000_000_000;
+
+/// Load and check one spec. With `path_base = "spec_dir"` the fragment
+/// paths are rewritten to be repo-relative, so everything downstream
+/// (hashing, `--changed-since`, `--coverage`) sees a single base.
+fn load_spec(repo_root: &Path, source: &str) -> Result<ComplianceSpec, OrchestratorError> {
+    let text = fs::read_to_string(repo_root.join(source))?;
+    let mut spec: ComplianceSpec = parse_toml(Path::new(source), &text)?;
+    if spec.path_base == PathBase::SpecDir {
+        let spec_dir = Path::new(source).parent().unwrap_or(Path::new(""));
+        for frag in &mut spec.fragments.items {
+            frag.path = rebase_rel_path(spec_dir, &frag.path);
+            frag.seal = rebase_rel_path(spec_dir, &frag.seal);
+        }
+    }
+    for (field, value, max) in [
+        (
+            "max_auet_per_day",
+            spec.energy.max_auet_per_day,
+            MAX_AUET_PER_DAY,
+        ),
+        (
+            "max_csp_per_day",
+            spec.energy.max_csp_per_day,
+            MAX_CSP_PER_DAY,
+        ),
+    ] {
+        if value == 0 || value > max {
+            return Err(OrchestratorError::InvalidEnergyBounds {
+                spec: source.to_string(),
+                field,
+                value,
+                max,
+            });
+        }
+    }
+    Ok(spec)
+}
+
+/// State for `--changed-since`: the files git reports as changed and the
+/// previous run's results, keyed by `(spec_source, fragment id)`.
+struct Incremental {
+    changed: HashSet<String>,
+    prior: HashMap<(String, String), FragmentResult>,
+}
+
+impl Incremental {
+    /// Build the incremental context, or `None` (meaning: validate everything)
+    /// when git can't produce a diff, no readable prior report exists, or a
+    /// spec in `specs` or the manifest changed.
+    fn load(
+        repo_root: &Path,
+        git_ref: &str,
+        specs: &[String],
+        prior_report: &Path,
+    ) -> Option<Self> {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(repo_root)
+            .args(["diff", "--name-only", "--relative", git_ref, "--"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            eprintln!(
+                "aln-orchestrator: git diff against {} failed; validating all fragments",
+                git_ref
+            );
+            return None;
+        }
+        let changed = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(normalize_rel_path)
+            .collect();
+
+        let text = fs::read_to_string(prior_report).ok()?;
+        let report: ValidationReport = serde_json::from_str(&text).ok()?;
+        Self::from_changes(changed, specs, report.fragments)
+    }
+
+    /// `None` when a spec or the manifest is among `changed`: a new hasher,
+    /// algorithm or `path_base` invalidates every cached result.
+    fn from_changes(
+        changed: HashSet<String>,
+        specs: &[String],
+        prior: Vec<FragmentResult>,
+    ) -> Option<Self> {
+        let spec_files = specs.iter().map(String::as_str).chain([SPEC_MANIFEST_PATH]);
+        if let Some(spec) = spec_files
+            .map(normalize_rel_path)
+            .find(|spec| changed.contains(spec))
+        {
+            eprintln!(
+                "aln-orchestrator: {} changed; validating all fragments",
+                spec
+            );
+            return None;
+        }
+        let prior = prior
+            .into_iter()
+            .map(|f| ((f.spec_source.clone(), f.id.clone()), f))
+            .collect();
+        Some(Self { changed, prior })
+    }
+
+    /// Reuse the prior result when neither the fragment nor its seal changed
+    /// and the fragment was previously ok.
+    fn cached_result(&self, spec_source: &str, frag: &FragmentSpec) -> Option<FragmentResult> {
+        if self.changed.contains(&normalize_rel_path(&frag.path))
+            || self.changed.contains(&normalize_rel_path(&frag.seal))
+        {
+            return None;
+        }
+        let prior = self
+            .prior
+            .get(&(spec_source.to_string(), frag.id.clone()))?;
+        if !prior.passed() || prior.algorithm != frag.algorithm {
+            return None;
+        }
+        Some(FragmentResult {
+            status: "ok_cached".into(),
+            ..prior.clone()
+        })
+    }
+}
+
+/// Validate every spec's fragments, reading fragment and seal files through
+/// `fs`. Specs, the prior report and `--coverage` walks use the local disk.
+fn validate_fragments<F: FragmentFs + Sync>(
+    fs: &F,
+    repo_root: &Path,
+    opts: &ValidateOptions,
+) -> Result<(ValidationReport, bool), OrchestratorError> {
+    let sources = spec_sources(repo_root)?;
+    let out_path = opts.report_path(repo_root);
+    // Read before the report is overwritten; the baseline is often the
+    // previous run's report at the same path.
+    let baseline = match &opts.compare {
+        Some(path) => {
+            let text = fs::read_to_string(path)?;
+            let report: ValidationReport = serde_json::from_str(&text).map_err(io::Error::from)?;
+            Some((path.display().to_string(), report))
+        }
+        None => None,
+    };
+    let incremental = opts
+        .changed_since
+        .as_deref()
+        .and_then(|git_ref| Incremental::load(repo_root, git_ref, &sources, &out_path));
+
+    // blueprint, version and energy bounds are taken from the first spec;
+    // the fragment list and `ok` span all of them.
+    let mut header = None;
+    let mut fragments = Vec::new();
+    let mut referenced = HashSet::new();
+    let mut pipeline_status = pipeline_ok();
+    let mut execution_order = Vec::new();
+    let mut fixed_seals = Vec::new();
+    let mut ok = true;
+    for source in &sources {
+        let spec = load_spec(repo_root, source)?;
+        let fragment_ids = spec.fragments.items.iter().map(|f| f.id.as_str()).collect();
+        let graph = &spec.orchestration.pipelines.graph;
+        match validate_pipeline_graph(graph, &fragment_ids) {
+            Ok(()) => execution_order.extend(pipeline_execution_order(graph)),
+            Err(e) => {
+                if pipeline_status == "ok" {
+                    pipeline_status = format!("{}: {}", source, e);
+                }
+                ok = false;
+            }
+        }
+        for frag in &spec.fragments.items {
+            referenced.insert(normalize_rel_path(&frag.path));
+            referenced.insert(normalize_rel_path(&frag.seal));
+        }
+        let (mut results, mut spec_ok) = check_fragments(
+            fs,
+            repo_root,
+            source,
+            &spec.fragments.items,
+            incremental.as_ref(),
+            opts,
+        )?;
+        if opts.fix_seals {
+            fixed_seals.extend(fix_seals(repo_root, &mut results)?);
+            spec_ok = results.iter().all(FragmentResult::passed);
+        }
+        fragments.extend(results);
+        ok &= spec_ok;
+        header.get_or_insert((spec.blueprint, spec.version, spec.energy));
+    }
+    let (blueprint, version, energy_bounds) =
+        header.expect("spec_sources returns at least one spec");
+
+    let coverage = match &opts.coverage {
+        Some(dir) => Some(coverage_section(repo_root, dir, &referenced)?),
+        None => None,
+    };
+    if opts.require_coverage && coverage.as_ref().is_some_and(|c| !c.uncovered.is_empty()) {
+        ok = false;
+    }
+    let energy_usage = match &opts.energy_ledger {
+        Some(path) => Some(energy::load_usage(path, &energy_bounds)?),
+        None => None,
+    };
+    if energy_usage.as_ref().is_some_and(|u| !u.passed()) {
+        ok = false;
+    }
+
+    let mut report = ValidationReport {
+        fragments,
+        blueprint,
+        version,
+        energy_bounds,
+        energy_usage,
+        coverage,
+        comparison: None,
+        execution_order: if pipeline_status == "ok" {
+            execution_order
+        } else {
+            Vec::new()
+        },
+        pipeline_status,
+        fixed_seals,
+    };
+    if let Some((path, baseline)) = &baseline {
+        report.comparison = Some(compare::diff_reports(path, baseline, &report));
+    }
+
+    write_atomic(
+        &out_path,
+        serde_json::to_string_pretty(&report).unwrap().as_bytes(),
+    )?;
+    Ok((report, ok))
+}
+
+/// Repo-relative path with `/` separators and no leading `./`, so spec
+/// entries and walked files compare equal.
+fn normalize_rel_path(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    path.trim_start_matches("./").to_string()
+}
+
+/// Join `path` onto the repo-relative directory `dir`, resolving `.` and
+/// `..` lexically. Absolute paths are returned unchanged.
+fn rebase_rel_path(dir: &Path, path: &str) -> String {
+    let path = normalize_rel_path(path);
+    if Path::new(&path).is_absolute() {
+        return path;
+    }
+    let mut parts: Vec<String> = Vec::new();
+    for component in dir.join(&path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if parts.last().is_some_and(|p| p != "..") => {
+                parts.pop();
+            }
+            other => parts.push(other.as_os_str().to_string_lossy().into_owned()),
+        }
+    }
+    parts.join("/")
+}
+
+/// Walk `dir` (relative to `repo_root`) and report every regular file that
+/// is not in `referenced`. `.git` directories are skipped; output is sorted.
+fn coverage_section(
+    repo_root: &Path,
+    dir: &Path,
+    referenced: &HashSet<String>,
+) -> io::Result<CoverageSection> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                if entry.file_name() != ".git" {
+                    walk(&path, files)?;
+                }
+            } else if file_type.is_file() {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    walk(&repo_root.join(dir), &mut files)?;
+    let mut uncovered: Vec<String> = files
+        .iter()
+        .filter_map(|f| f.strip_prefix(repo_root).ok())
+        .map(|rel| normalize_rel_path(&rel.to_string_lossy()))
+        .filter(|rel| !referenced.contains(rel))
+        .collect();
+    uncovered.sort();
+
+    Ok(CoverageSection {
+        root: normalize_rel_path(&dir.to_string_lossy()),
+        total_files: files.len(),
+        uncovered,
+    })
+}
+
+/// Line CI steps grep for to locate the report.
+fn report_marker(report_path: &Path) -> String {
+    format!("ALN_ORCHESTRATOR_REPORT={}", report_path.display())
+}
+
+/// Replace `path` with `contents` via a sibling temp file that is fsynced
+/// and then renamed over it, so readers (e.g. `notify`) see either the old
+/// file or the complete new one. On failure the temp file is removed and
+/// `path` is left untouched. Creates missing parents.
+pub(crate) fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    fs::create_dir_all(parent)?;
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "report path has no file name")
+    })?;
+    let tmp = parent.join(format!(
+        ".{}.tmp-{}",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+    let written = fs::File::create(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|()| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
+    }
+    // Persist the rename itself; not every platform can open a directory.
+    if let Ok(dir) = fs::File::open(parent) {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+/// Check every fragment of one spec, hashing on up to `opts.jobs` threads.
+/// Results keep the spec's order; the first error in that order wins.
+fn check_fragments<F: FragmentFs + Sync>(
+    fs: &F,
+    repo_root: &Path,
+    spec_source: &str,
+    fragments: &[FragmentSpec],
+    incremental: Option<&Incremental>,
+    opts: &ValidateOptions,
+) -> Result<(Vec<FragmentResult>, bool), OrchestratorError> {
+    let check = |frag: &FragmentSpec| match incremental
+        .and_then(|inc| inc.cached_result(spec_source, frag))
+    {
+        Some(cached) => Ok(cached),
+        None => check_fragment(fs, repo_root, spec_source, frag, opts.strict_seals),
+    };
+
+    let jobs = opts.jobs.clamp(1, fragments.len().max(1));
+    let checked: Vec<Result<FragmentResult, OrchestratorError>> = if jobs == 1 {
+        fragments.iter().map(check).collect()
+    } else {
+        let chunk = fragments.len().div_ceil(jobs);
+        std::thread::scope(|scope| {
+            let workers: Vec<_> = fragments
+                .chunks(chunk)
+                .map(|part| scope.spawn(move || part.iter().map(check).collect::<Vec<_>>()))
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|w| w.join().expect("fragment worker panicked"))
+                .collect()
+        })
+    };
+
+    let results = checked.into_iter().collect::<Result<Vec<_>, _>>()?;
+    let ok = results.iter().all(FragmentResult::passed);
+    Ok((results, ok))
+}
+
+fn check_fragment<F: FragmentFs>(
+    fs: &F,
+    repo_root: &Path,
+    spec_source: &str,
+    frag: &FragmentSpec,
+    strict_seals: bool,
+) -> Result<FragmentResult, OrchestratorError> {
+    let fpath = repo_root.join(&frag.path);
+    let spath = repo_root.join(&frag.seal);
+
+    let Some(algorithm) = SealAlgorithm::parse(&frag.algorithm) else {
+        return Ok(FragmentResult {
+            spec_source: spec_source.to_string(),
+            id: frag.id.clone(),
+            path: fpath.display().to_string(),
+            seal: spath.display().to_string(),
+            algorithm: frag.algorithm.clone(),
+            status: "unsupported_algorithm".into(),
+            expected: None,
+            actual: None,
+            detail: Some(format!(
+                "unknown seal algorithm {:?}; expected sha256, sha512 or blake3",
+                frag.algorithm
+            )),
+        });
+    };
+
+    if !fs.exists(&fpath) {
+        return Ok(FragmentResult {
+            spec_source: spec_source.to_string(),
+            id: frag.id.clone(),
+            path: fpath.display().to_string(),
+            seal: spath.display().to_string(),
+            algorithm: frag.algorithm.clone(),
+            status: "missing_fragment".into(),
+            expected: None,
+            actual: None,
+            detail: Some("fragment file not found".into()),
+        });
+    }
+
+    if !fs.exists(&spath) {
+        return Ok(FragmentResult {
+            spec_source: spec_source.to_string(),
+            id: frag.id.clone(),
+            path: fpath.display().to_string(),
+            seal: spath.display().to_string(),
+            algorithm: frag.algorithm.clone(),
+            status: "missing_seal".into(),
+            expected: None,
+            actual: None,
+            detail: Some("seal file not found".into()),
+        });
+    }
+
+    let actual = frag.hasher.hasher().hash(fs, &fpath, algorithm)?;
+    let expected = load_seal(fs, &spath)?;
+    let matches = if strict_seals {
+        check_strict_seal(&spath, &expected, algorithm)?;
+        actual == expected
+    } else {
+        actual.to_lowercase() == expected.to_lowercase()
+    };
+
+    let status = if matches { "ok" } else { "hash_mismatch" };
+    Ok(FragmentResult {
+        spec_source: spec_source.to_string(),
+        id: frag.id.clone(),
+        path: fpath.display().to_string(),
+        seal: spath.display().to_string(),
+        algorithm: frag.algorithm.clone(),
+        status: status.into(),
+        expected: Some(expected),
+        actual: Some(actual),
+        detail: None,
+    })
+}
+
+/// One seal whose contents differ from its fragment's current digest.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SealChange {
+    spec_source: String,
+    id: String,
+    /// Repo-relative seal path.
+    seal: String,
+    /// The digest the seal currently holds.
+    old: String,
+    new: String,
+}
+
+/// The rewrite `--fix` makes for `result`, if any. Only `hash_mismatch`
+/// fragments are resealed; missing fragments and seals are left for a human.
+/// `--plan-seals` lists exactly these, so the plan matches what `--fix` does.
+fn seal_rewrite(repo_root: &Path, result: &FragmentResult) -> Option<SealChange> {
+    if result.status != "hash_mismatch" {
+        return None;
+    }
+    let seal = Path::new(&result.seal);
+    Some(SealChange {
+        spec_source: result.spec_source.clone(),
+        id: result.id.clone(),
+        seal: normalize_rel_path(
+            &seal
+                .strip_prefix(repo_root)
+                .unwrap_or(seal)
+                .display()
+                .to_string(),
+        ),
+        old: result.expected.clone()?,
+        new: result.actual.clone()?,
+    })
+}
+
+/// Seals `--fix` would rewrite given the same options, in spec order.
+/// Reads only.
+fn plan_seals<F: FragmentFs + Sync>(
+    fs: &F,
+    repo_root: &Path,
+    opts: &ValidateOptions,
+) -> Result<Vec<SealChange>, OrchestratorError> {
+    let mut changes = Vec::new();
+    for source in spec_sources(repo_root)? {
+        let spec = load_spec(repo_root, &source)?;
+        let (results, _) =
+            check_fragments(fs, repo_root, &source, &spec.fragments.items, None, opts)?;
+        changes.extend(results.iter().filter_map(|r| seal_rewrite(repo_root, r)));
+    }
+    Ok(changes)
+}
+
+/// `--fix`: rewrite each seal `seal_rewrite` selects from `results` as
+/// `<algorithm> = <digest>` (the format `load_seal` reads) and mark the
+/// fragment `ok`. Writes go straight to disk, whatever `FragmentFs` hashed
+/// the fragments. Returns the rewritten seals in `results` order.
+fn fix_seals(
+    repo_root: &Path,
+    results: &mut [FragmentResult],
+) -> Result<Vec<SealChange>, OrchestratorError> {
+    let mut fixed = Vec::new();
+    for frag in results.iter_mut() {
+        let Some(change) = seal_rewrite(repo_root, frag) else {
+            continue;
+        };
+        write_atomic(
+            Path::new(&frag.seal),
+            format!("{} = {}\n", frag.algorithm, change.new).as_bytes(),
+        )?;
+        frag.expected = Some(change.new.clone());
+        frag.status = "ok".into();
+        frag.detail = Some("seal rewritten by --fix".into());
+        fixed.push(change);
+    }
+    Ok(fixed)
+}
+
+/// Repo root for CI runs: `$GITHUB_WORKSPACE`, else the current directory.
+pub fn repo_root_from_env() -> PathBuf {
+    pick_repo_root(
+        None,
+        std::env::var_os("GITHUB_WORKSPACE").map(PathBuf::from),
+        || std::env::current_dir().unwrap(),
+    )
+}
+
+/// Repo root to validate, in precedence order: `flag` (`--repo-root`), then
+/// `$GITHUB_WORKSPACE`, then the current directory. The chosen directory must
+/// exist and contain `.aln/compliance/`.
+pub fn resolve_repo_root(flag: Option<&Path>) -> Result<PathBuf, OrchestratorError> {
+    let root = pick_repo_root(
+        flag.map(Path::to_path_buf),
+        std::env::var_os("GITHUB_WORKSPACE").map(PathBuf::from),
+        || std::env::current_dir().unwrap(),
+    );
+    check_repo_root(&root)?;
+    Ok(root)
+}
+
+fn pick_repo_root(
+    flag: Option<PathBuf>,
+    env: Option<PathBuf>,
+    cwd: impl FnOnce() -> PathBuf,
+) -> PathBuf {
+    flag.or(env).unwrap_or_else(cwd)
+}
+
+fn check_repo_root(root: &Path) -> Result<(), OrchestratorError> {
+    let reason = if !root.is_dir() {
+        "directory does not exist"
+    } else if !root.join(".aln/compliance").is_dir() {
+        "no .aln/compliance/ directory"
+    } else {
+        return Ok(());
+    };
+    Err(OrchestratorError::RepoRoot {
+        path: root.to_path_buf(),
+        reason,
+    })
+}
+
+/// Validate (or, with `--verify`, check the signature of) the report for
+/// `repo_root` and print the outcome. Returns whether every fragment passed.
+pub fn run(
+    repo_root: &Path,
+    args: &ValidateArgs,
+    format: OutputFormat,
+) -> Result<bool, OrchestratorError> {
+    let opts = ValidateOptions {
+        changed_since: args.changed_since.clone(),
+        report_out: args.report_out.clone(),
+        coverage: args.coverage.clone(),
+        compare: args.compare.clone(),
+        energy_ledger: args.energy_ledger.clone(),
+        strict_seals: args.strict_seals || args.strict,
+        require_coverage: args.strict,
+        jobs: usize::from(args.jobs),
+        fix_seals: args.fix,
+    };
+    let report_path = opts.report_path(repo_root);
+
+    if let Some(pubkey) = &args.verify {
+        signing::verify_report(&report_path, pubkey)?;
+        println!("ALN_ORCHESTRATOR_SIGNATURE=verified");
+        return Ok(true);
+    }
+
+    if args.plan_seals {
+        let plan = plan_seals(&RealFs, repo_root, &opts)?;
+        match format {
+            OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string_pretty(&plan).map_err(io::Error::from)?
+            ),
+            OutputFormat::Text => {
+                for change in &plan {
+                    println!(
+                        "{} [{}]: {} -> {}",
+                        change.seal, change.id, change.old, change.new
+                    );
+                }
+                println!("{} seal(s) would be rewritten", plan.len());
+            }
+        }
+        return Ok(true);
+    }
+
+    let (report, ok) = validate_fragments(&RealFs, repo_root, &opts)?;
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(io::Error::from)?
+        ),
+        OutputFormat::Text => print_text_report(&report_path, &report),
+    }
+
+    if let Some(html_path) = &args.html {
+        write_atomic(html_path, html::render_report(&report).as_bytes())?;
+        if format == OutputFormat::Text {
+            println!("ALN_ORCHESTRATOR_HTML={}", html_path.display());
+        }
+    }
+
+    if let Some(key) = &args.sign {
+        let sig_path = signing::sign_report(&report_path, key)?;
+        println!("ALN_ORCHESTRATOR_SIGNATURE={}", sig_path.display());
+    }
+
+    match &report.comparison {
+        // Failures already in the baseline are tolerated, but not the ones
+        // `--fix` and `--strict` were asked to rule out.
+        Some(diff) => Ok(!diff.has_regressions()
+            && report.pipeline_status == "ok"
+            && report
+                .energy_usage
+                .as_ref()
+                .is_none_or(energy::EnergyUsage::passed)
+            && (!args.fix || report.fragments.iter().all(FragmentResult::passed))
+            && (!args.strict
+                || report
+                    .coverage
+                    .as_ref()
+                    .is_none_or(|c| c.uncovered.is_empty()))),
+        None => Ok(ok),
+    }
+}
+
+/// One root's outcome from `validate_many`.
+pub type RootOutcome = (PathBuf, Result<(ValidationReport, bool), OrchestratorError>);
+
+/// Validate each repo checkout in `roots` with default options, writing each
+/// root's `compliance_report.json` as a plain run would. A failing root does
+/// not stop the others; see `matrix_passed` for the combined verdict.
+pub fn validate_many(roots: &[PathBuf]) -> Vec<RootOutcome> {
+    let opts = ValidateOptions::default();
+    roots
+        .iter()
+        .map(|root| {
+            let outcome =
+                check_repo_root(root).and_then(|()| validate_fragments(&RealFs, root, &opts));
+            (root.clone(), outcome)
+        })
+        .collect()
+}
+
+/// Whether every root in a `validate_many` matrix validated and passed.
+pub fn matrix_passed(outcomes: &[RootOutcome]) -> bool {
+    outcomes
+        .iter()
+        .all(|(_, outcome)| matches!(outcome, Ok((_, true))))
+}
+
+fn print_text_report(report_path: &Path, report: &ValidationReport) {
+    println!("{}", report_marker(report_path));
+    if report.pipeline_status != "ok" {
+        println!("pipeline: {}", report.pipeline_status);
+    }
+    for change in &report.fixed_seals {
+        println!(
+            "fixed {} [{}]: {} -> {}",
+            change.seal, change.id, change.old, change.new
+        );
+    }
+    if !report.execution_order.is_empty() {
+        println!("pipeline order: {}", report.execution_order.join(" -> "));
+    }
+    for frag in &report.fragments {
+        println!("{} [{}]: {}", frag.path, frag.id, frag.status);
+    }
+    if let Some(coverage) = &report.coverage {
+        println!(
+            "coverage {}: {}/{} files sealed",
+            coverage.root,
+            coverage.total_files - coverage.uncovered.len(),
+            coverage.total_files
+        );
+        for file in &coverage.uncovered {
+            println!("{}: uncovered", file);
+        }
+    }
+    if let Some(usage) = &report.energy_usage {
+        println!(
+            "energy {}: {} (peak {} / {} AU.ET, {} / {} CSP per day)",
+            usage.ledger,
+            usage.status,
+            usage.peak_auet_per_day,
+            report.energy_bounds.max_auet_per_day,
+            usage.peak_csp_per_day,
+            report.energy_bounds.max_csp_per_day
+        );
+        for day in usage.days.iter().filter(|d| d.status != "ok") {
+            println!(
+                "{}: {} ({} AU.ET, {} CSP)",
+                day.day, day.status, day.auet, day.csp
+            );
+        }
+    }
+    if let Some(diff) = &report.comparison {
+        println!(
+            "compare {}: {} regressed, {} changed, {} added, {} removed",
+            diff.baseline,
+            diff.regressed.len(),
+            diff.changed.len(),
+            diff.added.len(),
+            diff.removed.len()
+        );
+        for (kind, changes) in [
+            ("regressed", &diff.regressed),
+            ("changed", &diff.changed),
+            ("added", &diff.added),
+            ("removed", &diff.removed),
+        ] {
+            for change in changes {
+                println!(
+                    "{} [{}]: {} {} -> {}",
+                    change.spec_source,
+                    change.id,
+                    kind,
+                    change.before.as_deref().unwrap_or("-"),
+                    change.after.as_deref().unwrap_or("-")
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    pub(crate) fn scratch_dir(tag: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("aln-orchestrator-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    pub(crate) fn fragment_result(id: &str, status: &str) -> FragmentResult {
+        FragmentResult {
+            spec_source: DEFAULT_SPEC_PATH.into(),
+            id: id.into(),
+            path: format!("{}.txt", id),
+            seal: format!("{}.txt.sha256", id),
+            algorithm: default_algorithm(),
+            status: status.into(),
+            expected: None,
+            actual: None,
+            detail: None,
+        }
+    }
+
+    pub(crate) fn report_with(fragments: Vec<FragmentResult>) -> ValidationReport {
+        ValidationReport {
+            fragments,
+            blueprint: "TEST_BLUEPRINT".into(),
+            version: "1.0.0".into(),
+            energy_bounds: EnergySection {
+                max_auet_per_day: 1000,
+                max_csp_per_day: 10,
+            },
+            energy_usage: None,
+            coverage: None,
+            comparison: None,
+            pipeline_status: pipeline_ok(),
+            execution_order: Vec::new(),
+            fixed_seals: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn normalized_hasher_ignores_crlf_and_trailing_whitespace() {
+        let dir = scratch_dir("normalized-hasher");
+        let lf = dir.join("lf.txt");
+        let crlf = dir.join("crlf.txt");
+        fs::write(&lf, "alpha\nbeta\n").unwrap();
+        fs::write(&crlf, "alpha  \r\nbeta\r\n").unwrap();
+
+        let hasher = HasherKind::NormalizedText.hasher();
+        assert_eq!(
+            hasher.hash(&RealFs, &lf, SealAlgorithm::Sha256).unwrap(),
+            hasher.hash(&RealFs, &crlf, SealAlgorithm::Sha256).unwrap()
+        );
+
+        let raw = HasherKind::Raw.hasher();
+        assert_ne!(
+            raw.hash(&RealFs, &lf, SealAlgorithm::Sha256).unwrap(),
+            raw.hash(&RealFs, &crlf, SealAlgorithm::Sha256).unwrap()
+        );
+    }
+
+    fn fragment(id: &str, path: &str, seal: &str) -> FragmentSpec {
+        FragmentSpec {
+            id: id.into(),
+            path: path.into(),
+            seal: seal.into(),
+            hasher: HasherKind::Raw,
+            algorithm: default_algorithm(),
+        }
+    }
+
+    fn sha256_file(fs: &dyn FragmentFs, path: &Path) -> io::Result<String> {
+        HasherKind::Raw
+            .hasher()
+            .hash(fs, path, SealAlgorithm::Sha256)
+    }
+
+    fn write_sealed(dir: &Path, name: &str, content: &str) {
+        fs::write(dir.join(name), content).unwrap();
+        let digest = sha256_file(&RealFs, &dir.join(name)).unwrap();
+        fs::write(
+            dir.join(format!("{}.sha256", name)),
+            format!("sha256 = {}\n", digest),
+        )
+        .unwrap();
+    }
+
+    /// Write a minimal `.aln/compliance/COMPLIANCE_SPEC.aln` with the given
+    /// `[fragments]` item lines.
+    pub(crate) fn write_spec(repo_root: &Path, items: &[&str]) {
+        write_spec_at(repo_root, DEFAULT_SPEC_PATH, items);
+    }
+
+    fn write_spec_at(repo_root: &Path, source: &str, items: &[&str]) {
+        write_spec_with_energy(repo_root, source, items, 1000, 10);
+    }
+
+    fn write_spec_with_energy(repo_root: &Path, source: &str, items: &[&str], auet: u64, csp: u64) {
+        let path = repo_root.join(source);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let spec = format!(
+            r#"version = "1.0.0"
+language = "aln"
+blueprint = "TEST_BLUEPRINT"
+
+[fragments]
+items = [
+{}
+]
+
+[orchestration]
+contracts = []
+
+[orchestration.pipelines]
+graph = []
+
+[energy]
+max_auet_per_day = {}
+max_csp_per_day = {}
+"#,
+            items.join(",\n"),
+            auet,
+            csp
+        );
+        fs::write(path, spec).unwrap();
+    }
+
+    #[test]
+    fn seals_are_checked_with_the_declared_algorithm() {
+        let dir = scratch_dir("algorithms");
+        for (name, digest) in [
+            ("default.txt", hex::encode(Sha256::digest(b"alpha"))),
+            ("wide.txt", hex::encode(Sha512::digest(b"alpha"))),
+            ("fast.txt", blake3::hash(b"alpha").to_hex().to_string()),
+        ] {
+            fs::write(dir.join(name), "alpha").unwrap();
+            fs::write(
+                dir.join(format!("{}.seal", name)),
+                format!("digest = {}\n", digest),
+            )
+            .unwrap();
+        }
+        write_spec(
+            &dir,
+            &[
+                r#"{ id = "default", path = "default.txt", seal = "default.txt.seal" }"#,
+                r#"{ id = "wide", path = "wide.txt", seal = "wide.txt.seal", algorithm = "sha512" }"#,
+                r#"{ id = "fast", path = "fast.txt", seal = "fast.txt.seal", algorithm = "blake3" }"#,
+                r#"{ id = "legacy", path = "default.txt", seal = "default.txt.seal", algorithm = "md5" }"#,
+            ],
+        );
+        let strict = ValidateOptions {
+            strict_seals: true,
+            ..ValidateOptions::default()
+        };
+
+        let (report, ok) = validate_fragments(&RealFs, &dir, &strict).unwrap();
+        assert!(!ok);
+        let summary: Vec<(&str, &str, &str)> = report
+            .fragments
+            .iter()
+            .map(|f| (f.id.as_str(), f.algorithm.as_str(), f.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("default", "sha256", "ok"),
+                ("wide", "sha512", "ok"),
+                ("fast", "blake3", "ok"),
+                ("legacy", "md5", "unsupported_algorithm"),
+            ]
+        );
+        assert!(report.fragments[3]
+            .detail
+            .as_deref()
+            .unwrap()
+            .contains("md5"));
+
+        // A SHA-256 seal on a fragment declared as SHA-512 is a mismatch.
+        fs::copy(dir.join("default.txt.seal"), dir.join("wide.txt.seal")).unwrap();
+        let (report, _) = validate_fragments(&RealFs, &dir, &ValidateOptions::default()).unwrap();
+        assert_eq!(report.fragments[1].status, "hash_mismatch");
+
+        // Reports from before this field existed load as sha256.
+        let mut legacy = serde_json::to_value(&report).unwrap();
+        legacy["fragments"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("algorithm");
+        let legacy: ValidationReport = serde_json::from_value(legacy).unwrap();
+        assert_eq!(legacy.fragments[0].algorithm, "sha256");
+    }
+
+    fn pipeline(edges: &[(&str, &[&str])]) -> Vec<PipelineNode> {
+        edges
+            .iter()
+            .map(|(id, requires)| PipelineNode {
+                id: id.to_string(),
+                requires: requires.iter().map(|r| r.to_string()).collect(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pipeline_graph_must_be_a_dag_of_known_nodes() {
+        let dag = pipeline(&[
+            ("build", &[]),
+            ("test", &["build"]),
+            ("deploy", &["build", "test"]),
+        ]);
+        assert_eq!(validate_pipeline_graph(&dag, &HashSet::new()), Ok(()));
+
+        let cycle = pipeline(&[
+            ("build", &["deploy"]),
+            ("test", &["build"]),
+            ("deploy", &["test"]),
+        ]);
+        assert_eq!(
+            validate_pipeline_graph(&cycle, &HashSet::new()),
+            Err(PipelineGraphError::Cycle(vec![
+                "build".into(),
+                "deploy".into(),
+                "test".into(),
+                "build".into()
+            ]))
+        );
+
+        let selfish = pipeline(&[("build", &[]), ("lint", &["lint"])]);
+        assert_eq!(
+            validate_pipeline_graph(&selfish, &HashSet::new())
+                .unwrap_err()
+                .to_string(),
+            "pipeline graph has a cycle: lint -> lint"
+        );
+
+        // Nodes may require fragments by id; anything else must be a node.
+        let dangling = pipeline(&[("deploy", &["spec", "build"])]);
+        assert_eq!(
+            validate_pipeline_graph(&dangling, &HashSet::from(["spec", "build"])),
+            Ok(())
+        );
+        assert_eq!(
+            validate_pipeline_graph(&dangling, &HashSet::from(["spec"])),
+            Err(PipelineGraphError::UnknownDependency {
+                node: "deploy".into(),
+                missing: "build".into()
+            })
+        );
+    }
+
+    #[test]
+    fn execution_order_breaks_ties_by_id() {
+        let graph = pipeline(&[
+            ("deploy", &["test", "lint"]),
+            ("test", &["build"]),
+            ("lint", &["spec"]),
+            ("build", &["spec"]),
+            ("audit", &[]),
+        ]);
+        assert_eq!(
+            validate_pipeline_graph(&graph, &HashSet::from(["spec"])),
+            Ok(())
+        );
+        assert_eq!(
+            pipeline_execution_order(&graph),
+            ["audit", "build", "lint", "test", "deploy"]
+        );
+    }
+
+    #[test]
+    fn pipeline_cycle_fails_the_report() {
+        let dir = scratch_dir("pipeline-cycle");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+        let (report, ok) = validate_fragments(&RealFs, &dir, &ValidateOptions::default()).unwrap();
+        assert!(ok && report.passed());
+        assert_eq!(report.pipeline_status, "ok");
+        assert!(report.execution_order.is_empty());
+
+        let spec_path = dir.join(DEFAULT_SPEC_PATH);
+        let original = fs::read_to_string(&spec_path).unwrap();
+        let dag = original.replace(
+            "graph = []",
+            r#"graph = [{ id = "z", requires = ["a"] }, { id = "b", requires = ["z"] }]"#,
+        );
+        fs::write(&spec_path, dag).unwrap();
+        let (report, ok) = validate_fragments(&RealFs, &dir, &ValidateOptions::default()).unwrap();
+        assert!(ok && report.passed());
+        assert_eq!(report.execution_order, ["z", "b"]);
+
+        let spec = original.replace(
+            "graph = []",
+            r#"graph = [{ id = "a", requires = ["b"] }, { id = "b", requires = ["a"] }]"#,
+        );
+        fs::write(&spec_path, spec).unwrap();
+        let (report, ok) = validate_fragments(&RealFs, &dir, &ValidateOptions::default()).unwrap();
+        assert!(!ok && !report.passed());
+        assert_eq!(report.fragments[0].status, "ok");
+        assert_eq!(
+            report.pipeline_status,
+            format!(
+                "{}: pipeline graph has a cycle: a -> b -> a",
+                DEFAULT_SPEC_PATH
+            )
+        );
+        assert!(report.execution_order.is_empty());
+    }
+
+    #[test]
+    fn plan_seals_lists_rewrites_without_touching_disk() {
+        let dir = scratch_dir("plan-seals");
+        write_sealed(&dir, "ok.txt", "unchanged");
+        write_sealed(&dir, "edited.txt", "before");
+        fs::write(dir.join("edited.txt"), "after").unwrap();
+        fs::write(dir.join("unsealed.txt"), "new").unwrap();
+        write_sealed(&dir, "upper.txt", "shouting");
+        let upper = fs::read_to_string(dir.join("upper.txt.sha256")).unwrap();
+        fs::write(dir.join("upper.txt.sha256"), upper.to_uppercase()).unwrap();
+        write_spec(
+            &dir,
+            &[
+                r#"{ id = "ok", path = "ok.txt", seal = "ok.txt.sha256" }"#,
+                r#"{ id = "edited", path = "edited.txt", seal = "edited.txt.sha256" }"#,
+                r#"{ id = "unsealed", path = "unsealed.txt", seal = "unsealed.txt.sha256" }"#,
+                r#"{ id = "upper", path = "upper.txt", seal = "upper.txt.sha256" }"#,
+                r#"{ id = "gone", path = "gone.txt", seal = "gone.txt.sha256" }"#,
+            ],
+        );
+        let edited_seal = fs::read_to_string(dir.join("edited.txt.sha256")).unwrap();
+
+        // Only what --fix would rewrite: not the missing seal, and not the
+        // case-only difference that lenient checking already accepts.
+        let opts = ValidateOptions::default();
+        let plan = plan_seals(&RealFs, &dir, &opts).unwrap();
+        let ids: Vec<&str> = plan.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, ["edited"]);
+        let after = sha256_file(&RealFs, &dir.join("edited.txt")).unwrap();
+        assert_eq!(plan[0].seal, "edited.txt.sha256");
+        assert_eq!(
+            Some(plan[0].old.as_str()),
+            edited_seal.trim().strip_prefix("sha256 = ")
+        );
+        assert_eq!(plan[0].new, after);
+
+        let args = ValidateArgs {
+            plan_seals: true,
+            ..ValidateArgs::default()
+        };
+        assert!(run(&dir, &args, OutputFormat::Json).unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("edited.txt.sha256")).unwrap(),
+            edited_seal
+        );
+        assert!(!dir.join("unsealed.txt.sha256").exists());
+        assert!(!dir.join("compliance_report.json").exists());
+
+        let fix = ValidateOptions {
+            fix_seals: true,
+            ..opts
+        };
+        let (report, _) = validate_fragments(&RealFs, &dir, &fix).unwrap();
+        assert_eq!(report.fixed_seals, plan);
+    }
+
+    #[test]
+    fn fix_rewrites_mismatched_seals_but_not_other_failures() {
+        let dir = scratch_dir("fix-seals");
+        write_sealed(&dir, "ok.txt", "unchanged");
+        write_sealed(&dir, "edited.txt", "before");
+        fs::write(dir.join("edited.txt"), "after").unwrap();
+        write_spec(
+            &dir,
+            &[
+                r#"{ id = "ok", path = "ok.txt", seal = "ok.txt.sha256" }"#,
+                r#"{ id = "edited", path = "edited.txt", seal = "edited.txt.sha256" }"#,
+                r#"{ id = "gone", path = "gone.txt", seal = "gone.txt.sha256" }"#,
+            ],
+        );
+        let ok_seal = fs::read_to_string(dir.join("ok.txt.sha256")).unwrap();
+        let opts = ValidateOptions {
+            fix_seals: true,
+            ..ValidateOptions::default()
+        };
+
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(!ok, "the missing fragment still fails the run");
+        let after = sha256_file(&RealFs, &dir.join("edited.txt")).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("edited.txt.sha256")).unwrap(),
+            format!("sha256 = {}\n", after)
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("ok.txt.sha256")).unwrap(),
+            ok_seal
+        );
+        let statuses: Vec<&str> = report.fragments.iter().map(|f| f.status.as_str()).collect();
+        assert_eq!(statuses, ["ok", "ok", "missing_fragment"]);
+        assert_eq!(report.fixed_seals.len(), 1);
+        assert_eq!(report.fixed_seals[0].seal, "edited.txt.sha256");
+        assert_eq!(report.fixed_seals[0].new, after);
+
+        write_sealed(&dir, "gone.txt", "back");
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(ok && report.passed());
+        assert!(report.fixed_seals.is_empty());
+    }
+
+    #[test]
+    fn compare_mode_still_fails_on_what_fix_and_strict_rule_out() {
+        let dir = scratch_dir("compare-fix-strict");
+        write_sealed(&dir, "edited.txt", "before");
+        fs::write(dir.join("edited.txt"), "after").unwrap();
+        fs::write(dir.join("stray.txt"), "unsealed").unwrap();
+        write_spec(
+            &dir,
+            &[
+                r#"{ id = "edited", path = "edited.txt", seal = "edited.txt.sha256" }"#,
+                r#"{ id = "gone", path = "gone.txt", seal = "gone.txt.sha256" }"#,
+            ],
+        );
+        let baseline = dir.join("baseline.json");
+        let args = ValidateArgs {
+            jobs: 1,
+            report_out: Some(baseline.clone()),
+            ..ValidateArgs::default()
+        };
+        assert!(!run(&dir, &args, OutputFormat::Json).unwrap());
+
+        let compare = |fix, strict| ValidateArgs {
+            jobs: 1,
+            compare: Some(baseline.clone()),
+            coverage: Some(PathBuf::from(".")),
+            fix,
+            strict,
+            ..ValidateArgs::default()
+        };
+        // `gone` was already missing in the baseline, so plain compare passes.
+        assert!(run(&dir, &compare(false, false), OutputFormat::Json).unwrap());
+        assert!(!run(&dir, &compare(true, false), OutputFormat::Json).unwrap());
+
+        write_sealed(&dir, "gone.txt", "back");
+        assert!(run(&dir, &compare(true, false), OutputFormat::Json).unwrap());
+        // `stray.txt` is not covered by any seal.
+        assert!(!run(&dir, &compare(false, true), OutputFormat::Json).unwrap());
+    }
+
+    #[test]
+    fn validate_many_reports_each_root_and_fails_the_matrix() {
+        let clean = scratch_dir("matrix-clean");
+        write_sealed(&clean, "a.txt", "alpha");
+        write_spec(
+            &clean,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+        let broken = scratch_dir("matrix-broken");
+        write_sealed(&broken, "a.txt", "alpha");
+        fs::write(broken.join("a.txt"), "tampered").unwrap();
+        write_spec(
+            &broken,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+
+        let outcomes = validate_many(&[clean.clone(), broken.clone()]);
+        assert_eq!(outcomes.len(), 2);
+        assert_eq!(outcomes[0].0, clean);
+        let (report, ok) = outcomes[0].1.as_ref().unwrap();
+        assert!(*ok && report.passed());
+        let (report, ok) = outcomes[1].1.as_ref().unwrap();
+        assert!(!*ok);
+        assert_eq!(report.fragments[0].status, "hash_mismatch");
+        assert!(broken.join("compliance_report.json").is_file());
+        assert!(!matrix_passed(&outcomes));
+        assert!(matrix_passed(&outcomes[..1]));
+
+        let missing = clean.join("no-such-checkout");
+        let outcomes = validate_many(&[clean.clone(), missing]);
+        assert!(matches!(
+            outcomes[1].1,
+            Err(OrchestratorError::RepoRoot { .. })
+        ));
+        assert!(!matrix_passed(&outcomes));
+    }
+
+    #[test]
+    fn write_atomic_leaves_no_partial_file_on_error() {
+        let dir = scratch_dir("atomic");
+        let report = dir.join("compliance_report.json");
+        write_atomic(&report, b"{\"first\": true}").unwrap();
+        write_atomic(&report, b"{\"second\": true}").unwrap();
+        assert_eq!(fs::read_to_string(&report).unwrap(), "{\"second\": true}");
+
+        // A non-empty directory at the target makes the final rename fail
+        // after the temp file has been fully written.
+        let blocked = dir.join("blocked.json");
+        fs::create_dir_all(blocked.join("occupied")).unwrap();
+        assert!(write_atomic(&blocked, b"{}").is_err());
+        assert!(blocked.join("occupied").is_dir());
+
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["blocked.json", "compliance_report.json"]);
+    }
+
+    #[test]
+    fn strict_seals_reject_mixed_case_that_lenient_mode_accepts() {
+        let dir = scratch_dir("strict-seals");
+        write_sealed(&dir, "a.txt", "alpha");
+        let seal_path = dir.join("a.txt.sha256");
+        let digest = load_seal(&RealFs, &seal_path).unwrap();
+        let mixed = format!("{}{}", digest[..32].to_uppercase(), &digest[32..]);
+        fs::write(&seal_path, format!("sha256 = {}\n", mixed)).unwrap();
+        let frags = [fragment("a", "a.txt", "a.txt.sha256")];
+        let strict = ValidateOptions {
+            strict_seals: true,
+            ..ValidateOptions::default()
+        };
+
+        let (results, ok) = check_fragments(
+            &RealFs,
+            &dir,
+            DEFAULT_SPEC_PATH,
+            &frags,
+            None,
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(ok);
+        assert_eq!(results[0].status, "ok");
+
+        match check_fragments(&RealFs, &dir, DEFAULT_SPEC_PATH, &frags, None, &strict) {
+            Err(OrchestratorError::InvalidSeal { path, reason }) => {
+                assert_eq!(path, seal_path);
+                assert!(reason.contains("lowercase hex"), "{}", reason);
+            }
+            other => panic!("expected InvalidSeal, got {:?}", other.map(|(_, ok)| ok)),
+        }
+
+        fs::write(
+            &seal_path,
+            format!("sha256 = {} {}\n", &digest[..32], &digest[32..]),
+        )
+        .unwrap();
+        assert!(matches!(
+            check_fragments(&RealFs, &dir, DEFAULT_SPEC_PATH, &frags, None, &strict),
+            Err(OrchestratorError::InvalidSeal { .. })
+        ));
+
+        fs::write(&seal_path, format!("sha256 = {}\n", digest)).unwrap();
+        let (_, ok) =
+            check_fragments(&RealFs, &dir, DEFAULT_SPEC_PATH, &frags, None, &strict).unwrap();
+        assert!(ok);
+    }
+
+    #[test]
+    fn report_out_writes_to_custom_path() {
+        let dir = scratch_dir("report-out");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+
+        let custom = dir.join("reports/nested/out.json");
+        let opts = ValidateOptions {
+            report_out: Some(custom.clone()),
+            ..ValidateOptions::default()
+        };
+        let (_, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(ok);
+        assert!(custom.exists());
+        assert!(!dir.join("compliance_report.json").exists());
+        assert_eq!(
+            report_marker(&opts.report_path(&dir)),
+            format!("ALN_ORCHESTRATOR_REPORT={}", custom.display())
+        );
+
+        let written: ValidationReport =
+            serde_json::from_str(&fs::read_to_string(&custom).unwrap()).unwrap();
+        assert_eq!(written.fragments[0].status, "ok");
+    }
+
+    #[test]
+    fn changed_since_only_rehashes_changed_fragments() {
+        let dir = scratch_dir("changed-since");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_sealed(&dir, "b.txt", "beta");
+        let frags = vec![
+            fragment("a", "a.txt", "a.txt.sha256"),
+            fragment("b", "b.txt", "b.txt.sha256"),
+        ];
+        let (prior, ok) = check_fragments(
+            &RealFs,
+            &dir,
+            DEFAULT_SPEC_PATH,
+            &frags,
+            None,
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(ok);
+
+        // Both files drift, but git only reports `a.txt` as changed, so `b`
+        // must come from the prior report without being re-hashed.
+        fs::write(dir.join("a.txt"), "alpha v2").unwrap();
+        fs::write(dir.join("b.txt"), "beta v2").unwrap();
+        let incremental = Incremental {
+            changed: HashSet::from(["a.txt".to_string()]),
+            prior: prior
+                .iter()
+                .map(|f| ((f.spec_source.clone(), f.id.clone()), f.clone()))
+                .collect(),
+        };
+
+        let (results, ok) = check_fragments(
+            &RealFs,
+            &dir,
+            DEFAULT_SPEC_PATH,
+            &frags,
+            Some(&incremental),
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(!ok);
+        assert_eq!(results[0].status, "hash_mismatch");
+        assert_eq!(results[1].status, "ok_cached");
+
+        // A spec entry spelled `./a.txt` is still the `a.txt` git reports.
+        let dotted = vec![fragment("a", "./a.txt", "./a.txt.sha256")];
+        let (results, ok) = check_fragments(
+            &RealFs,
+            &dir,
+            DEFAULT_SPEC_PATH,
+            &dotted,
+            Some(&incremental),
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(!ok);
+        assert_eq!(results[0].status, "hash_mismatch");
+
+        // Editing the spec itself discards the cache altogether.
+        let specs = [DEFAULT_SPEC_PATH.to_string()];
+        let spec_changed = HashSet::from([DEFAULT_SPEC_PATH.to_string()]);
+        assert!(Incremental::from_changes(spec_changed, &specs, prior.clone()).is_none());
+        let manifest_changed = HashSet::from([SPEC_MANIFEST_PATH.to_string()]);
+        assert!(Incremental::from_changes(manifest_changed, &specs, prior.clone()).is_none());
+        assert!(Incremental::from_changes(HashSet::new(), &specs, prior).is_some());
+    }
+
+    #[test]
+    fn hasher_defaults_to_raw_when_omitted() {
+        let frag: FragmentSpec =
+            toml::from_str("id = \"a\"\npath = \"a.txt\"\nseal = \"a.sha256\"").unwrap();
+        assert_eq!(frag.hasher, HasherKind::Raw);
+
+        let frag: FragmentSpec = toml::from_str(
+            "id = \"a\"\npath = \"a.txt\"\nseal = \"a.sha256\"\nhasher = \"normalized_text\"",
+        )
+        .unwrap();
+        assert_eq!(frag.hasher, HasherKind::NormalizedText);
+    }
+
+    #[test]
+    fn manifest_combines_specs_and_ands_their_status() {
+        let dir = scratch_dir("spec-manifest");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_sealed(&dir, "b.txt", "beta");
+        fs::write(dir.join("b.txt"), "beta drifted").unwrap();
+        write_spec_at(
+            &dir,
+            "svc-a/COMPLIANCE_SPEC.aln",
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+        write_spec_at(
+            &dir,
+            "svc-b/COMPLIANCE_SPEC.aln",
+            &[r#"{ id = "b", path = "b.txt", seal = "b.txt.sha256" }"#],
+        );
+        fs::create_dir_all(dir.join(".aln/compliance")).unwrap();
+        fs::write(
+            dir.join(SPEC_MANIFEST_PATH),
+            "specs = [\"svc-a/COMPLIANCE_SPEC.aln\", \"svc-b/COMPLIANCE_SPEC.aln\"]\n",
+        )
+        .unwrap();
+
+        let (report, ok) = validate_fragments(&RealFs, &dir, &ValidateOptions::default()).unwrap();
+        assert!(!ok);
+        let summary: Vec<(&str, &str, &str)> = report
+            .fragments
+            .iter()
+            .map(|f| (f.spec_source.as_str(), f.id.as_str(), f.status.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("svc-a/COMPLIANCE_SPEC.aln", "a", "ok"),
+                ("svc-b/COMPLIANCE_SPEC.aln", "b", "hash_mismatch"),
+            ]
+        );
+    }
+
+    #[test]
+    fn coverage_flags_unsealed_files() {
+        let dir = scratch_dir("coverage");
+        fs::create_dir_all(dir.join("policies/nested")).unwrap();
+        write_sealed(&dir.join("policies"), "a.aln", "alpha");
+        fs::write(dir.join("policies/nested/forgotten.aln"), "beta").unwrap();
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "./policies/a.aln", seal = "policies/a.aln.sha256" }"#],
+        );
+
+        let opts = ValidateOptions {
+            coverage: Some(PathBuf::from("policies")),
+            ..ValidateOptions::default()
+        };
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(ok);
+        assert_eq!(
+            report.coverage,
+            Some(CoverageSection {
+                root: "policies".into(),
+                total_files: 3,
+                uncovered: vec!["policies/nested/forgotten.aln".into()],
+            })
+        );
+    }
+
+    #[test]
+    fn strict_fails_on_uncovered_files() {
+        let dir = scratch_dir("coverage-strict");
+        fs::create_dir_all(dir.join("policies")).unwrap();
+        write_sealed(&dir.join("policies"), "a.aln", "alpha");
+        fs::write(dir.join("policies/forgotten.aln"), "beta").unwrap();
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "policies/a.aln", seal = "policies/a.aln.sha256" }"#],
+        );
+
+        let opts = ValidateOptions {
+            coverage: Some(PathBuf::from("policies")),
+            require_coverage: true,
+            ..ValidateOptions::default()
+        };
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(report.passed());
+        assert!(!ok);
+    }
+
+    #[test]
+    fn parallel_jobs_keep_spec_order() {
+        let dir = scratch_dir("jobs");
+        let mut frags = Vec::new();
+        for i in 0..7 {
+            let name = format!("f{}.txt", i);
+            write_sealed(&dir, &name, &format!("fragment {}", i));
+            frags.push(fragment(
+                &format!("f{}", i),
+                &name,
+                &format!("{}.sha256", name),
+            ));
+        }
+        fs::write(dir.join("f3.txt"), "drifted").unwrap();
+        frags.push(fragment("gone", "gone.txt", "gone.txt.sha256"));
+
+        let run = |jobs| {
+            let opts = ValidateOptions {
+                jobs,
+                ..ValidateOptions::default()
+            };
+            let (results, ok) =
+                check_fragments(&RealFs, &dir, DEFAULT_SPEC_PATH, &frags, None, &opts).unwrap();
+            let summary: Vec<(String, String)> =
+                results.into_iter().map(|r| (r.id, r.status)).collect();
+            (summary, ok)
+        };
+        let (sequential, ok) = run(1);
+        assert!(!ok);
+        assert_eq!(
+            sequential[3],
+            ("f3".to_string(), "hash_mismatch".to_string())
+        );
+        assert_eq!(
+            sequential[7],
+            ("gone".to_string(), "missing_fragment".to_string())
+        );
+        for jobs in [2, 3, 16] {
+            assert_eq!(run(jobs), (sequential.clone(), false), "jobs = {}", jobs);
+        }
+    }
+
+    #[test]
+    fn parallel_report_is_byte_identical_to_serial() {
+        let dir = scratch_dir("jobs-report");
+        let mut items = Vec::new();
+        for i in 0..9 {
+            let name = format!("f{}.txt", i);
+            write_sealed(&dir, &name, &format!("fragment {}", i));
+            items.push(format!(
+                r#"{{ id = "f{}", path = "{}", seal = "{}.sha256" }}"#,
+                i, name, name
+            ));
+        }
+        fs::write(dir.join("f5.txt"), "drifted").unwrap();
+        let items: Vec<&str> = items.iter().map(String::as_str).collect();
+        write_spec(&dir, &items);
+
+        let report_for = |jobs| {
+            let opts = ValidateOptions {
+                jobs,
+                ..ValidateOptions::default()
+            };
+            let (_, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+            assert!(!ok);
+            fs::read(dir.join("compliance_report.json")).unwrap()
+        };
+        let serial = report_for(1);
+        for jobs in [2, 4, 9] {
+            assert!(report_for(jobs) == serial, "jobs = {}", jobs);
+        }
+    }
+
+    #[test]
+    fn energy_bounds_must_be_positive_and_under_ceiling() {
+        let dir = scratch_dir("energy-bounds");
+        write_spec_with_energy(&dir, DEFAULT_SPEC_PATH, &[], 1000, 0);
+        match load_spec(&dir, DEFAULT_SPEC_PATH) {
+            Err(OrchestratorError::InvalidEnergyBounds { field, value, .. }) => {
+                assert_eq!((field, value), ("max_csp_per_day", 0));
+            }
+            other => panic!("expected InvalidEnergyBounds, got {:?}", other),
+        }
+
+        write_spec_with_energy(&dir, DEFAULT_SPEC_PATH, &[], MAX_AUET_PER_DAY + 1, 10);
+        match load_spec(&dir, DEFAULT_SPEC_PATH) {
+            Err(OrchestratorError::InvalidEnergyBounds { field, max, .. }) => {
+                assert_eq!((field, max), ("max_auet_per_day", MAX_AUET_PER_DAY));
+            }
+            other => panic!("expected InvalidEnergyBounds, got {:?}", other),
+        }
+
+        write_spec_with_energy(
+            &dir,
+            DEFAULT_SPEC_PATH,
+            &[],
+            MAX_AUET_PER_DAY,
+            MAX_CSP_PER_DAY,
+        );
+        assert!(load_spec(&dir, DEFAULT_SPEC_PATH).is_ok());
+    }
+
+    #[test]
+    fn energy_ledger_over_the_daily_cap_fails_the_report() {
+        let dir = scratch_dir("energy-ledger");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+        let ledger = dir.join("ledger.json");
+        let charge = |at: &str, auet: f64| {
+            serde_json::json!({
+                "event_id": at, "vnode_id": "vnode-1", "agent_id": "agent-1",
+                "au_et_delta": auet, "csp_delta": 1.0, "reason": "AbilityUse",
+                "timestamp": at, "prev_hash": "", "hash": "",
+            })
+        };
+        let events = [
+            charge("2025-01-01T08:00:00Z", 600.0),
+            charge("2025-01-01T20:00:00Z", 400.0),
+        ];
+        fs::write(&ledger, serde_json::json!({ "events": events }).to_string()).unwrap();
+        let opts = ValidateOptions {
+            energy_ledger: Some(ledger.clone()),
+            ..ValidateOptions::default()
+        };
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(ok && report.passed());
+        let usage = report.energy_usage.as_ref().unwrap();
+        assert_eq!(
+            (usage.peak_auet_per_day, usage.peak_csp_per_day),
+            (1000.0, 2.0)
+        );
+
+        let events = [
+            charge("2025-01-01T08:00:00Z", 600.0),
+            charge("2025-01-01T20:00:00Z", 401.0),
+        ];
+        fs::write(&ledger, serde_json::json!(events).to_string()).unwrap();
+        let (report, ok) = validate_fragments(&RealFs, &dir, &opts).unwrap();
+        assert!(!ok && !report.passed());
+        assert_eq!(report.energy_usage.unwrap().status, "energy_cap_exceeded");
+    }
+
+    #[test]
+    fn spec_dir_paths_resolve_next_to_the_spec() {
+        let dir = scratch_dir("path-base");
+        fs::create_dir_all(dir.join("svc/policies")).unwrap();
+        fs::create_dir_all(dir.join("shared")).unwrap();
+        write_sealed(&dir.join("svc/policies"), "a.aln", "alpha");
+        write_sealed(&dir.join("shared"), "b.aln", "beta");
+        let source = "svc/policies/COMPLIANCE_SPEC.aln";
+        write_spec_at(
+            &dir,
+            source,
+            &[
+                r#"{ id = "a", path = "a.aln", seal = "./a.aln.sha256" }"#,
+                r#"{ id = "b", path = "../../shared/b.aln", seal = "../../shared/b.aln.sha256" }"#,
+            ],
+        );
+        let spec_path = dir.join(source);
+        let text = fs::read_to_string(&spec_path).unwrap();
+        fs::write(&spec_path, format!("path_base = \"spec_dir\"\n{}", text)).unwrap();
+
+        let spec = load_spec(&dir, source).unwrap();
+        let paths: Vec<(&str, &str)> = spec
+            .fragments
+            .items
+            .iter()
+            .map(|f| (f.path.as_str(), f.seal.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            vec![
+                ("svc/policies/a.aln", "svc/policies/a.aln.sha256"),
+                ("shared/b.aln", "shared/b.aln.sha256"),
+            ]
+        );
+
+        let (results, ok) = check_fragments(
+            &RealFs,
+            &dir,
+            source,
+            &spec.fragments.items,
+            None,
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(ok, "{:?}", results);
+
+        // Without `path_base` the same entries resolve against the repo root.
+        fs::write(&spec_path, text).unwrap();
+        let spec = load_spec(&dir, source).unwrap();
+        assert_eq!(spec.fragments.items[0].path, "a.aln");
+    }
+
+    #[test]
+    fn repo_root_precedence_and_validation() {
+        let cwd = || PathBuf::from("cwd");
+        let (flag, env) = (Some(PathBuf::from("flag")), Some(PathBuf::from("env")));
+        assert_eq!(
+            pick_repo_root(flag.clone(), env.clone(), cwd),
+            PathBuf::from("flag")
+        );
+        assert_eq!(pick_repo_root(None, env, cwd), PathBuf::from("env"));
+        assert_eq!(pick_repo_root(None, None, cwd), PathBuf::from("cwd"));
+
+        let dir = scratch_dir("repo-root");
+        match check_repo_root(&dir.join("missing")) {
+            Err(OrchestratorError::RepoRoot { reason, .. }) => {
+                assert_eq!(reason, "directory does not exist")
+            }
+            other => panic!("expected RepoRoot error, got {:?}", other),
+        }
+        assert!(matches!(
+            resolve_repo_root(Some(&dir)),
+            Err(OrchestratorError::RepoRoot {
+                reason: "no .aln/compliance/ directory",
+                ..
+            })
+        ));
+        write_spec(&dir, &[]);
+        assert_eq!(resolve_repo_root(Some(&dir)).unwrap(), dir);
+    }
+
+    /// In-memory `FragmentFs`: each path holds either its bytes or the error
+    /// kind every read of it fails with. Unlisted paths don't exist.
+    #[derive(Default)]
+    struct MockFs {
+        files: HashMap<PathBuf, Result<Vec<u8>, io::ErrorKind>>,
+    }
+
+    impl MockFs {
+        fn with_file(mut self, path: &str, contents: &str) -> Self {
+            self.files
+                .insert(PathBuf::from(path), Ok(contents.as_bytes().to_vec()));
+            self
+        }
+
+        fn with_error(mut self, path: &str, kind: io::ErrorKind) -> Self {
+            self.files.insert(PathBuf::from(path), Err(kind));
+            self
+        }
+    }
+
+    impl FragmentFs for MockFs {
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            match self.files.get(path) {
+                Some(Ok(bytes)) => Ok(bytes.clone()),
+                Some(Err(kind)) => Err(io::Error::new(*kind, "simulated read failure")),
+                None => Err(io::ErrorKind::NotFound.into()),
+            }
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.files.contains_key(path)
+        }
+    }
+
+    #[test]
+    fn mock_fs_reports_missing_files_and_surfaces_read_errors() {
+        let root = Path::new("/repo");
+        let alpha_digest = hex::encode(Sha256::digest(b"alpha"));
+        let fs = MockFs::default()
+            .with_file("/repo/a.txt", "alpha")
+            .with_file(
+                "/repo/a.txt.sha256",
+                &format!("sha256 = {}\n", alpha_digest),
+            )
+            .with_file("/repo/b.txt.sha256", "sha256 = 00\n")
+            .with_file("/repo/c.txt", "gamma")
+            .with_error("/repo/c.txt.sha256", io::ErrorKind::PermissionDenied);
+
+        let frags = vec![
+            fragment("a", "a.txt", "a.txt.sha256"),
+            fragment("b", "b.txt", "b.txt.sha256"),
+        ];
+        let (results, ok) = check_fragments(
+            &fs,
+            root,
+            DEFAULT_SPEC_PATH,
+            &frags,
+            None,
+            &ValidateOptions::default(),
+        )
+        .unwrap();
+        assert!(!ok);
+        assert_eq!(results[0].status, "ok");
+        assert_eq!(results[1].status, "missing_fragment");
+
+        let unreadable = vec![fragment("c", "c.txt", "c.txt.sha256")];
+        match check_fragments(
+            &fs,
+            root,
+            DEFAULT_SPEC_PATH,
+            &unreadable,
+            None,
+            &ValidateOptions::default(),
+        ) {
+            Err(OrchestratorError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::PermissionDenied),
+            other => panic!("expected a permission error, got {:?}", other),
+        }
+
+        // The fragment itself is hashed before its seal is read.
+        let fs = fs.with_error("/repo/c.txt", io::ErrorKind::UnexpectedEof);
+        match check_fragments(
+            &fs,
+            root,
+            DEFAULT_SPEC_PATH,
+            &unreadable,
+            None,
+            &ValidateOptions::default(),
+        ) {
+            Err(OrchestratorError::Io(e)) => assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof),
+            other => panic!("expected a partial-read error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn run_results_map_to_documented_exit_codes() {
+        use crate::exit::Exit;
+
+        let dir = scratch_dir("exit-codes");
+        write_sealed(&dir, "a.txt", "alpha");
+        write_spec(
+            &dir,
+            &[r#"{ id = "a", path = "a.txt", seal = "a.txt.sha256" }"#],
+        );
+        let args = ValidateArgs {
+            jobs: 1,
+            ..ValidateArgs::default()
+        };
+        let result = run(&dir, &args, OutputFormat::Json);
+        assert_eq!(Exit::from_result(&result), Exit::Ok);
+        assert_eq!(Exit::Ok.code(), 0);
+
+        fs::write(dir.join("a.txt"), "tampered").unwrap();
+        let result = run(&dir, &args, OutputFormat::Json);
+        assert_eq!(Exit::from_result(&result), Exit::ValidationFailed);
+        assert_eq!(Exit::ValidationFailed.code(), 1);
+
+        // A ledger that reads fine but isn't JSON is malformed input, not IO.
+        let ledger = dir.join("ledger.json");
+        fs::write(&ledger, "not json").unwrap();
+        let with_ledger = ValidateArgs {
+            jobs: 1,
+            energy_ledger: Some(ledger),
+            ..ValidateArgs::default()
+        };
+        let result = run(&dir, &with_ledger, OutputFormat::Json);
+        assert!(matches!(result, Err(OrchestratorError::Io(_))));
+        assert_eq!(Exit::from_result(&result), Exit::Data);
+
+        fs::write(dir.join(DEFAULT_SPEC_PATH), "items = [ not toml").unwrap();
+        let result = run(&dir, &args, OutputFormat::Json);
+        assert!(matches!(result, Err(OrchestratorError::Toml(_))));
+        assert_eq!(Exit::from_result(&result), Exit::Data);
+        assert_eq!(Exit::Data.code(), 4);
+
+        let missing = resolve_repo_root(Some(&dir.join("no-such-dir"))).unwrap_err();
+        assert_eq!(Exit::from(&missing), Exit::Usage);
+    }
+
+    #[test]
+    fn broken_spec_reports_path_and_location() {
+        let dir = scratch_dir("toml-diagnostic");
+        let path = dir.join(DEFAULT_SPEC_PATH);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(
+            &path,
+            "version = \"1.0.0\"\nlanguage = \"aln\"\nblueprint = \"B\"\n\n[fragments]\n\
+             items = [ { id = \"a\", path = \"a.txt\" seal = \"a.txt.sha256\" } ]\n",
+        )
+        .unwrap();
+
+        let err = match load_spec(&dir, DEFAULT_SPEC_PATH) {
+            Err(OrchestratorError::Toml(diag)) => diag,
+            other => panic!("expected a TOML diagnostic, got {:?}", other.map(|_| ())),
+        };
+        assert_eq!(err.path, Path::new(DEFAULT_SPEC_PATH));
+        assert_eq!(err.line, Some(6));
+        let column = err.column.unwrap();
+        assert!((38..=42).contains(&column), "column {}", column);
+
+        let rendered = OrchestratorError::Toml(err).to_string();
+        assert!(
+            rendered.starts_with(&format!(
+                "TOML parse error in {}:6:{}: ",
+                DEFAULT_SPEC_PATH, column
+            )),
+            "{}",
+            rendered
+        );
+        assert!(
+            rendered.contains("\n 6 | items = [ { id = \"a\""),
+            "{}",
+            rendered
+        );
+    }
+}
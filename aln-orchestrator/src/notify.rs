@@ -1,7 +1,8 @@
 // Path: aln-orchestrator/src/notify.rs
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::Path;
 
 #[derive(Debug, Deserialize)]
 struct FragmentResult {
@@ -11,17 +12,156 @@ struct FragmentResult {
     detail: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+struct DailyEnergy {
+    day: String,
+    auet: f64,
+    csp: f64,
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EnergyUsage {
+    ledger: String,
+    days: Vec<DailyEnergy>,
+}
+
 #[derive(Debug, Deserialize)]
 struct ValidationReport {
     fragments: Vec<FragmentResult>,
     blueprint: String,
     version: String,
+    #[serde(default)]
+    energy_usage: Option<EnergyUsage>,
+    /// Absent from reports written before pipeline graphs were checked.
+    #[serde(default)]
+    pipeline_status: Option<String>,
+}
+
+/// GitHub workflow-command annotation level for a fragment status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    Error,
+    Warning,
+    Notice,
+}
+
+impl Severity {
+    fn command(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Notice => "notice",
+        }
+    }
+}
+
+/// Status -> severity overrides. `ok` is never annotated; any status not in
+/// the map is an error, so new failure modes block merges until classified.
+#[derive(Debug, Default)]
+struct SeverityMap {
+    overrides: HashMap<String, Severity>,
 }
 
-fn main() -> std::io::Result<()> {
-    let repo_root = std::env::var("GITHUB_WORKSPACE")
-        .map(PathBuf::from)
-        .unwrap_or_else(|_| std::env::current_dir().unwrap());
+impl SeverityMap {
+    fn builtin() -> Self {
+        let mut overrides = HashMap::new();
+        overrides.insert("ok_cached".to_string(), Severity::Notice);
+        overrides.insert("deprecated_seal".to_string(), Severity::Warning);
+        Self { overrides }
+    }
+
+    /// Built-in defaults, overlaid with the JSON object (`{"status": "warning"}`)
+    /// at `$ALN_NOTIFY_SEVERITY_MAP`, or at
+    /// `.aln/compliance/notify_severity.json` when the variable is unset.
+    fn load(repo_root: &Path) -> std::io::Result<Self> {
+        match std::env::var_os("ALN_NOTIFY_SEVERITY_MAP") {
+            Some(path) => Self::load_from(Path::new(&path), true),
+            None => Self::load_from(
+                &repo_root.join(".aln/compliance/notify_severity.json"),
+                false,
+            ),
+        }
+    }
+
+    /// The map at `path` over the built-in defaults. Only an implicit
+    /// (`explicit == false`) path may be missing; a configured one must exist.
+    fn load_from(path: &Path, explicit: bool) -> std::io::Result<Self> {
+        let mut map = Self::builtin();
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => return Ok(map),
+            Err(e) => {
+                return Err(std::io::Error::new(
+                    e.kind(),
+                    format!("severity map {}: {}", path.display(), e),
+                ))
+            }
+        };
+        let overrides: HashMap<String, Severity> = serde_json::from_str(&text)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        map.overrides.extend(overrides);
+        Ok(map)
+    }
+
+    fn severity(&self, status: &str) -> Option<Severity> {
+        if status == "ok" {
+            return None;
+        }
+        Some(
+            self.overrides
+                .get(status)
+                .copied()
+                .unwrap_or(Severity::Error),
+        )
+    }
+}
+
+/// Render one annotation per non-ok fragment, plus an error for an invalid
+/// pipeline graph and for each day over an energy cap, and report whether any
+/// of them is error-level (the only level that marks the orchestration as
+/// violated).
+fn annotate(report: &ValidationReport, severities: &SeverityMap) -> (Vec<String>, bool) {
+    let mut lines = Vec::new();
+    let mut violated = false;
+    if let Some(status) = report.pipeline_status.as_deref().filter(|s| *s != "ok") {
+        violated = true;
+        lines.push(format!(
+            "::error title=ALN pipeline graph invalid::status={}",
+            status
+        ));
+    }
+    if let Some(usage) = &report.energy_usage {
+        for day in usage.days.iter().filter(|d| d.status != "ok") {
+            violated = true;
+            lines.push(format!(
+                "::error title=ALN energy cap exceeded,file={}::day={} auet={} csp={} status={}",
+                usage.ledger, day.day, day.auet, day.csp, day.status
+            ));
+        }
+    }
+    for frag in &report.fragments {
+        let Some(severity) = severities.severity(&frag.status) else {
+            continue;
+        };
+        violated |= severity == Severity::Error;
+        let detail = frag.detail.as_deref().unwrap_or("");
+        lines.push(format!(
+            "::{} title=ALN compliance violation,file={}::id={} status={} detail={}",
+            severity.command(),
+            frag.path,
+            frag.id,
+            frag.status,
+            detail
+        ));
+    }
+    (lines, violated)
+}
+
+/// Print GitHub annotations for `<repo_root>/compliance_report.json` followed
+/// by the `ALN_ORCHESTRATION` status line.
+pub fn run(repo_root: &Path) -> std::io::Result<()> {
     let report_path = repo_root.join("compliance_report.json");
     if !report_path.exists() {
         println!("No compliance_report.json; nothing to notify.");
@@ -30,17 +170,11 @@ fn main() -> std::io::Result<()> {
     let text = fs::read_to_string(&report_path)?;
     let report: ValidationReport = serde_json::from_str(&text)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let severities = SeverityMap::load(repo_root)?;
 
-    let mut violated = false;
-    for frag in &report.fragments {
-        if frag.status != "ok" {
-            violated = true;
-            let detail = frag.detail.as_deref().unwrap_or("");
-            println!(
-                "::error title=ALN compliance violation,file={}::id={} status={} detail={}",
-                frag.path, frag.id, frag.status, detail
-            );
-        }
+    let (lines, violated) = annotate(&report, &severities);
+    for line in &lines {
+        println!("{}", line);
     }
 
     if violated {
@@ -57,3 +191,107 @@ fn main() -> std::io::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(statuses: &[&str]) -> ValidationReport {
+        ValidationReport {
+            fragments: statuses
+                .iter()
+                .enumerate()
+                .map(|(i, status)| FragmentResult {
+                    id: format!("frag-{}", i),
+                    path: format!("frag-{}.aln", i),
+                    status: status.to_string(),
+                    detail: None,
+                })
+                .collect(),
+            blueprint: "BP".into(),
+            version: "1.0.0".into(),
+            energy_usage: None,
+            pipeline_status: None,
+        }
+    }
+
+    #[test]
+    fn warning_mapped_status_does_not_violate() {
+        let mut severities = SeverityMap::builtin();
+        severities
+            .overrides
+            .insert("missing_seal".to_string(), Severity::Warning);
+
+        let (lines, violated) = annotate(&report(&["ok", "missing_seal"]), &severities);
+        assert!(!violated);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("::warning "));
+    }
+
+    #[test]
+    fn unmapped_statuses_default_to_error() {
+        let (lines, violated) = annotate(
+            &report(&["hash_mismatch", "ok_cached"]),
+            &SeverityMap::builtin(),
+        );
+        assert!(violated);
+        assert!(lines[0].starts_with("::error "));
+        assert!(lines[1].starts_with("::notice "));
+    }
+
+    #[test]
+    fn configured_severity_map_must_exist() {
+        let missing = std::env::temp_dir().join(format!(
+            "aln-notify-severity-missing-{}.json",
+            std::process::id()
+        ));
+        let err = SeverityMap::load_from(&missing, true).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+        assert!(err.to_string().contains("severity map"), "{}", err);
+
+        let defaults = SeverityMap::load_from(&missing, false).unwrap();
+        assert_eq!(defaults.severity("ok_cached"), Some(Severity::Notice));
+    }
+
+    #[test]
+    fn invalid_pipeline_graph_violates() {
+        let mut report = report(&["ok"]);
+        report.pipeline_status = Some("ok".into());
+        assert!(!annotate(&report, &SeverityMap::builtin()).1);
+
+        report.pipeline_status =
+            Some(".aln/compliance/COMPLIANCE_SPEC.aln: cycle through a -> b -> a".into());
+        let (lines, violated) = annotate(&report, &SeverityMap::builtin());
+        assert!(violated);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("::error title=ALN pipeline graph invalid::"));
+        assert!(lines[0].contains("cycle"), "{}", lines[0]);
+    }
+
+    #[test]
+    fn days_over_an_energy_cap_violate() {
+        let text = r#"{
+            "fragments": [{"id": "a", "path": "a.aln", "status": "ok"}],
+            "blueprint": "BP",
+            "version": "1.0.0",
+            "energy_usage": {
+                "ledger": "ledger.json",
+                "status": "energy_cap_exceeded",
+                "days": [
+                    {"day": "2025-01-01", "auet": 10.0, "csp": 1.0, "status": "ok"},
+                    {"day": "2025-01-02", "auet": 120.0, "csp": 1.0, "status": "energy_cap_exceeded"}
+                ]
+            }
+        }"#;
+        let report: ValidationReport = serde_json::from_str(text).unwrap();
+        let (lines, violated) = annotate(&report, &SeverityMap::builtin());
+        assert!(violated);
+        assert_eq!(
+            lines,
+            [
+                "::error title=ALN energy cap exceeded,file=ledger.json::day=2025-01-02 auet=120 \
+              csp=1 status=energy_cap_exceeded"
+            ]
+        );
+    }
+}
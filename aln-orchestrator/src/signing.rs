@@ -0,0 +1,154 @@
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{write_atomic, OrchestratorError};
+
+/// SHA-256 over the report exactly as written to disk.
+///
+/// The report is serialized from fixed struct field order, so its bytes are
+/// already canonical; the hex digest of those bytes is what gets signed.
+pub fn report_hash(report_bytes: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(report_bytes);
+    hex::encode(hasher.finalize())
+}
+
+/// Location of the detached signature for a report (`compliance_report.sig`).
+pub fn signature_path(report_path: &Path) -> PathBuf {
+    report_path.with_extension("sig")
+}
+
+/// Sign `report_path` with the Ed25519 secret key at `key_path` and write the
+/// detached signature next to it. Returns the signature file path.
+///
+/// The key file holds the 32-byte secret seed, either raw or hex-encoded.
+pub fn sign_report(report_path: &Path, key_path: &Path) -> Result<PathBuf, OrchestratorError> {
+    let seed = read_key_bytes(key_path)?;
+    let signing_key = SigningKey::from_bytes(&seed);
+
+    let hash = report_hash(&fs::read(report_path)?);
+    let signature = signing_key.sign(hash.as_bytes());
+
+    let sig_path = signature_path(report_path);
+    let body = format!(
+        "algorithm = ed25519\nreport_hash = {}\npublic_key = {}\nsignature = {}\n",
+        hash,
+        hex::encode(signing_key.verifying_key().to_bytes()),
+        hex::encode(signature.to_bytes()),
+    );
+    write_atomic(&sig_path, body.as_bytes())?;
+    Ok(sig_path)
+}
+
+/// Check that `report_path` matches its detached signature and that the
+/// signature was produced by the holder of the public key at `pubkey_path`.
+pub fn verify_report(report_path: &Path, pubkey_path: &Path) -> Result<(), OrchestratorError> {
+    let expected_key = read_key_bytes(pubkey_path)?;
+    let verifying_key = VerifyingKey::from_bytes(&expected_key)
+        .map_err(|e| OrchestratorError::Signature(format!("invalid public key: {}", e)))?;
+
+    let sig_text = fs::read_to_string(signature_path(report_path))?;
+    let fields = parse_signature_file(&sig_text);
+
+    let recorded_key = field_bytes::<32>(&fields, "public_key")?;
+    if recorded_key != expected_key {
+        return Err(OrchestratorError::Signature(
+            "signature was produced by a different public key".into(),
+        ));
+    }
+
+    let hash = report_hash(&fs::read(report_path)?);
+    if fields.get("report_hash").map(String::as_str) != Some(hash.as_str()) {
+        return Err(OrchestratorError::Signature(
+            "report_hash does not match report contents".into(),
+        ));
+    }
+
+    let signature = Signature::from_bytes(&field_bytes::<64>(&fields, "signature")?);
+    verifying_key
+        .verify(hash.as_bytes(), &signature)
+        .map_err(|e| OrchestratorError::Signature(format!("verification failed: {}", e)))
+}
+
+fn parse_signature_file(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+fn field_bytes<const N: usize>(
+    fields: &HashMap<String, String>,
+    name: &str,
+) -> Result<[u8; N], OrchestratorError> {
+    let value = fields
+        .get(name)
+        .ok_or_else(|| OrchestratorError::Signature(format!("signature file missing {}", name)))?;
+    decode_fixed(value.as_bytes()).ok_or_else(|| {
+        OrchestratorError::Signature(format!("malformed {} in signature file", name))
+    })
+}
+
+fn read_key_bytes(path: &Path) -> Result<[u8; 32], OrchestratorError> {
+    let bytes = fs::read(path)?;
+    decode_fixed(&bytes).ok_or_else(|| {
+        OrchestratorError::Signature(format!(
+            "{}: expected 32 raw bytes or 64 hex chars",
+            path.display()
+        ))
+    })
+}
+
+/// Accept either exactly `N` raw bytes or their hex encoding (surrounding
+/// whitespace ignored).
+fn decode_fixed<const N: usize>(bytes: &[u8]) -> Option<[u8; N]> {
+    if bytes.len() == N {
+        return bytes.try_into().ok();
+    }
+    let text = std::str::from_utf8(bytes).ok()?.trim();
+    hex::decode(text).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::scratch_dir;
+
+    fn write_keypair(dir: &Path) -> (PathBuf, PathBuf) {
+        let seed = [7u8; 32];
+        let key_path = dir.join("signing.key");
+        let pub_path = dir.join("signing.pub");
+        fs::write(&key_path, hex::encode(seed)).unwrap();
+        let public = SigningKey::from_bytes(&seed).verifying_key();
+        fs::write(&pub_path, hex::encode(public.to_bytes())).unwrap();
+        (key_path, pub_path)
+    }
+
+    #[test]
+    fn sign_then_verify_roundtrip() {
+        let dir = scratch_dir("sign-roundtrip");
+        let (key_path, pub_path) = write_keypair(&dir);
+        let report = dir.join("compliance_report.json");
+        fs::write(&report, "{\"fragments\": []}").unwrap();
+
+        let sig_path = sign_report(&report, &key_path).unwrap();
+        assert_eq!(sig_path, dir.join("compliance_report.sig"));
+        verify_report(&report, &pub_path).unwrap();
+    }
+
+    #[test]
+    fn tampered_report_fails_verification() {
+        let dir = scratch_dir("sign-tampered");
+        let (key_path, pub_path) = write_keypair(&dir);
+        let report = dir.join("compliance_report.json");
+        fs::write(&report, "{\"fragments\": []}").unwrap();
+        sign_report(&report, &key_path).unwrap();
+
+        fs::write(&report, "{\"fragments\": [\"forged\"]}").unwrap();
+        let err = verify_report(&report, &pub_path).unwrap_err();
+        assert!(matches!(err, OrchestratorError::Signature(_)));
+    }
+}
@@ -0,0 +1,10 @@
+// Path: javaspectre-cli/src/lib.rs
+//! Library half of the `javaspectre` CLI: sources that have no manifest of
+//! their own, compiled in directly so the CLI and its tests share them.
+#[path = "../../src/aln_vnodes/lib.rs"]
+#[rustfmt::skip]
+pub mod aln_vnodes;
+#[path = "../../src/cybercore/javaspectre/cybercore_javaspectre_sqlite_bridge.rs"]
+#[rustfmt::skip]
+#[allow(clippy::arc_with_non_send_sync)]
+pub mod sqlite_bridge;
@@ -0,0 +1,251 @@
+// Path: javaspectre-cli/src/main.rs
+//! Single `javaspectre` entrypoint for the orchestrator, notifier, pattern
+//! lint, vnode builder and span ingestion. The standalone binaries remain as
+//! thin shims over the same library functions.
+use aln_orchestrator::exit::{Exit, EXIT_CODES_HELP};
+use aln_orchestrator::{OrchestratorError, OutputFormat, ValidateArgs};
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use javaspectre_cli::aln_vnodes::{
+    build_vnode_graph_file, verify_vnode_graph_file, EnergyError, GraphLoadError,
+};
+use javaspectre_cli::sqlite_bridge::{
+    ingest_otel_span, IngestOutcome, JavaspectreConfig, JavaspectreError, JavaspectreStore,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "javaspectre", version, after_help = EXIT_CODES_HELP)]
+struct Cli {
+    /// Print the resolved repo root and inputs to stderr.
+    #[arg(long, global = true)]
+    verbose: bool,
+    /// Output format for subcommand results.
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Validate sealed compliance fragments (same flags as `aln-orchestrator`).
+    Validate(ValidateArgs),
+    /// Emit GitHub annotations for `compliance_report.json`.
+    Notify,
+    /// Scan a tree for forbidden pattern terms.
+    Lint {
+        /// Root directory to scan.
+        #[arg(default_value = ".")]
+        root: PathBuf,
+        /// Lint config to use instead of `pattern_lint.config.json` in the
+        /// scan root. Fails if the file is missing or invalid.
+        #[arg(long, value_name = "PATH")]
+        config: Option<PathBuf>,
+    },
+    /// Build a VNode graph from a MachineObjects JSON array.
+    Vnodes {
+        /// Path to MachineObjects JSON file (array of MachineObject)
+        #[arg(long, required_unless_present = "verify")]
+        input: Option<PathBuf>,
+        /// Instead of building, re-derive the blueprint hash of a stored
+        /// graph and fail if it does not match.
+        #[arg(long, value_name = "GRAPH_JSON", conflicts_with = "input")]
+        verify: Option<PathBuf>,
+        /// Origin tag, e.g. "JavaSpectre-0.1.0"
+        #[arg(long, default_value = "JavaSpectre")]
+        origin: String,
+    },
+    /// Ingest OTEL spans (one JSON object per line) into a SQLite store.
+    Ingest {
+        /// SQLite database path.
+        #[arg(long, default_value = "javaspectre.db")]
+        db: PathBuf,
+        /// Span file to read, or `-` for stdin.
+        #[arg(long, default_value = "-")]
+        input: PathBuf,
+        /// Skip spans whose content hash is already stored.
+        #[arg(long)]
+        dedup: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    // clap exits with `Exit::Usage` (2) on bad arguments.
+    let cli = Cli::parse();
+    match run(&cli) {
+        Ok(ok) => Exit::from_outcome(ok).into(),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            exit_for(&e).into()
+        }
+    }
+}
+
+/// Run the selected subcommand. Returns whether its check passed.
+fn run(cli: &Cli) -> anyhow::Result<bool> {
+    let ok = match &cli.command {
+        Command::Validate(args) => {
+            let repo_root = aln_orchestrator::resolve_repo_root(args.repo_root.as_deref())?;
+            if cli.verbose {
+                eprintln!("repo root: {}", repo_root.display());
+            }
+            aln_orchestrator::run(&repo_root, args, cli.format)?
+        }
+        Command::Notify => {
+            let repo_root = aln_orchestrator::repo_root_from_env();
+            if cli.verbose {
+                eprintln!("repo root: {}", repo_root.display());
+            }
+            aln_orchestrator::notify::run(&repo_root)?;
+            true
+        }
+        Command::Lint { root, config } => {
+            if cli.verbose {
+                eprintln!("lint root: {}", root.display());
+            }
+            match cli.format {
+                OutputFormat::Text => pattern_lint::run(root, config.as_deref())?,
+                OutputFormat::Json => {
+                    let violations = pattern_lint::lint(root, config.as_deref())?;
+                    println!("{}", serde_json::to_string_pretty(&violations)?);
+                    violations.is_empty()
+                }
+            }
+        }
+        Command::Vnodes {
+            input,
+            verify,
+            origin,
+        } => {
+            if let Some(graph) = verify {
+                if cli.verbose {
+                    eprintln!("verifying graph: {}", graph.display());
+                }
+                verify_vnodes(graph, cli.format)?;
+            } else {
+                let input = input
+                    .as_deref()
+                    .expect("clap requires --input without --verify");
+                if cli.verbose {
+                    eprintln!("machine objects: {}", input.display());
+                }
+                run_vnodes(input, origin, cli.format)?;
+            }
+            true
+        }
+        Command::Ingest { db, input, dedup } => {
+            if cli.verbose {
+                eprintln!("ingesting {} into {}", input.display(), db.display());
+            }
+            run_ingest(db, input, *dedup, cli.format)?;
+            true
+        }
+    };
+    Ok(ok)
+}
+
+/// Classify an error by the first typed cause in its chain.
+fn exit_for(e: &anyhow::Error) -> Exit {
+    for cause in e.chain() {
+        if let Some(e) = cause.downcast_ref::<OrchestratorError>() {
+            return Exit::from(e);
+        }
+        if let Some(e) = cause.downcast_ref::<JavaspectreError>() {
+            return match e {
+                JavaspectreError::Io(e) => Exit::from(e),
+                JavaspectreError::Sqlite(_) | JavaspectreError::Busy { .. } => Exit::Io,
+                JavaspectreError::Json(_)
+                | JavaspectreError::Hash(_)
+                | JavaspectreError::Schema(_) => Exit::Data,
+                JavaspectreError::Config(_) => Exit::Usage,
+                JavaspectreError::StillShared { .. } | JavaspectreError::Cancelled { .. } => {
+                    Exit::Internal
+                }
+            };
+        }
+        if cause.downcast_ref::<EnergyError>().is_some() {
+            return Exit::ValidationFailed;
+        }
+        if cause.downcast_ref::<GraphLoadError>().is_some() {
+            return Exit::Data;
+        }
+        if let Some(pattern_lint::ConfigError::BlankTerms { .. }) = cause.downcast_ref() {
+            return Exit::Data;
+        }
+        if let Some(e) = cause.downcast_ref::<serde_json::Error>() {
+            return if e.is_io() { Exit::Io } else { Exit::Data };
+        }
+        if let Some(e) = cause.downcast_ref::<io::Error>() {
+            return Exit::from(e);
+        }
+    }
+    Exit::Internal
+}
+
+fn run_vnodes(input: &Path, origin: &str, format: OutputFormat) -> anyhow::Result<()> {
+    let graph = build_vnode_graph_file(origin, input)?;
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&graph)?),
+        OutputFormat::Text => {
+            println!("vnodes: {}", graph.vnodes.len());
+            println!("BLUEPRINT_HASH {}", graph.blueprint_hash);
+        }
+    }
+    Ok(())
+}
+
+fn verify_vnodes(path: &Path, format: OutputFormat) -> anyhow::Result<()> {
+    let graph = verify_vnode_graph_file(path)?;
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({"verified": true, "blueprint_hash": graph.blueprint_hash})
+        ),
+        OutputFormat::Text => println!("BLUEPRINT_HASH_VERIFIED {}", graph.blueprint_hash),
+    }
+    Ok(())
+}
+
+fn run_ingest(db: &Path, input: &Path, dedup: bool, format: OutputFormat) -> anyhow::Result<()> {
+    let store = JavaspectreStore::open(JavaspectreConfig {
+        path: db.to_string_lossy().into_owned(),
+        dedup_ingest: dedup,
+        ..JavaspectreConfig::default()
+    })?;
+    let reader: Box<dyn BufRead> = if input == Path::new("-") {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        let file = fs::File::open(input).with_context(|| input.display().to_string())?;
+        Box::new(BufReader::new(file))
+    };
+
+    let (mut stored, mut already_present) = (0usize, 0usize);
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match ingest_otel_span(&store, &line).with_context(|| format!("line {}", n + 1))? {
+            IngestOutcome::Stored => stored += 1,
+            IngestOutcome::AlreadyPresent => already_present += 1,
+        }
+    }
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::json!({"stored": stored, "already_present": already_present})
+        ),
+        OutputFormat::Text => {
+            println!(
+                "ingested {} spans ({} already present)",
+                stored, already_present
+            )
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,135 @@
+// Path: javaspectre-cli/tests/exit_codes.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn scratch_dir(tag: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("javaspectre-exit-{}-{}", tag, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn javaspectre(args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_javaspectre"))
+        .args(args)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+fn path_arg(path: &Path) -> &str {
+    path.to_str().unwrap()
+}
+
+#[test]
+fn usage_io_and_malformed_input_have_distinct_codes() {
+    let dir = scratch_dir("vnodes");
+    assert_eq!(javaspectre(&["vnodes", "--no-such-flag"]), 2);
+
+    let missing = dir.join("missing.json");
+    assert_eq!(javaspectre(&["vnodes", "--input", path_arg(&missing)]), 3);
+
+    let malformed = dir.join("malformed.json");
+    fs::write(&malformed, "[ {").unwrap();
+    assert_eq!(javaspectre(&["vnodes", "--input", path_arg(&malformed)]), 4);
+
+    let empty = dir.join("empty.json");
+    fs::write(&empty, "[]").unwrap();
+    assert_eq!(javaspectre(&["vnodes", "--input", path_arg(&empty)]), 0);
+}
+
+#[test]
+fn failed_validation_exits_with_one() {
+    let dir = scratch_dir("validate");
+    let spec_dir = dir.join(".aln/compliance");
+    fs::create_dir_all(&spec_dir).unwrap();
+    fs::write(dir.join("a.txt"), "alpha").unwrap();
+    fs::write(
+        dir.join("a.txt.sha256"),
+        format!("sha256 = {}\n", "0".repeat(64)),
+    )
+    .unwrap();
+    fs::write(
+        spec_dir.join("COMPLIANCE_SPEC.aln"),
+        r#"version = "1.0.0"
+language = "aln"
+blueprint = "TEST_BLUEPRINT"
+
+[fragments]
+items = [
+{ id = "a", path = "a.txt", seal = "a.txt.sha256" }
+]
+
+[orchestration]
+contracts = []
+
+[orchestration.pipelines]
+graph = []
+
+[energy]
+max_auet_per_day = 1000
+max_csp_per_day = 10
+"#,
+    )
+    .unwrap();
+
+    assert_eq!(javaspectre(&["validate", "--repo-root", path_arg(&dir)]), 1);
+}
+
+#[test]
+fn vnodes_verify_detects_edited_graphs() {
+    let dir = scratch_dir("verify");
+    let objects = dir.join("objects.json");
+    fs::write(
+        &objects,
+        r#"[{"id": "svc-1", "path": "/cluster/api/service", "type": "Service", "attributes": {}}]"#,
+    )
+    .unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_javaspectre"))
+        .args(["--format", "json", "vnodes", "--input", path_arg(&objects)])
+        .output()
+        .unwrap();
+    assert!(out.status.success());
+
+    let intact = dir.join("graph.json");
+    fs::write(&intact, &out.stdout).unwrap();
+    assert_eq!(javaspectre(&["vnodes", "--verify", path_arg(&intact)]), 0);
+
+    let mut graph: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    graph["vnodes"][0]["path"] = serde_json::json!("/cluster/api/edited");
+    let edited = dir.join("edited.json");
+    fs::write(&edited, graph.to_string()).unwrap();
+    let out = Command::new(env!("CARGO_BIN_EXE_javaspectre"))
+        .args(["vnodes", "--verify", path_arg(&edited)])
+        .output()
+        .unwrap();
+    assert_eq!(out.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(
+        stderr.contains("blueprint_hash mismatch: expected"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn lint_config_must_exist_and_parse() {
+    let dir = scratch_dir("lint-config");
+    let lint = |config: &Path| javaspectre(&["lint", path_arg(&dir), "--config", path_arg(config)]);
+    assert_eq!(lint(&dir.join("missing.json")), 3);
+
+    let invalid = dir.join("invalid.json");
+    fs::write(&invalid, r#"{"forbidden_terms": "CSP"}"#).unwrap();
+    assert_eq!(lint(&invalid), 4);
+
+    let blank = dir.join("blank.json");
+    fs::write(&blank, r#"{"forbidden_terms": [" "], "ignore_paths": []}"#).unwrap();
+    assert_eq!(lint(&blank), 4);
+
+    let valid = dir.join("valid.json");
+    fs::write(&valid, r#"{"forbidden_terms": [], "ignore_paths": []}"#).unwrap();
+    assert_eq!(lint(&valid), 0);
+}
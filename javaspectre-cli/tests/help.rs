@@ -0,0 +1,15 @@
+// Path: javaspectre-cli/tests/help.rs
+use std::process::Command;
+
+#[test]
+fn every_subcommand_prints_help() {
+    for sub in ["validate", "notify", "lint", "vnodes", "ingest"] {
+        let out = Command::new(env!("CARGO_BIN_EXE_javaspectre"))
+            .args([sub, "--help"])
+            .output()
+            .unwrap();
+        assert!(out.status.success(), "{} --help failed", sub);
+        let stdout = String::from_utf8_lossy(&out.stdout);
+        assert!(stdout.contains("--format"), "{} --help: {}", sub, stdout);
+    }
+}
@@ -3,6 +3,11 @@ use crate::energy_event::{EnergyEvent, EnergyEventReason};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnergyBalance {
@@ -16,6 +21,10 @@ pub struct LedgerState {
     pub events: Vec<EnergyEvent>,
     pub global_au_cap: f64,
     pub global_csp_cap: f64,
+    /// Newline-delimited JSON file every applied event is appended to; see
+    /// `with_audit_log`. Not part of the serialized state.
+    #[serde(skip)]
+    audit_log: Option<PathBuf>,
 }
 
 impl LedgerState {
@@ -25,9 +34,40 @@ impl LedgerState {
             events: Vec::new(),
             global_au_cap,
             global_csp_cap,
+            audit_log: None,
         }
     }
 
+    /// Mirror every event applied from now on to the append-only file at
+    /// `path`, one JSON object per line. Each line is synced to disk before
+    /// `apply_event` returns, so a crash loses nothing that was committed.
+    pub fn with_audit_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.audit_log = Some(path.into());
+        self
+    }
+
+    /// Replay `events` (e.g. from `read_audit_log`) into a fresh ledger with
+    /// the given caps. Every event must pass the same checks as
+    /// `apply_event` and re-derive to its stored `hash`, so a log that was
+    /// edited, reordered or truncated in the middle is rejected.
+    pub fn rebuild_from_events(
+        global_au_cap: f64,
+        global_csp_cap: f64,
+        events: impl IntoIterator<Item = EnergyEvent>,
+    ) -> Result<Self, String> {
+        let mut state = Self::new(global_au_cap, global_csp_cap);
+        for (idx, ev) in events.into_iter().enumerate() {
+            let (event_id, stored_hash) = (ev.event_id.clone(), ev.hash.clone());
+            state
+                .apply_event(ev)
+                .map_err(|e| format!("event {} ({}): {}", idx, event_id, e))?;
+            if state.events[idx].hash != stored_hash {
+                return Err(format!("event {} ({}) hash mismatch", idx, event_id));
+            }
+        }
+        Ok(state)
+    }
+
     fn compute_hash(prev_hash: &str, payload: &str) -> String {
         let mut hasher = Sha256::new();
         hasher.update(prev_hash.as_bytes());
@@ -35,9 +75,32 @@ impl LedgerState {
         format!("{:x}", hasher.finalize())
     }
 
+    /// Event JSON that feeds the chain hash, with `prev_hash`/`hash` blanked
+    /// so the chain can be re-derived from stored events.
+    fn hash_payload(ev: &EnergyEvent) -> Result<String, String> {
+        let mut unsealed = ev.clone();
+        unsealed.prev_hash.clear();
+        unsealed.hash.clear();
+        serde_json::to_string(&unsealed).map_err(|e| e.to_string())
+    }
+
+    /// Validate `ev` against nonnegativity and the global caps, then chain and
+    /// record it.
+    ///
+    /// Balances are `f64`: whole units are exact up to 2^53, and a delta
+    /// smaller than half an ulp of the running balance is absorbed without
+    /// changing it. Non-finite deltas, and sums that overflow to infinity, are
+    /// rejected before anything is recorded.
     pub fn apply_event(&mut self, mut ev: EnergyEvent) -> Result<(), String> {
+        if !ev.au_et_delta.is_finite() || !ev.csp_delta.is_finite() {
+            return Err(format!(
+                "event {} has a non-finite delta (au_et {}, csp {})",
+                ev.event_id, ev.au_et_delta, ev.csp_delta
+            ));
+        }
+
         let prev_hash = self.events.last().map(|e| e.hash.clone()).unwrap_or_default();
-        let payload = serde_json::to_string(&ev).map_err(|e| e.to_string())?;
+        let payload = Self::hash_payload(&ev)?;
         let hash = Self::compute_hash(&prev_hash, &payload);
 
         ev.prev_hash = prev_hash;
@@ -51,6 +114,13 @@ impl LedgerState {
         let new_au = balance.au_et + ev.au_et_delta;
         let new_csp = balance.csp + ev.csp_delta;
 
+        if !new_au.is_finite() || !new_csp.is_finite() {
+            return Err(format!(
+                "event {} overflows the balance of {}",
+                ev.event_id, ev.agent_id
+            ));
+        }
+
         if new_au < 0.0 || new_csp < 0.0 {
             return Err("Nonnegativity violation".into());
         }
@@ -59,10 +129,508 @@ impl LedgerState {
             return Err("Global cap exceeded".into());
         }
 
+        let previous = balance.clone();
         balance.au_et = new_au;
         balance.csp = new_csp;
         self.events.push(ev);
 
+        // The event is validated and committed in memory; mirror it, undoing
+        // the commit if the log cannot be written. A failed append leaves the
+        // file as it was, so the next event chains from the same `prev_hash`
+        // in memory and on disk.
+        if let Err(e) = self.append_to_audit_log() {
+            let ev = self.events.pop().expect("event was just pushed");
+            self.balances.insert(ev.agent_id, previous);
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    fn append_to_audit_log(&self) -> Result<(), String> {
+        let (Some(path), Some(ev)) = (&self.audit_log, self.events.last()) else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_string(ev).map_err(|e| e.to_string())?;
+        line.push('\n');
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| format!("cannot open audit log {}: {}", path.display(), e))?;
+        append_line(&mut file, line.as_bytes())
+            .map_err(|e| format!("cannot write audit log {}: {}", path.display(), e))
+    }
+
+    pub fn balance_of(&self, agent_id: &str) -> Option<EnergyBalance> {
+        self.balances.get(agent_id).cloned()
+    }
+
+    /// Recompute every link of the event hash chain.
+    pub fn verify_chain(&self) -> Result<(), String> {
+        let mut prev_hash = String::new();
+        for (idx, ev) in self.events.iter().enumerate() {
+            if ev.prev_hash != prev_hash {
+                return Err(format!(
+                    "event {} ({}) has a broken prev_hash link",
+                    idx, ev.event_id
+                ));
+            }
+            if ev.hash != Self::compute_hash(&prev_hash, &Self::hash_payload(ev)?) {
+                return Err(format!("event {} ({}) hash mismatch", idx, ev.event_id));
+            }
+            prev_hash = ev.hash.clone();
+        }
         Ok(())
     }
+
+    /// Events recorded after the one whose hash is `last_hash`, for
+    /// consumers replicating the chain incrementally. The tip hash yields an
+    /// empty slice and the empty genesis hash yields every event.
+    pub fn events_since(&self, last_hash: &str) -> Result<&[EnergyEvent], String> {
+        if last_hash.is_empty() {
+            return Ok(&self.events);
+        }
+        self.events
+            .iter()
+            .position(|ev| ev.hash == last_hash)
+            .map(|idx| &self.events[idx + 1..])
+            .ok_or_else(|| format!("hash {} is not in the event chain", last_hash))
+    }
+
+    /// Root of the Merkle tree over every event's chain `hash`, in ledger
+    /// order; `None` for an empty ledger. Publishing this root lets a single
+    /// event be proven with `inclusion_proof` without disclosing the others.
+    pub fn merkle_root(&self) -> Option<String> {
+        let mut level: Vec<String> = self.events.iter().map(|ev| merkle_leaf(&ev.hash)).collect();
+        while level.len() > 1 {
+            level = merkle_parent_level(&level);
+        }
+        level.pop()
+    }
+
+    /// Sibling hashes from `event_id`'s leaf up to `merkle_root`.
+    pub fn inclusion_proof(&self, event_id: &str) -> Result<MerkleProof, String> {
+        let mut index = self
+            .events
+            .iter()
+            .position(|ev| ev.event_id == event_id)
+            .ok_or_else(|| format!("event {} is not in the ledger", event_id))?;
+        let event_hash = self.events[index].hash.clone();
+
+        let mut siblings = Vec::new();
+        let mut level: Vec<String> = self.events.iter().map(|ev| merkle_leaf(&ev.hash)).collect();
+        while level.len() > 1 {
+            // An unpaired last node is promoted as-is and contributes no step.
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                siblings.push(MerkleStep {
+                    hash: level[sibling].clone(),
+                    side: if sibling < index {
+                        MerkleSide::Left
+                    } else {
+                        MerkleSide::Right
+                    },
+                });
+            }
+            level = merkle_parent_level(&level);
+            index /= 2;
+        }
+
+        Ok(MerkleProof {
+            event_id: event_id.to_string(),
+            event_hash,
+            siblings,
+        })
+    }
+}
+
+/// The audit log as `append_line` uses it; a trait so tests can fail a write
+/// part-way through.
+trait AuditFile: Write {
+    fn len(&self) -> io::Result<u64>;
+    fn sync_data(&self) -> io::Result<()>;
+    fn set_len(&mut self, len: u64) -> io::Result<()>;
+}
+
+impl AuditFile for File {
+    fn len(&self) -> io::Result<u64> {
+        Ok(self.metadata()?.len())
+    }
+
+    fn sync_data(&self) -> io::Result<()> {
+        File::sync_data(self)
+    }
+
+    fn set_len(&mut self, len: u64) -> io::Result<()> {
+        File::set_len(self, len)
+    }
+}
+
+/// Append `line` and sync it. If either step fails, whatever part of the line
+/// reached the file is cut off again, so the log still ends at the last event
+/// that was committed.
+fn append_line(file: &mut impl AuditFile, line: &[u8]) -> io::Result<()> {
+    let len = file.len()?;
+    let written = file.write_all(line).and_then(|()| file.sync_data());
+    if let Err(e) = written {
+        file.set_len(len).map_err(|cut| {
+            io::Error::new(
+                cut.kind(),
+                format!("{}; truncating the log back failed: {}", e, cut),
+            )
+        })?;
+        return Err(e);
+    }
+    Ok(())
+}
+
+/// Events recorded in an audit log written by `LedgerState::with_audit_log`,
+/// in file order, for `LedgerState::rebuild_from_events`. Blank lines are
+/// skipped; a line that is not an event fails with its 1-based number.
+pub fn read_audit_log(path: &Path) -> Result<Vec<EnergyEvent>, String> {
+    let file = std::fs::File::open(path)
+        .map_err(|e| format!("cannot open audit log {}: {}", path.display(), e))?;
+    let mut events = Vec::new();
+    for (idx, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|e| format!("cannot read audit log {}: {}", path.display(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let ev = serde_json::from_str(&line)
+            .map_err(|e| format!("{} line {}: {}", path.display(), idx + 1, e))?;
+        events.push(ev);
+    }
+    Ok(events)
+}
+
+/// Which side of the running hash a proof sibling sits on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MerkleSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleStep {
+    pub hash: String,
+    pub side: MerkleSide,
+}
+
+/// Evidence that the event with `event_hash` is a leaf under a Merkle root;
+/// see `LedgerState::inclusion_proof` and `verify_inclusion`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub event_id: String,
+    /// The event's chain `hash`, which is what the leaf commits to.
+    pub event_hash: String,
+    /// Siblings from the leaf level upwards.
+    pub siblings: Vec<MerkleStep>,
+}
+
+/// Recompute the root from `proof` and compare it with `root`.
+pub fn verify_inclusion(proof: &MerkleProof, root: &str) -> bool {
+    let mut acc = merkle_leaf(&proof.event_hash);
+    for step in &proof.siblings {
+        acc = match step.side {
+            MerkleSide::Left => merkle_node(&step.hash, &acc),
+            MerkleSide::Right => merkle_node(&acc, &step.hash),
+        };
+    }
+    acc == root
+}
+
+// Leaves and inner nodes are hashed under distinct prefixes so an inner node
+// can never be passed off as a leaf.
+fn merkle_leaf(event_hash: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([0u8]);
+    hasher.update(event_hash.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn merkle_node(left: &str, right: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update([1u8]);
+    hasher.update(left.as_bytes());
+    hasher.update(right.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn merkle_parent_level(level: &[String]) -> Vec<String> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => merkle_node(left, right),
+            [single] => single.clone(),
+            _ => unreachable!("chunks(2) yields one or two nodes"),
+        })
+        .collect()
+}
+
+/// Cloneable handle for async, multi-task use. Writes are serialized behind
+/// the lock so each event links to the one applied just before it; reads
+/// share the lock and never observe a half-applied event.
+#[derive(Debug, Clone, Default)]
+pub struct SharedLedger {
+    inner: Arc<RwLock<LedgerState>>,
+}
+
+impl SharedLedger {
+    pub fn new(state: LedgerState) -> Self {
+        Self {
+            inner: Arc::new(RwLock::new(state)),
+        }
+    }
+
+    pub async fn apply_event(&self, ev: EnergyEvent) -> Result<(), String> {
+        self.inner.write().await.apply_event(ev)
+    }
+
+    pub async fn balance_of(&self, agent_id: &str) -> Option<EnergyBalance> {
+        self.inner.read().await.balance_of(agent_id)
+    }
+
+    /// Point-in-time copy of the whole ledger.
+    pub async fn snapshot(&self) -> LedgerState {
+        self.inner.read().await.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(agent_id: &str, seq: usize) -> EnergyEvent {
+        EnergyEvent {
+            event_id: format!("{}-{}", agent_id, seq),
+            vnode_id: "vnode-1".into(),
+            agent_id: agent_id.into(),
+            au_et_delta: 1.0,
+            csp_delta: 0.5,
+            reason: EnergyEventReason::AbilityUse,
+            timestamp: "2024-01-01T00:00:00Z".into(),
+            prev_hash: String::new(),
+            hash: String::new(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn concurrent_writers_keep_the_chain_valid() {
+        let ledger = SharedLedger::new(LedgerState::new(1_000.0, 1_000.0));
+        let tasks: Vec<_> = (0..8)
+            .map(|t| {
+                let ledger = ledger.clone();
+                tokio::spawn(async move {
+                    let agent = format!("agent-{}", t);
+                    for seq in 0..25 {
+                        ledger.apply_event(event(&agent, seq)).await.unwrap();
+                        assert!(ledger.balance_of(&agent).await.is_some());
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let state = ledger.snapshot().await;
+        assert_eq!(state.events.len(), 200);
+        state.verify_chain().unwrap();
+        let balance = ledger.balance_of("agent-3").await.unwrap();
+        assert_eq!(balance.au_et, 25.0);
+        assert_eq!(balance.csp, 12.5);
+    }
+
+    #[test]
+    fn verify_chain_detects_tampering() {
+        let mut state = LedgerState::new(100.0, 100.0);
+        state.apply_event(event("a", 0)).unwrap();
+        state.apply_event(event("a", 1)).unwrap();
+        state.verify_chain().unwrap();
+
+        state.events[0].au_et_delta = 50.0;
+        assert!(state.verify_chain().is_err());
+    }
+
+    #[test]
+    fn non_finite_deltas_are_rejected() {
+        let mut state = LedgerState::new(f64::MAX, f64::MAX);
+        state.apply_event(event("a", 0)).unwrap();
+
+        for (au, csp) in [
+            (f64::INFINITY, 0.0),
+            (0.0, f64::NEG_INFINITY),
+            (f64::NAN, 0.0),
+            (0.0, f64::NAN),
+        ] {
+            let mut ev = event("a", 1);
+            ev.au_et_delta = au;
+            ev.csp_delta = csp;
+            let err = state.apply_event(ev).unwrap_err();
+            assert!(err.contains("non-finite"), "{}", err);
+        }
+
+        let mut huge = event("a", 2);
+        huge.au_et_delta = f64::MAX;
+        state.apply_event(huge.clone()).unwrap();
+        huge.event_id = "a-3".into();
+        let err = state.apply_event(huge).unwrap_err();
+        assert!(err.contains("overflows"), "{}", err);
+
+        assert_eq!(state.events.len(), 2);
+        state.verify_chain().unwrap();
+        assert_eq!(state.balance_of("a").unwrap().au_et, f64::MAX);
+    }
+
+    #[test]
+    fn inclusion_proofs_verify_against_the_merkle_root() {
+        let mut state = LedgerState::new(100.0, 100.0);
+        assert_eq!(state.merkle_root(), None);
+        for seq in 0..5 {
+            state.apply_event(event("a", seq)).unwrap();
+        }
+        let root = state.merkle_root().unwrap();
+
+        for ev in &state.events {
+            let proof = state.inclusion_proof(&ev.event_id).unwrap();
+            assert_eq!(proof.event_hash, ev.hash);
+            assert!(verify_inclusion(&proof, &root), "{}", ev.event_id);
+        }
+
+        let proof = state.inclusion_proof("a-2").unwrap();
+        assert_eq!(proof.siblings.len(), 3);
+        let mut forged = proof.clone();
+        forged.event_hash = state.events[3].hash.clone();
+        assert!(!verify_inclusion(&forged, &root));
+        let mut tampered = proof.clone();
+        tampered.siblings[0].hash = state.events[0].hash.clone();
+        assert!(!verify_inclusion(&tampered, &root));
+
+        // Appending an event moves the root; old proofs verify only against
+        // the root they were issued for.
+        state.apply_event(event("a", 5)).unwrap();
+        assert!(!verify_inclusion(&proof, &state.merkle_root().unwrap()));
+        assert!(verify_inclusion(&proof, &root));
+
+        assert!(state.inclusion_proof("missing").is_err());
+    }
+
+    #[test]
+    fn audit_log_round_trips_through_rebuild() {
+        let path = std::env::temp_dir().join(format!("ledger-audit-{}.ndjson", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut state = LedgerState::new(3.0, 100.0).with_audit_log(&path);
+        for seq in 0..3 {
+            state.apply_event(event("a", seq)).unwrap();
+        }
+        state.apply_event(event("b", 0)).unwrap();
+        // Rejected events never reach the log.
+        assert!(state.apply_event(event("a", 3)).is_err());
+
+        let logged = read_audit_log(&path).unwrap();
+        assert_eq!(logged.len(), 4);
+        let rebuilt = LedgerState::rebuild_from_events(3.0, 100.0, logged.clone()).unwrap();
+        assert_eq!(rebuilt.merkle_root(), state.merkle_root());
+        assert_eq!(rebuilt.balance_of("a").unwrap().au_et, 3.0);
+        assert_eq!(rebuilt.balance_of("b").unwrap().csp, 0.5);
+        rebuilt.verify_chain().unwrap();
+
+        let mut tampered = logged;
+        tampered[1].au_et_delta = 0.5;
+        let err = LedgerState::rebuild_from_events(3.0, 100.0, tampered).unwrap_err();
+        assert!(err.contains("hash mismatch"), "{}", err);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    /// In-memory audit file that accepts `room` more bytes, then fails.
+    struct FlakyFile {
+        data: Vec<u8>,
+        room: usize,
+        fail_sync: bool,
+    }
+
+    impl Write for FlakyFile {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.room == 0 {
+                return Err(io::Error::other("disk full"));
+            }
+            let n = buf.len().min(self.room);
+            self.data.extend_from_slice(&buf[..n]);
+            self.room -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl AuditFile for FlakyFile {
+        fn len(&self) -> io::Result<u64> {
+            Ok(self.data.len() as u64)
+        }
+
+        fn sync_data(&self) -> io::Result<()> {
+            if self.fail_sync {
+                return Err(io::Error::other("sync failed"));
+            }
+            Ok(())
+        }
+
+        fn set_len(&mut self, len: u64) -> io::Result<()> {
+            self.data.truncate(len as usize);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn failed_appends_leave_the_log_at_the_last_event() {
+        let committed = b"{\"event_id\":\"a-0\"}\n".to_vec();
+
+        // A write that runs out of room part-way through the line.
+        let mut partial = FlakyFile {
+            data: committed.clone(),
+            room: 5,
+            fail_sync: false,
+        };
+        assert!(append_line(&mut partial, b"{\"event_id\":\"a-1\"}\n").is_err());
+        assert_eq!(partial.data, committed);
+
+        // A complete write whose sync fails.
+        let mut unsynced = FlakyFile {
+            data: committed.clone(),
+            room: usize::MAX,
+            fail_sync: true,
+        };
+        let err = append_line(&mut unsynced, b"{\"event_id\":\"a-1\"}\n").unwrap_err();
+        assert!(err.to_string().contains("sync failed"), "{}", err);
+        assert_eq!(unsynced.data, committed);
+    }
+
+    #[test]
+    fn events_since_returns_the_suffix_after_a_known_hash() {
+        let mut state = LedgerState::new(100.0, 100.0);
+        for seq in 0..4 {
+            state.apply_event(event("a", seq)).unwrap();
+        }
+
+        let mid = state.events[1].hash.clone();
+        let ids: Vec<&str> = state
+            .events_since(&mid)
+            .unwrap()
+            .iter()
+            .map(|ev| ev.event_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["a-2", "a-3"]);
+
+        let tip = state.events.last().unwrap().hash.clone();
+        assert!(state.events_since(&tip).unwrap().is_empty());
+        assert_eq!(state.events_since("").unwrap().len(), 4);
+
+        let err = state.events_since("deadbeef").unwrap_err();
+        assert!(err.contains("deadbeef"), "{}", err);
+    }
 }
@@ -1,7 +1,16 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::io::{BufReader, Read};
 use std::path::{Path, PathBuf};
 
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+/// `schemaVersion` of the current registry layout; legacy files are upgraded to it.
+pub const CURRENT_SCHEMA_VERSION: &str = "javaspectre-1.0";
 
 /// Top-level registry structure mirroring `patterns/registry.json`.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +23,9 @@ pub struct PatternRegistry {
     pub patterns: Vec<Pattern>,
     #[serde(default)]
     pub metadata: RegistryMetadata,
+    /// id -> position in `patterns`, rebuilt by `backfill_metadata`.
+    #[serde(skip)]
+    index: HashMap<String, usize>,
 }
 
 /// Individual pattern entry.
@@ -99,12 +111,16 @@ pub enum RegistryError {
         source: serde_json::Error,
     },
 
+    #[error("Failed to parse registry JSON stream: {0}")]
+    Stream(#[source] serde_json::Error),
+
     #[error("Registry validation failed: {0}")]
     Validation(String),
 }
 
 impl PatternRegistry {
-    /// Load and validate a registry from the given path.
+    /// Load and validate a registry from the given path, upgrading legacy
+    /// layouts first (see `upgrade_legacy_layout`).
     ///
     /// This enforces:
     /// - Non-empty `id` and `path` for each pattern.
@@ -117,25 +133,169 @@ impl PatternRegistry {
             source,
         })?;
 
+        let json_err = |source| RegistryError::Json {
+            path: path_ref.to_path_buf(),
+            source,
+        };
+        let raw: Value = serde_json::from_str(&contents).map_err(json_err)?;
         let mut registry: PatternRegistry =
-            serde_json::from_str(&contents).map_err(|source| RegistryError::Json {
-                path: path_ref.to_path_buf(),
-                source,
-            })?;
+            serde_json::from_value(upgrade_legacy_layout(raw)).map_err(json_err)?;
 
         registry.backfill_metadata();
         registry.validate()?;
         Ok(registry)
     }
 
+    /// Validate a registry read from `reader` without holding all of its
+    /// patterns in memory: each pattern is deserialized, checked and dropped,
+    /// keeping only the set of ids and any dependencies that referred forward
+    /// when read. Applies the same checks as `load_from_path` and, for the
+    /// current and bare-array layouts, reports the same first error. Returns
+    /// the number of patterns checked.
+    pub fn validate_streaming<R: Read>(reader: R) -> Result<usize, RegistryError> {
+        let mut check = StreamingCheck::default();
+        let mut de = serde_json::Deserializer::from_reader(BufReader::new(reader));
+        let version = RegistrySeed(&mut check)
+            .deserialize(&mut de)
+            .map_err(RegistryError::Stream)?;
+        de.end().map_err(RegistryError::Stream)?;
+        check.finish(&version)
+    }
+
+    /// Write the registry as pretty-printed JSON (the layout `load_from_path`
+    /// reads back without upgrading).
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), RegistryError> {
+        let path_ref = path.as_ref();
+        let mut json =
+            serde_json::to_string_pretty(self).map_err(|source| RegistryError::Json {
+                path: path_ref.to_path_buf(),
+                source,
+            })?;
+        json.push('\n');
+        fs::write(path_ref, json).map_err(|source| RegistryError::Io {
+            path: path_ref.to_path_buf(),
+            source,
+        })
+    }
+
+    /// Recompute every pattern's `hash` as `sha256:<hex>` over the file at
+    /// `base_dir/<path>`, returning how many hashes changed. Patterns whose
+    /// file is missing keep their hash and are reported on stderr.
+    pub fn recompute_hashes<P: AsRef<Path>>(
+        &mut self,
+        base_dir: P,
+    ) -> Result<usize, RegistryError> {
+        let base_dir = base_dir.as_ref();
+        let mut changed = 0;
+        for pattern in &mut self.patterns {
+            let file = base_dir.join(&pattern.path);
+            let bytes = match fs::read(&file) {
+                Ok(bytes) => bytes,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    eprintln!(
+                        "warning: pattern '{}': {} not found; hash left unchanged",
+                        pattern.id,
+                        file.display()
+                    );
+                    continue;
+                }
+                Err(source) => return Err(RegistryError::Io { path: file, source }),
+            };
+            let hash = format!("sha256:{}", hex::encode(Sha256::digest(&bytes)));
+            if pattern.hash != hash {
+                pattern.hash = hash;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
     /// Lightweight accessor to get a pattern by ID.
+    ///
+    /// Uses the id index built at load time; falls back to a linear scan if
+    /// `patterns` was modified after loading and the index is stale.
     pub fn get_pattern(&self, id: &str) -> Option<&Pattern> {
-        self.patterns.iter().find(|p| p.id == id)
+        match self.index.get(id).and_then(|&i| self.patterns.get(i)) {
+            Some(p) if p.id == id => Some(p),
+            _ => self.patterns.iter().find(|p| p.id == id),
+        }
+    }
+
+    /// Batch lookup; the result is positionally aligned with `ids`.
+    pub fn get_patterns(&self, ids: &[&str]) -> Vec<Option<&Pattern>> {
+        ids.iter().map(|id| self.get_pattern(id)).collect()
+    }
+
+    /// Whether a pattern with this ID is registered.
+    pub fn contains(&self, id: &str) -> bool {
+        self.get_pattern(id).is_some()
+    }
+
+    /// Resolve a pattern's `entrypoint` to an existing file under `base_dir`.
+    ///
+    /// The entrypoint is first taken relative to the directory of the
+    /// pattern's `path`, then relative to `base_dir` itself (registry.json
+    /// stores repo-relative entrypoints). The canonical result must stay
+    /// inside `base_dir`, so `..` segments and symlinks cannot escape it.
+    pub fn resolve_entrypoint<P: AsRef<Path>>(
+        &self,
+        id: &str,
+        base_dir: P,
+    ) -> Result<PathBuf, RegistryError> {
+        let pattern = self
+            .get_pattern(id)
+            .ok_or_else(|| RegistryError::Validation(format!("unknown pattern id '{}'", id)))?;
+        if pattern.entrypoint.trim().is_empty() {
+            return Err(RegistryError::Validation(format!(
+                "pattern '{}' has no entrypoint",
+                id
+            )));
+        }
+
+        let base_dir = base_dir.as_ref();
+        let pattern_dir = Path::new(&pattern.path).parent().unwrap_or(Path::new(""));
+        let candidate = [
+            base_dir.join(pattern_dir).join(&pattern.entrypoint),
+            base_dir.join(&pattern.entrypoint),
+        ]
+        .into_iter()
+        .find(|p| p.is_file())
+        .ok_or_else(|| {
+            RegistryError::Validation(format!(
+                "entrypoint '{}' for pattern '{}' does not exist under {}",
+                pattern.entrypoint,
+                id,
+                base_dir.display()
+            ))
+        })?;
+
+        let io_err = |path: &Path| {
+            let path = path.to_path_buf();
+            move |source| RegistryError::Io { path, source }
+        };
+        let base = base_dir.canonicalize().map_err(io_err(base_dir))?;
+        let resolved = candidate.canonicalize().map_err(io_err(&candidate))?;
+        if !resolved.starts_with(&base) {
+            return Err(RegistryError::Validation(format!(
+                "entrypoint '{}' for pattern '{}' escapes {}",
+                pattern.entrypoint,
+                id,
+                base_dir.display()
+            )));
+        }
+        Ok(resolved)
     }
 
     /// Ensure metadata fields are consistent with the patterns list
     /// even if they were omitted or out of date in the JSON file.
     fn backfill_metadata(&mut self) {
+        self.index = self
+            .patterns
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.id.clone(), i))
+            .collect();
+
         self.metadata.totalPatterns = self.patterns.len();
         self.metadata.categories = {
             let mut cats: Vec<String> = self
@@ -170,36 +330,16 @@ impl PatternRegistry {
         }
 
         // Validate each pattern and enforce uniqueness.
-        let mut seen_ids = std::collections::HashSet::new();
+        let mut seen_ids = HashSet::new();
         for pattern in &self.patterns {
-            if pattern.id.trim().is_empty() {
-                return Err(RegistryError::Validation(
-                    "pattern.id must not be empty".into(),
-                ));
-            }
-            if pattern.path.trim().is_empty() {
-                return Err(RegistryError::Validation(format!(
-                    "pattern.path must not be empty for id '{}'",
-                    pattern.id
-                )));
-            }
-
-            if !seen_ids.insert(pattern.id.clone()) {
-                return Err(RegistryError::Validation(format!(
-                    "duplicate pattern id '{}'",
-                    pattern.id
-                )));
-            }
+            check_pattern(pattern, &mut seen_ids)?;
         }
 
         // Dependency integrity.
         for pattern in &self.patterns {
             for dep in &pattern.dependencies {
                 if !seen_ids.contains(dep) {
-                    return Err(RegistryError::Validation(format!(
-                        "pattern '{}' depends on unknown pattern id '{}'",
-                        pattern.id, dep
-                    )));
+                    return Err(unknown_dependency(&pattern.id, dep));
                 }
             }
         }
@@ -207,3 +347,435 @@ impl PatternRegistry {
         Ok(())
     }
 }
+
+/// Per-pattern checks shared by `validate` and `validate_streaming`:
+/// non-empty `id` and `path`, and an `id` not already in `seen_ids`.
+fn check_pattern(pattern: &Pattern, seen_ids: &mut HashSet<String>) -> Result<(), RegistryError> {
+    if pattern.id.trim().is_empty() {
+        return Err(RegistryError::Validation(
+            "pattern.id must not be empty".into(),
+        ));
+    }
+    if pattern.path.trim().is_empty() {
+        return Err(RegistryError::Validation(format!(
+            "pattern.path must not be empty for id '{}'",
+            pattern.id
+        )));
+    }
+
+    if !seen_ids.insert(pattern.id.clone()) {
+        return Err(RegistryError::Validation(format!(
+            "duplicate pattern id '{}'",
+            pattern.id
+        )));
+    }
+    Ok(())
+}
+
+fn unknown_dependency(id: &str, dep: &str) -> RegistryError {
+    RegistryError::Validation(format!(
+        "pattern '{}' depends on unknown pattern id '{}'",
+        id, dep
+    ))
+}
+
+/// Running state of `validate_streaming`. Like `validate`, per-pattern
+/// errors take precedence over dependency errors, so the first one is kept
+/// and reported only after the whole stream has parsed.
+#[derive(Default)]
+struct StreamingCheck {
+    seen_ids: HashSet<String>,
+    /// `(pattern id, dependency)` pairs whose dependency was not yet seen.
+    forward_deps: Vec<(String, String)>,
+    first_error: Option<RegistryError>,
+    count: usize,
+}
+
+impl StreamingCheck {
+    fn check(&mut self, pattern: Pattern) {
+        self.count += 1;
+        if self.first_error.is_some() {
+            return;
+        }
+        if let Err(e) = check_pattern(&pattern, &mut self.seen_ids) {
+            self.first_error = Some(e);
+            return;
+        }
+        for dep in pattern.dependencies {
+            if !self.seen_ids.contains(&dep) {
+                self.forward_deps.push((pattern.id.clone(), dep));
+            }
+        }
+    }
+
+    fn finish(self, version: &str) -> Result<usize, RegistryError> {
+        if version.trim().is_empty() {
+            return Err(RegistryError::Validation(
+                "registry.version must not be empty".into(),
+            ));
+        }
+        if let Some(e) = self.first_error {
+            return Err(e);
+        }
+        for (id, dep) in &self.forward_deps {
+            if !self.seen_ids.contains(dep) {
+                return Err(unknown_dependency(id, dep));
+            }
+        }
+        Ok(self.count)
+    }
+}
+
+/// Top level of a streamed registry: an object with `version` and
+/// `patterns`, or a legacy bare array of patterns. Yields the version.
+struct RegistrySeed<'a>(&'a mut StreamingCheck);
+
+impl<'de> DeserializeSeed<'de> for RegistrySeed<'_> {
+    type Value = String;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<String, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for RegistrySeed<'_> {
+    type Value = String;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a pattern registry object or an array of patterns")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<String, A::Error> {
+        PatternsSeed(self.0).visit_seq(seq)?;
+        Ok("0.0.0".into())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<String, A::Error> {
+        let mut version = None;
+        let mut saw_patterns = false;
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => version = Some(map.next_value::<String>()?),
+                "patterns" => {
+                    map.next_value_seed(PatternsSeed(&mut *self.0))?;
+                    saw_patterns = true;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        if !saw_patterns {
+            return Err(de::Error::missing_field("patterns"));
+        }
+        version.ok_or_else(|| de::Error::missing_field("version"))
+    }
+}
+
+/// The `patterns` value, as a list or a legacy `{id: pattern}` object.
+/// Keyed entries are checked in file order rather than `load_from_path`'s
+/// sorted order, so with several bad entries a different one may be named.
+struct PatternsSeed<'a>(&'a mut StreamingCheck);
+
+impl<'de> DeserializeSeed<'de> for PatternsSeed<'_> {
+    type Value = ();
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PatternsSeed<'_> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("an array or id-keyed object of patterns")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(pattern) = seq.next_element::<Pattern>()? {
+            self.0.check(pattern);
+        }
+        Ok(())
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        while let Some((id, mut pattern)) = map.next_entry::<String, Value>()? {
+            if let Value::Object(fields) = &mut pattern {
+                fields.entry("id").or_insert_with(|| Value::String(id));
+            }
+            self.0
+                .check(Pattern::deserialize(pattern).map_err(de::Error::custom)?);
+        }
+        Ok(())
+    }
+}
+
+/// Rewrite older registry shapes into the current one before deserializing:
+/// - a bare array of patterns is wrapped as `{"version": "0.0.0", "patterns": [...]}`;
+/// - `patterns` given as an `{id: pattern}` object becomes a list, ids taken
+///   from the keys (sorted, for a deterministic order);
+/// - a snake_case `schema_version`, or none at all, becomes `schemaVersion`
+///   (defaulting to `CURRENT_SCHEMA_VERSION`);
+/// - a missing `metadata` block is synthesized; `backfill_metadata` fills it.
+fn upgrade_legacy_layout(raw: Value) -> Value {
+    let mut root = match raw {
+        Value::Array(patterns) => {
+            let mut root = Map::new();
+            root.insert("version".into(), Value::String("0.0.0".into()));
+            root.insert("patterns".into(), Value::Array(patterns));
+            root
+        }
+        Value::Object(root) => root,
+        other => return other,
+    };
+
+    if let Some(Value::Object(by_id)) = root.get("patterns") {
+        let mut ids: Vec<&String> = by_id.keys().collect();
+        ids.sort();
+        let patterns: Vec<Value> = ids
+            .into_iter()
+            .map(|id| {
+                let mut pattern = by_id[id].clone();
+                if let Value::Object(fields) = &mut pattern {
+                    fields
+                        .entry("id")
+                        .or_insert_with(|| Value::String(id.clone()));
+                }
+                pattern
+            })
+            .collect();
+        root.insert("patterns".into(), Value::Array(patterns));
+    }
+
+    if !root.contains_key("schemaVersion") {
+        let schema_version = root
+            .remove("schema_version")
+            .unwrap_or_else(|| Value::String(CURRENT_SCHEMA_VERSION.into()));
+        root.insert("schemaVersion".into(), schema_version);
+    }
+    root.entry("metadata")
+        .or_insert_with(|| Value::Object(Map::new()));
+    Value::Object(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_registry(tag: &str, json: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("pattern-registry-{}-{}", tag, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("registry.json");
+        fs::write(&path, json).unwrap();
+        path
+    }
+
+    fn sample_json() -> String {
+        let patterns: Vec<String> = (0..50)
+            .map(|i| {
+                format!(
+                    r#"{{"id": "p{i}", "title": "Pattern {i}", "category": "c{}", "path": "patterns/p{i}.md"}}"#,
+                    i % 3
+                )
+            })
+            .collect();
+        format!(
+            r#"{{"version": "1.0.0", "patterns": [{}]}}"#,
+            patterns.join(",")
+        )
+    }
+
+    #[test]
+    fn streaming_validation_matches_in_memory_validation() {
+        let large = |edit: &dyn Fn(usize, &mut Value)| {
+            let patterns: Vec<Value> = (0..20_000)
+                .map(|i| {
+                    let mut p = serde_json::json!({
+                        "id": format!("p{i}"),
+                        "title": format!("Pattern {i}"),
+                        "category": format!("c{}", i % 7),
+                        "path": format!("patterns/p{i}.md"),
+                        // One backward and one forward dependency per pattern.
+                        "dependencies": [format!("p{}", i / 2), format!("p{}", (i + 1) % 20_000)]
+                    });
+                    edit(i, &mut p);
+                    p
+                })
+                .collect();
+            serde_json::json!({"version": "1.0.0", "patterns": patterns}).to_string()
+        };
+        let cases = [
+            ("valid", large(&|_, _| {})),
+            ("duplicate", large(&|i, p| {
+                if i == 15_000 {
+                    p["id"] = "p42".into();
+                }
+            })),
+            ("unknown-dep", large(&|i, p| {
+                if i == 12_345 {
+                    p["dependencies"] = serde_json::json!(["p1", "ghost"]);
+                }
+            })),
+            ("empty-path-and-ghost", large(&|i, p| {
+                if i == 10 {
+                    p["dependencies"] = serde_json::json!(["ghost"]);
+                }
+                if i == 19_999 {
+                    p["path"] = "".into();
+                }
+            })),
+        ];
+
+        for (tag, json) in &cases {
+            let path = write_registry(&format!("stream-{}", tag), json);
+            let in_memory = PatternRegistry::load_from_path(&path);
+            let streamed = PatternRegistry::validate_streaming(fs::File::open(&path).unwrap());
+            match (in_memory, streamed) {
+                (Ok(registry), Ok(count)) => {
+                    assert_eq!(*tag, "valid");
+                    assert_eq!(count, registry.patterns.len());
+                }
+                (Err(expected), Err(actual)) => {
+                    assert_eq!(actual.to_string(), expected.to_string(), "{}", tag)
+                }
+                (expected, actual) => panic!(
+                    "{}: in-memory {:?} vs streaming {:?}",
+                    tag,
+                    expected.map(|_| ()),
+                    actual
+                ),
+            }
+        }
+
+        let bare = r#"[{"id": "x", "title": "X", "category": "c", "path": "p.md", "dependencies": ["x"]}]"#;
+        assert_eq!(PatternRegistry::validate_streaming(bare.as_bytes()).unwrap(), 1);
+        assert!(matches!(
+            PatternRegistry::validate_streaming(r#"{"version": "1"}"#.as_bytes()),
+            Err(RegistryError::Stream(_))
+        ));
+    }
+
+    #[test]
+    fn indexed_lookup_matches_linear_scan() {
+        let registry =
+            PatternRegistry::load_from_path(write_registry("index", &sample_json())).unwrap();
+
+        for id in ["p0", "p17", "p49", "missing"] {
+            let linear = registry.patterns.iter().find(|p| p.id == id);
+            assert_eq!(
+                registry.get_pattern(id).map(|p| &p.id),
+                linear.map(|p| &p.id)
+            );
+            assert_eq!(registry.contains(id), linear.is_some());
+        }
+
+        let batch = registry.get_patterns(&["p3", "nope", "p4"]);
+        assert_eq!(batch.len(), 3);
+        assert_eq!(batch[0].unwrap().id, "p3");
+        assert!(batch[1].is_none());
+        assert_eq!(batch[2].unwrap().id, "p4");
+    }
+
+    #[test]
+    fn legacy_registry_layouts_upgrade_cleanly() {
+        let keyed = r#"{
+            "version": "0.9.0",
+            "patterns": {
+                "b": {"title": "B", "category": "c2", "path": "patterns/b.md", "dependencies": ["a"]},
+                "a": {"title": "A", "category": "c1", "path": "patterns/a.md", "stability": "stable"}
+            }
+        }"#;
+        let registry =
+            PatternRegistry::load_from_path(write_registry("legacy-keyed", keyed)).unwrap();
+        assert_eq!(registry.version, "0.9.0");
+        assert_eq!(registry.schemaVersion, CURRENT_SCHEMA_VERSION);
+        let ids: Vec<&str> = registry.patterns.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(registry.metadata.totalPatterns, 2);
+        assert_eq!(registry.metadata.categories, ["c1", "c2"]);
+        assert_eq!(registry.metadata.stabilityBreakdown.stable, 1);
+        assert!(registry.contains("b"));
+
+        let bare = r#"[{"id": "x", "title": "X", "category": "c", "path": "patterns/x.md"}]"#;
+        let registry =
+            PatternRegistry::load_from_path(write_registry("legacy-bare", bare)).unwrap();
+        assert_eq!(registry.version, "0.0.0");
+        assert_eq!(registry.get_pattern("x").unwrap().title, "X");
+
+        let snake = r#"{"version": "1.0.0", "schema_version": "javaspectre-0.9", "patterns": []}"#;
+        let registry =
+            PatternRegistry::load_from_path(write_registry("legacy-snake", snake)).unwrap();
+        assert_eq!(registry.schemaVersion, "javaspectre-0.9");
+    }
+
+    #[test]
+    fn resolve_entrypoint_stays_inside_base_dir() {
+        let root =
+            std::env::temp_dir().join(format!("pattern-registry-entry-{}", std::process::id()));
+        let base = root.join("repo");
+        fs::create_dir_all(base.join("patterns/demo")).unwrap();
+        fs::write(base.join("patterns/demo/impl.js"), "run()").unwrap();
+        fs::write(root.join("outside.js"), "escape()").unwrap();
+
+        let json = r#"{"version": "1.0.0", "patterns": [
+            {"id": "local", "title": "L", "category": "c", "path": "patterns/demo/pattern.md", "entrypoint": "impl.js"},
+            {"id": "repo", "title": "R", "category": "c", "path": "patterns/demo/pattern.md", "entrypoint": "patterns/demo/impl.js"},
+            {"id": "escape", "title": "E", "category": "c", "path": "patterns/demo/pattern.md", "entrypoint": "../../../outside.js"},
+            {"id": "none", "title": "N", "category": "c", "path": "patterns/demo/pattern.md"}
+        ]}"#;
+        let registry = PatternRegistry::load_from_path(write_registry("entry", json)).unwrap();
+
+        let expected = base.join("patterns/demo/impl.js").canonicalize().unwrap();
+        assert_eq!(
+            registry.resolve_entrypoint("local", &base).unwrap(),
+            expected
+        );
+        assert_eq!(
+            registry.resolve_entrypoint("repo", &base).unwrap(),
+            expected
+        );
+
+        let err = registry.resolve_entrypoint("escape", &base).unwrap_err();
+        assert!(err.to_string().contains("escapes"), "{}", err);
+        assert!(registry.resolve_entrypoint("none", &base).is_err());
+        assert!(registry.resolve_entrypoint("missing", &base).is_err());
+    }
+
+    #[test]
+    fn recompute_hashes_tracks_edited_files() {
+        let base =
+            std::env::temp_dir().join(format!("pattern-registry-hashes-{}", std::process::id()));
+        fs::create_dir_all(base.join("patterns")).unwrap();
+        fs::write(base.join("patterns/a.md"), "alpha").unwrap();
+        fs::write(base.join("patterns/b.md"), "beta").unwrap();
+        let json = r#"{"version": "1.0.0", "patterns": [
+            {"id": "a", "title": "A", "category": "c", "path": "patterns/a.md"},
+            {"id": "b", "title": "B", "category": "c", "path": "patterns/b.md"},
+            {"id": "gone", "title": "G", "category": "c", "path": "patterns/gone.md", "hash": "sha256:old"}
+        ]}"#;
+        let path = write_registry("hashes", json);
+        let mut registry = PatternRegistry::load_from_path(&path).unwrap();
+
+        assert_eq!(registry.recompute_hashes(&base).unwrap(), 2);
+        assert_eq!(registry.recompute_hashes(&base).unwrap(), 0);
+        let before = registry.get_pattern("a").unwrap().hash.clone();
+        assert_eq!(
+            before,
+            format!("sha256:{}", hex::encode(Sha256::digest(b"alpha")))
+        );
+
+        fs::write(base.join("patterns/a.md"), "alpha, edited").unwrap();
+        assert_eq!(registry.recompute_hashes(&base).unwrap(), 1);
+        assert_ne!(registry.get_pattern("a").unwrap().hash, before);
+        assert_eq!(registry.get_pattern("gone").unwrap().hash, "sha256:old");
+
+        registry.save_to_path(&path).unwrap();
+        let reloaded = PatternRegistry::load_from_path(&path).unwrap();
+        assert_eq!(
+            reloaded.get_pattern("a").unwrap().hash,
+            registry.get_pattern("a").unwrap().hash
+        );
+    }
+}
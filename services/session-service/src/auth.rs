@@ -0,0 +1,10 @@
+// services/session-service/src/auth.rs
+// Verification of the client's auth assertion. Everything keyed by caller
+// (idempotency keys, rate-limit buckets, ledger charges) uses the identity
+// returned here, never anything taken from the request body.
+
+/// Checks a WebAuthn/FIDO2 assertion with the external verifier.
+pub trait AssertionVerifier {
+    /// The verified agent id, or why the assertion was rejected.
+    fn verify(&self, assertion: &str) -> Result<String, String>;
+}
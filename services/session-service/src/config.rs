@@ -0,0 +1,15 @@
+// services/session-service/src/config.rs
+use crate::rate_limit::RateLimitConfig;
+use serde::Deserialize;
+
+/// Service settings, read from the config file at startup.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Config {
+    /// Mirror URIs advertised in every SessionTicket.
+    #[serde(default)]
+    pub mirrors: Vec<String>,
+    /// Per-agent bound on session creation; defaults apply when the section
+    /// is absent.
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+}
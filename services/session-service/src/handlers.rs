@@ -1,34 +1,96 @@
 // services/session-service/src/handlers.rs
 use crate::tokens::MintedToken;
+use crate::auth::AssertionVerifier;
 use crate::config::Config;
-use crate::ledger::LedgerHandle;
+use crate::idempotency::IdempotencyStore;
+use crate::rate_limit::RateLimiter;
+use ledger_core::energy_event::{EnergyEvent, EnergyEventReason};
+use ledger_core::ids::IdSource;
+use ledger_core::ledger_state::SharedLedger;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::time::Duration;
+
+/// AU.ET / CSP charged to the ledger for each newly minted session.
+const SESSION_AU_ET_COST: f64 = 1.0;
+const SESSION_CSP_COST: f64 = 0.5;
 
 #[derive(Debug, Deserialize)]
 pub struct SessionRequest {
     pub vnode_profile: serde_json::Value,
     pub requested_abilities: Vec<String>,
     pub auth_assertion: String, // abstract WebAuthn/FIDO2 assertion
+    /// Client-chosen key; a retry by the same agent carrying the same key
+    /// within the store's TTL gets the original response back and is not
+    /// charged again.
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SessionResponse {
     pub session_ticket: serde_json::Value,
     pub access_token: MintedToken,
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionError {
+    /// The auth assertion did not verify.
+    Unauthorized(String),
+    /// The agent has used up its request budget; retry after the given delay.
+    RateLimited { retry_after: Duration },
+    /// The ledger rejected the session charge.
+    Ledger(String),
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SessionError::Unauthorized(e) => write!(f, "unauthorized: {}", e),
+            SessionError::RateLimited { retry_after } => write!(
+                f,
+                "rate limited; retry after {}s",
+                retry_after.as_secs_f64().ceil()
+            ),
+            SessionError::Ledger(e) => write!(f, "ledger error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
 pub async fn create_session(
     cfg: &Config,
-    ledger: &mut LedgerHandle,
+    ledger: &SharedLedger,
+    verifier: &(dyn AssertionVerifier + Sync),
+    ids: &mut (dyn IdSource + Send),
+    keys: &mut (dyn IdempotencyStore + Send),
+    limiter: &mut (dyn RateLimiter + Send),
     req: SessionRequest,
-) -> Result<SessionResponse, String> {
+) -> Result<SessionResponse, SessionError> {
     // 1. Validate auth_assertion externally (FIDO2/WebAuthn service)
+    let agent_id = verifier
+        .verify(&req.auth_assertion)
+        .map_err(SessionError::Unauthorized)?;
+
+    // Looked up only once the caller is known, and only among its own keys.
+    // A replayed response is not a new session, so it costs no token.
+    let now = limiter.now();
+    if let Some(key) = req.idempotency_key.as_deref() {
+        if let Some(previous) = keys.get(&agent_id, key, now) {
+            return Ok(previous);
+        }
+    }
+    limiter
+        .check(&agent_id)
+        .map_err(|retry_after| SessionError::RateLimited { retry_after })?;
+
     // 2. Check AU.ET/CSP in ledger
     // 3. If allowed, mint scoped token and SessionTicket JSON (using protocol schemas)
 
     // Placeholder token
     let token = MintedToken {
-        token: "opaque-oauth-like-token".into(),
+        token: ids.next(),
         expires_at: "2025-01-01T00:00:00Z".into(),
         scope: vec!["repo:read".into(), "repo:write".into()],
         vnode_id: "vnode-123".into(),
@@ -36,13 +98,13 @@ pub async fn create_session(
 
     // Placeholder SessionTicket
     let ticket = serde_json::json!({
-      "ticket_id": "ticket-abc",
+      "ticket_id": ids.next(),
       "vnode_id": "vnode-123",
       "issued_at": "2025-01-01T00:00:00Z",
       "expires_at": "2025-01-01T12:00:00Z",
       "auth_binding": {
         "method": "WebAuthn",
-        "subject": "user@example.com"
+        "subject": agent_id
       },
       "au_et_limit": 100.0,
       "csp_limit": 50.0,
@@ -50,8 +112,256 @@ pub async fn create_session(
       "mirrors": cfg.mirrors
     });
 
-    Ok(SessionResponse {
+    ledger
+        .apply_event(EnergyEvent {
+            event_id: ids.next(),
+            vnode_id: "vnode-123".into(),
+            agent_id: agent_id.clone(),
+            au_et_delta: SESSION_AU_ET_COST,
+            csp_delta: SESSION_CSP_COST,
+            reason: EnergyEventReason::AbilityUse,
+            timestamp: "2025-01-01T00:00:00Z".into(),
+            prev_hash: String::new(),
+            hash: String::new(),
+        })
+        .await
+        .map_err(SessionError::Ledger)?;
+
+    let response = SessionResponse {
         session_ticket: ticket,
         access_token: token,
-    })
+    };
+    if let Some(key) = req.idempotency_key {
+        keys.put(agent_id, key, response.clone(), now);
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::idempotency::InMemoryIdempotencyStore;
+    use crate::rate_limit::{Clock, RateLimitConfig, TokenBucketLimiter};
+    use ledger_core::ids::SeededIdSource;
+    use ledger_core::ledger_state::LedgerState;
+    use std::sync::{Arc, Mutex};
+    use std::time::Instant;
+
+    /// Clock the test advances by hand; clones share the same time.
+    #[derive(Debug, Clone)]
+    struct ManualClock(Arc<Mutex<Instant>>);
+
+    impl ManualClock {
+        fn new() -> Self {
+            Self(Arc::new(Mutex::new(Instant::now())))
+        }
+
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    /// Accepts any non-empty assertion as the id of the agent it names.
+    struct NamedAgentVerifier;
+
+    impl AssertionVerifier for NamedAgentVerifier {
+        fn verify(&self, assertion: &str) -> Result<String, String> {
+            if assertion.is_empty() {
+                return Err("empty assertion".into());
+            }
+            Ok(assertion.to_string())
+        }
+    }
+
+    fn config(capacity: u32, refill_per_sec: f64) -> Config {
+        Config {
+            rate_limit: RateLimitConfig {
+                capacity,
+                refill_per_sec,
+            },
+            ..Config::default()
+        }
+    }
+
+    /// Service state threaded through `create_session`.
+    struct Harness {
+        cfg: Config,
+        ledger: SharedLedger,
+        ids: SeededIdSource,
+        keys: InMemoryIdempotencyStore,
+        limits: TokenBucketLimiter<ManualClock>,
+        clock: ManualClock,
+    }
+
+    impl Harness {
+        /// The limiter is built from `cfg.rate_limit`, as the service does.
+        fn new(cfg: Config) -> Self {
+            let clock = ManualClock::new();
+            Self {
+                limits: TokenBucketLimiter::new(cfg.rate_limit.clone(), clock.clone()),
+                cfg,
+                ledger: SharedLedger::new(LedgerState::new(1_000.0, 1_000.0)),
+                ids: SeededIdSource::new(7),
+                keys: InMemoryIdempotencyStore::new(Duration::from_secs(600)),
+                clock,
+            }
+        }
+
+        async fn create(&mut self, req: SessionRequest) -> Result<SessionResponse, SessionError> {
+            create_session(
+                &self.cfg,
+                &self.ledger,
+                &NamedAgentVerifier,
+                &mut self.ids,
+                &mut self.keys,
+                &mut self.limits,
+                req,
+            )
+            .await
+        }
+
+        async fn charges(&self) -> usize {
+            self.ledger.snapshot().await.events.len()
+        }
+    }
+
+    fn request(agent: &str, key: Option<&str>) -> SessionRequest {
+        SessionRequest {
+            vnode_profile: serde_json::json!({}),
+            requested_abilities: vec!["repo:read".into()],
+            auth_assertion: agent.into(),
+            idempotency_key: key.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn repeated_idempotency_key_returns_same_token_and_charges_once() {
+        let mut h = Harness::new(config(10, 1.0));
+
+        let first = h.create(request("alice", Some("retry-1"))).await.unwrap();
+        let retry = h.create(request("alice", Some("retry-1"))).await.unwrap();
+        assert_eq!(retry.access_token.token, first.access_token.token);
+        assert_eq!(retry.session_ticket, first.session_ticket);
+        assert_eq!(h.charges().await, 1);
+
+        let other = h.create(request("alice", None)).await.unwrap();
+        assert_ne!(other.access_token.token, first.access_token.token);
+        assert_eq!(h.charges().await, 2);
+    }
+
+    #[tokio::test]
+    async fn idempotency_keys_are_scoped_to_the_verified_agent() {
+        let mut h = Harness::new(config(10, 1.0));
+
+        let alice = h.create(request("alice", Some("shared"))).await.unwrap();
+        let err = h.create(request("", Some("shared"))).await.unwrap_err();
+        assert_eq!(err, SessionError::Unauthorized("empty assertion".into()));
+        let bob = h.create(request("bob", Some("shared"))).await.unwrap();
+        assert_ne!(bob.access_token.token, alice.access_token.token);
+        assert_eq!(bob.session_ticket["auth_binding"]["subject"], "bob");
+        assert_eq!(h.charges().await, 2);
+    }
+
+    #[tokio::test]
+    async fn rapid_requests_past_capacity_are_rate_limited() {
+        let mut h = Harness::new(config(3, 0.5));
+
+        for _ in 0..3 {
+            h.create(request("alice", None)).await.unwrap();
+        }
+        let err = h.create(request("alice", None)).await.unwrap_err();
+        assert_eq!(
+            err,
+            SessionError::RateLimited {
+                retry_after: Duration::from_secs(2)
+            }
+        );
+        // The limited request is not charged.
+        assert_eq!(h.charges().await, 3);
+
+        h.clock.advance(Duration::from_secs(2));
+        h.create(request("alice", None)).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn buckets_are_per_verified_agent() {
+        let mut h = Harness::new(config(1, 0.5));
+
+        h.create(request("alice", None)).await.unwrap();
+        assert!(matches!(
+            h.create(request("alice", None)).await,
+            Err(SessionError::RateLimited { .. })
+        ));
+        h.create(request("bob", None)).await.unwrap();
+        assert_eq!(h.charges().await, 2);
+    }
+
+    #[tokio::test]
+    async fn cached_retries_skip_the_limiter_until_the_key_expires() {
+        let mut h = Harness::new(config(1, 0.01));
+
+        let first = h.create(request("alice", Some("k"))).await.unwrap();
+        // The bucket is empty, but a replay is not a new session.
+        let retry = h.create(request("alice", Some("k"))).await.unwrap();
+        assert_eq!(retry.access_token.token, first.access_token.token);
+        assert!(matches!(
+            h.create(request("alice", Some("other"))).await,
+            Err(SessionError::RateLimited { .. })
+        ));
+
+        // The store's TTL runs on the limiter's clock.
+        h.clock.advance(Duration::from_secs(600));
+        let fresh = h.create(request("alice", Some("k"))).await.unwrap();
+        assert_ne!(fresh.access_token.token, first.access_token.token);
+        assert_eq!(h.charges().await, 2);
+    }
+
+    #[test]
+    fn refill_rate_must_be_positive() {
+        for rate in ["0", "-1"] {
+            let text = format!(
+                r#"{{"rate_limit": {{"capacity": 1, "refill_per_sec": {}}}}}"#,
+                rate
+            );
+            let err = serde_json::from_str::<Config>(&text).unwrap_err();
+            assert!(err.to_string().contains("refill_per_sec"), "{}", err);
+        }
+
+        // A rate too small to ever refill a token waits forever, not panics.
+        let tiny = RateLimitConfig {
+            capacity: 1,
+            refill_per_sec: 1e-300,
+        };
+        let mut limits = TokenBucketLimiter::new(tiny, ManualClock::new());
+        limits.check("alice").unwrap();
+        assert_eq!(limits.check("alice"), Err(Duration::MAX));
+    }
+
+    #[test]
+    fn in_memory_store_expires_entries_after_ttl() {
+        let mut keys = InMemoryIdempotencyStore::new(Duration::from_secs(60));
+        let start = Instant::now();
+        let response = SessionResponse {
+            session_ticket: serde_json::json!({ "ticket_id": "t-1" }),
+            access_token: MintedToken {
+                token: "tok-1".into(),
+                expires_at: "2025-01-01T00:00:00Z".into(),
+                scope: vec![],
+                vnode_id: "vnode-123".into(),
+            },
+        };
+        keys.put("alice".into(), "k".into(), response, start);
+
+        let later = start + Duration::from_secs(59);
+        assert!(keys.get("alice", "k", later).is_some());
+        assert!(keys.get("bob", "k", later).is_none());
+        let expired = start + Duration::from_secs(60);
+        assert!(keys.get("alice", "k", expired).is_none());
+    }
 }
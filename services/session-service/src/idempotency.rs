@@ -0,0 +1,50 @@
+// services/session-service/src/idempotency.rs
+// Replay protection for session creation: a client retrying with the same
+// idempotency key gets the originally minted response back instead of a
+// second token and a second ledger charge.
+
+use crate::handlers::SessionResponse;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Responses already minted, keyed by the verified agent id and the client's
+/// idempotency key, so one agent can never replay another's response.
+pub trait IdempotencyStore {
+    /// The response stored for `agent_id`'s `key`, if it has not expired as
+    /// of `now`.
+    fn get(&mut self, agent_id: &str, key: &str, now: Instant) -> Option<SessionResponse>;
+    /// Remember `response` for `agent_id`'s `key`, replacing any earlier entry.
+    fn put(&mut self, agent_id: String, key: String, response: SessionResponse, now: Instant);
+}
+
+/// Process-local store that keeps each response for a fixed TTL.
+#[derive(Debug)]
+pub struct InMemoryIdempotencyStore {
+    ttl: Duration,
+    entries: HashMap<(String, String), (Instant, SessionResponse)>,
+}
+
+impl InMemoryIdempotencyStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+}
+
+impl IdempotencyStore for InMemoryIdempotencyStore {
+    fn get(&mut self, agent_id: &str, key: &str, now: Instant) -> Option<SessionResponse> {
+        let ttl = self.ttl;
+        // Expired entries are dropped here so the map does not grow without bound.
+        self.entries
+            .retain(|_, (stored_at, _)| now.saturating_duration_since(*stored_at) < ttl);
+        self.entries
+            .get(&(agent_id.to_string(), key.to_string()))
+            .map(|(_, response)| response.clone())
+    }
+
+    fn put(&mut self, agent_id: String, key: String, response: SessionResponse, now: Instant) {
+        self.entries.insert((agent_id, key), (now, response));
+    }
+}
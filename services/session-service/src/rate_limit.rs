@@ -0,0 +1,117 @@
+// services/session-service/src/rate_limit.rs
+// Per-agent token buckets bounding how fast sessions can be created, so a
+// single identity cannot drain the ledger or flood the service.
+
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// Bucket size and refill rate; the `rate_limit` section of the service config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RateLimitConfig {
+    /// Requests an idle agent may burst before being limited.
+    pub capacity: u32,
+    /// Tokens added back per second, up to `capacity`. Must be finite and
+    /// positive.
+    #[serde(deserialize_with = "positive_rate")]
+    pub refill_per_sec: f64,
+}
+
+fn positive_rate<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    let rate = f64::deserialize(deserializer)?;
+    if rate.is_finite() && rate > 0.0 {
+        Ok(rate)
+    } else {
+        Err(D::Error::custom(format!(
+            "refill_per_sec must be a positive number, got {}",
+            rate
+        )))
+    }
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10,
+            refill_per_sec: 0.1,
+        }
+    }
+}
+
+/// Time source for the limiter; swapped for a manual clock in tests.
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Admission check for one request by a verified identity.
+pub trait RateLimiter {
+    /// Take a token for `agent_id`, or return how long until one is available.
+    fn check(&mut self, agent_id: &str) -> Result<(), Duration>;
+    /// The limiter's current time, which the handler also dates idempotency
+    /// entries with.
+    fn now(&self) -> Instant;
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+/// Token-bucket limiter keyed by agent id. Buckets start full.
+#[derive(Debug)]
+pub struct TokenBucketLimiter<C: Clock> {
+    config: RateLimitConfig,
+    clock: C,
+    buckets: HashMap<String, Bucket>,
+}
+
+impl<C: Clock> TokenBucketLimiter<C> {
+    pub fn new(config: RateLimitConfig, clock: C) -> Self {
+        Self {
+            config,
+            clock,
+            buckets: HashMap::new(),
+        }
+    }
+}
+
+impl<C: Clock> RateLimiter for TokenBucketLimiter<C> {
+    fn check(&mut self, agent_id: &str) -> Result<(), Duration> {
+        let now = self.clock.now();
+        let capacity = f64::from(self.config.capacity);
+        let refill = self.config.refill_per_sec;
+        let bucket = self.buckets.entry(agent_id.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated_at: now,
+        });
+
+        let elapsed = now
+            .saturating_duration_since(bucket.updated_at)
+            .as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * refill).min(capacity);
+        bucket.updated_at = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return Ok(());
+        }
+        // A refill rate too small (or, built by hand, not positive) to ever
+        // produce a token has no representable wait.
+        Err(Duration::try_from_secs_f64((1.0 - bucket.tokens) / refill).unwrap_or(Duration::MAX))
+    }
+
+    fn now(&self) -> Instant {
+        self.clock.now()
+    }
+}
@@ -2,7 +2,10 @@
 
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
 
 /// Compression + decimal parameters (CEM-aligned).
 const CE: f64 = 1e-12;      // AU.ET compression
@@ -11,6 +14,13 @@ const DALN: u32 = 9;        // internal decimals, e.g. 10^-9
 const MAX_TOTAL_AUET: u128 = 1_000_000_000_000; // 1e12 in 10^-9 units
 const MAX_TOTAL_CSP: u128  = 1_000_000_000;     // 1e9 in 10^-9 units
 
+/// Layout version written into every `VNodeGraph`. Bump on any breaking
+/// change to the serialized graph so older binaries refuse newer files.
+///
+/// 0: unversioned files. 1: adds `schema_version`. 2: records `ce_used` and
+/// `cs_used` on every `EnergyBudget`.
+pub const VNODE_GRAPH_SCHEMA_VERSION: u32 = 2;
+
 // ---- 1. Java MachineObject mirror (from MachineParser output JSON) ----
 
 #[derive(Debug, Clone, Deserialize)]
@@ -103,12 +113,99 @@ pub struct SourceState {
 pub enum EnergyError {
     #[error("invalid compression factors")]
     InvalidCompression,
+    #[error("stored {field} {stored} does not match per-vnode sum {computed}")]
+    TotalsMismatch {
+        field: &'static str,
+        stored: u128,
+        computed: u128,
+    },
+    #[error("combined {field} {total} exceeds global cap {cap}")]
+    GlobalCapExceeded {
+        field: &'static str,
+        total: u128,
+        cap: u128,
+    },
+    #[error("per-vnode {field} floor {min} is above its ceiling {max}")]
+    InvalidVnodeBounds {
+        field: &'static str,
+        min: u128,
+        max: u128,
+    },
+    #[error("{count} objects exceeds max_objects {max}")]
+    TooManyObjects { count: usize, max: usize },
+    #[error("blueprint_hash mismatch: expected {expected}, stored {stored}")]
+    BlueprintHashMismatch { expected: String, stored: String },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum GraphLoadError {
+    #[error("invalid graph JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("graph schema_version {found} is newer than supported version {supported}; upgrade aln_vnodes")]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+}
+
+/// Failure of the file-level entrypoints the CLIs share. The underlying
+/// error is the `source`, so `{:#}` prints the path followed by the cause.
+#[derive(Debug, thiserror::Error)]
+pub enum VNodeFileError {
+    #[error("{path}")]
+    Io {
+        path: String,
+        source: std::io::Error,
+    },
+    #[error("{path}: invalid MachineObjects JSON")]
+    Json {
+        path: String,
+        source: serde_json::Error,
+    },
+    #[error("{path}")]
+    Load {
+        path: String,
+        source: GraphLoadError,
+    },
+    #[error("{path}")]
+    Energy { path: String, source: EnergyError },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EnergyBudget {
     pub auet: u128,
     pub csp: u128,
+    /// Compression factors `auet`/`csp` were derived with. Graphs written
+    /// before these were recorded were built with the defaults.
+    #[serde(default = "default_ce")]
+    pub ce_used: f64,
+    #[serde(default = "default_cs")]
+    pub cs_used: f64,
+    /// Per-vnode bounds that replaced the mapped value, if any. Omitted when
+    /// empty, so unclamped graphs hash as before.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub clamps: Vec<EnergyClamp>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClampBound {
+    Floor,
+    Ceiling,
+}
+
+/// Record of a per-vnode floor or ceiling overriding `map_to_energy`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnergyClamp {
+    /// `"auet"` or `"csp"`.
+    pub field: String,
+    pub bound: ClampBound,
+    /// The value `map_to_energy` produced before clamping.
+    pub unclamped: u128,
+}
+
+fn default_ce() -> f64 {
+    CE
+}
+
+fn default_cs() -> f64 {
+    CS
 }
 
 fn map_to_energy(state: &SourceState, ce: f64, cs: f64) -> Result<EnergyBudget, EnergyError> {
@@ -124,12 +221,39 @@ fn map_to_energy(state: &SourceState, ce: f64, cs: f64) -> Result<EnergyBudget,
     let be = (ae * factor_aln).floor().max(0.0) as u128;
     let bs = (as_ * factor_aln).floor().max(0.0) as u128;
 
-    Ok(EnergyBudget { auet: be, csp: bs })
+    Ok(EnergyBudget {
+        auet: be,
+        csp: bs,
+        ce_used: ce,
+        cs_used: cs,
+        clamps: Vec::new(),
+    })
+}
+
+/// Raise `value` to `min` / lower it to `max`, recording which bound fired.
+fn clamp_energy(
+    field: &str,
+    value: u128,
+    min: Option<u128>,
+    max: Option<u128>,
+    clamps: &mut Vec<EnergyClamp>,
+) -> u128 {
+    let (clamped, bound) = match (min, max) {
+        (Some(min), _) if value < min => (min, ClampBound::Floor),
+        (_, Some(max)) if value > max => (max, ClampBound::Ceiling),
+        _ => return value,
+    };
+    clamps.push(EnergyClamp {
+        field: field.to_string(),
+        bound,
+        unclamped: value,
+    });
+    clamped
 }
 
 // ---- 4. VNode definition and hashing ----
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum VNodeKind {
     Service,
     Node,
@@ -143,18 +267,29 @@ pub struct VNode {
     pub path: String,
     pub kind: VNodeKind,
     pub attributes: BTreeMap<String, serde_json::Value>,
+    /// Operator tags for filtering, e.g. `team = "infra"`. Covered by the
+    /// blueprint hash; omitted when empty so unlabelled graphs hash as before.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
     pub energy: EnergyBudget,
     pub rad_envelope: RadEnvelopeQpu,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VNodeGraph {
+    /// Graph files written before versioning load as version 0.
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub vnodes: Vec<VNode>,
     pub total_auet: u128,
     pub total_csp: u128,
     pub blueprint_hash: String,
 }
 
+fn legacy_schema_version() -> u32 {
+    0
+}
+
 /// Infer VNodeKind from MachineObject.type/path (sanitized).
 fn infer_kind(obj: &MachineObject) -> VNodeKind {
     let t = obj.r#type.to_lowercase();
@@ -169,6 +304,34 @@ fn infer_kind(obj: &MachineObject) -> VNodeKind {
     }
 }
 
+/// Labels from the object's reserved `labels` attribute: either an object
+/// (string values kept as-is, others as their JSON text) or an array of
+/// `"key:value"` strings, where a bare `"key"` gets an empty value.
+fn labels_from_attributes(
+    attributes: &BTreeMap<String, serde_json::Value>,
+) -> BTreeMap<String, String> {
+    let mut labels = BTreeMap::new();
+    match attributes.get("labels") {
+        Some(serde_json::Value::Object(map)) => {
+            for (key, value) in map {
+                let value = match value {
+                    serde_json::Value::String(s) => s.clone(),
+                    other => other.to_string(),
+                };
+                labels.insert(key.clone(), value);
+            }
+        }
+        Some(serde_json::Value::Array(items)) => {
+            for item in items.iter().filter_map(|v| v.as_str()) {
+                let (key, value) = item.split_once(':').unwrap_or((item, ""));
+                labels.insert(key.to_string(), value.to_string());
+            }
+        }
+        _ => {}
+    }
+    labels
+}
+
 /// Deterministic per-type safety caps (example, ICNIRP/IEEE-consistent ranges). [file:5]
 fn default_rad_caps(kind: &VNodeKind) -> RadEnvelopeQpu {
     match kind {
@@ -187,17 +350,298 @@ fn default_rad_caps(kind: &VNodeKind) -> RadEnvelopeQpu {
     }
 }
 
+/// Deterministic blueprint hash over canonical JSON, laid out the way graphs
+/// of `schema_version` were hashed when written: version 0 has no
+/// `schema_version` key and versions before 2 have no compression factors.
+fn compute_blueprint_hash(
+    schema_version: u32,
+    vnodes: &[VNode],
+    total_auet: u128,
+    total_csp: u128,
+) -> String {
+    let mut vnodes = serde_json::json!(vnodes);
+    if schema_version < 2 {
+        for vnode in vnodes.as_array_mut().into_iter().flatten() {
+            if let Some(energy) = vnode.get_mut("energy").and_then(|e| e.as_object_mut()) {
+                energy.remove("ce_used");
+                energy.remove("cs_used");
+            }
+        }
+    }
+    let mut graph_tmp = serde_json::json!({
+        "vnodes": vnodes,
+        "total_auet": total_auet.to_string(),
+        "total_csp": total_csp.to_string(),
+    });
+    if schema_version > 0 {
+        graph_tmp["schema_version"] = serde_json::json!(schema_version);
+    }
+    let blob = graph_tmp.to_string();
+    let mut hasher = Sha256::new();
+    hasher.update(blob.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+impl VNodeGraph {
+    /// Parse a serialized graph, rejecting files written by a newer schema.
+    pub fn from_json_str(text: &str) -> Result<Self, GraphLoadError> {
+        let graph: VNodeGraph = serde_json::from_str(text)?;
+        if graph.schema_version > VNODE_GRAPH_SCHEMA_VERSION {
+            return Err(GraphLoadError::UnsupportedSchemaVersion {
+                found: graph.schema_version,
+                supported: VNODE_GRAPH_SCHEMA_VERSION,
+            });
+        }
+        Ok(graph)
+    }
+
+    /// Per-vnode energy sums, accumulated exactly as `build_vnode_graph` does.
+    fn summed_totals(&self) -> (u128, u128) {
+        self.vnodes.iter().fold((0u128, 0u128), |(auet, csp), v| {
+            (
+                auet.saturating_add(v.energy.auet),
+                csp.saturating_add(v.energy.csp),
+            )
+        })
+    }
+
+    /// Check that the stored totals equal the sums over `vnodes`, e.g. for a
+    /// graph deserialized from a file that may have been edited by hand.
+    pub fn verify_totals(&self) -> Result<(), EnergyError> {
+        let (auet, csp) = self.summed_totals();
+        if auet != self.total_auet {
+            return Err(EnergyError::TotalsMismatch {
+                field: "total_auet",
+                stored: self.total_auet,
+                computed: auet,
+            });
+        }
+        if csp != self.total_csp {
+            return Err(EnergyError::TotalsMismatch {
+                field: "total_csp",
+                stored: self.total_csp,
+                computed: csp,
+            });
+        }
+        Ok(())
+    }
+
+    /// Recompute `blueprint_hash` from the graph's vnodes and stored totals,
+    /// exactly as `build_vnode_graph` does, and check it matches the stored
+    /// hash. Detects edits to a serialized graph that left the hash alone.
+    pub fn verify_blueprint_hash(&self) -> Result<(), EnergyError> {
+        let expected = compute_blueprint_hash(
+            self.schema_version,
+            &self.vnodes,
+            self.total_auet,
+            self.total_csp,
+        );
+        if expected != self.blueprint_hash {
+            return Err(EnergyError::BlueprintHashMismatch {
+                expected,
+                stored: self.blueprint_hash.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Overwrite the stored totals with the per-vnode sums and refresh
+    /// `blueprint_hash`, which covers the totals.
+    pub fn recompute_totals(&mut self) {
+        let (auet, csp) = self.summed_totals();
+        self.total_auet = auet;
+        self.total_csp = csp;
+        self.blueprint_hash = compute_blueprint_hash(self.schema_version, &self.vnodes, auet, csp);
+    }
+
+    /// Set label `key` on vnode `vnode_id` and refresh `blueprint_hash`.
+    /// Returns false, leaving the graph unchanged, if no such vnode exists.
+    pub fn add_label(&mut self, vnode_id: &str, key: &str, value: &str) -> bool {
+        let Some(vnode) = self.vnodes.iter_mut().find(|v| v.vnode_id == vnode_id) else {
+            return false;
+        };
+        vnode.labels.insert(key.to_string(), value.to_string());
+        self.blueprint_hash = compute_blueprint_hash(
+            self.schema_version,
+            &self.vnodes,
+            self.total_auet,
+            self.total_csp,
+        );
+        true
+    }
+
+    /// Vnodes whose label `key` equals `value`, in graph order.
+    pub fn filter_by_label(&self, key: &str, value: &str) -> Vec<&VNode> {
+        self.vnodes
+            .iter()
+            .filter(|v| v.labels.get(key).map(String::as_str) == Some(value))
+            .collect()
+    }
+
+    /// Render the graph as GraphViz DOT. Path segments become directory
+    /// nodes rooted at `/`; each vnode hangs off its parent directory,
+    /// labelled with its kind and AU.ET and filled by σ band. Nodes and edges
+    /// are emitted in sorted order so the output is stable across runs.
+    pub fn to_dot(&self) -> String {
+        let mut dirs: BTreeSet<String> = BTreeSet::new();
+        let mut edges: BTreeSet<(String, String)> = BTreeSet::new();
+        dirs.insert("/".to_string());
+
+        let mut vnodes: Vec<&VNode> = self.vnodes.iter().collect();
+        vnodes.sort_by(|a, b| (&a.path, &a.vnode_id).cmp(&(&b.path, &b.vnode_id)));
+
+        for v in &vnodes {
+            let segments: Vec<&str> = v.path.split('/').filter(|s| !s.is_empty()).collect();
+            let mut parent = "/".to_string();
+            for seg in segments.iter().take(segments.len().saturating_sub(1)) {
+                let dir = if parent == "/" {
+                    format!("/{seg}")
+                } else {
+                    format!("{parent}/{seg}")
+                };
+                dirs.insert(dir.clone());
+                edges.insert((format!("dir:{parent}"), format!("dir:{dir}")));
+                parent = dir;
+            }
+            edges.insert((format!("dir:{parent}"), format!("vnode:{}", v.vnode_id)));
+        }
+
+        let mut out = String::from("digraph vnodes {\n    rankdir=LR;\n");
+        for dir in &dirs {
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", shape=folder];",
+                dot_escape(&format!("dir:{dir}")),
+                dot_escape(dir)
+            );
+        }
+        for v in &vnodes {
+            let label = format!("{}\n{:?}\nAU.ET {}", v.vnode_id, v.kind, format_auet(v.energy.auet));
+            let _ = writeln!(
+                out,
+                "    \"{}\" [label=\"{}\", shape=box, style=filled, fillcolor=\"{}\"];",
+                dot_escape(&format!("vnode:{}", v.vnode_id)),
+                dot_escape(&label),
+                sigma_color(v.rad_envelope.sigma())
+            );
+        }
+        for (from, to) in &edges {
+            let _ = writeln!(out, "    \"{}\" -> \"{}\";", dot_escape(from), dot_escape(to));
+        }
+        out.push_str("}\n");
+        out
+    }
+}
+
+/// Escape a string for use inside a double-quoted DOT identifier or label.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// Render 10^-9 AU.ET units as a fixed-point decimal.
+fn format_auet(units: u128) -> String {
+    let scale = 10u128.pow(DALN);
+    format!("{}.{:0width$}", units / scale, units % scale, width = DALN as usize)
+}
+
+/// Fill colour for a σ band: green when mostly unloaded, red near saturation.
+fn sigma_color(sigma: f32) -> &'static str {
+    if sigma >= 0.66 {
+        "palegreen"
+    } else if sigma >= 0.33 {
+        "gold"
+    } else {
+        "salmon"
+    }
+}
+
+/// Bounds, compression factors and classification overrides applied while
+/// building a graph.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildLimits {
+    /// Reject inputs with more objects than this before building anything.
+    pub max_objects: Option<usize>,
+    pub max_total_auet: u128,
+    pub max_total_csp: u128,
+    /// AU.ET / CSP compression factors, each in `0.0..=1.0`. Recorded on
+    /// every vnode's `EnergyBudget` and so covered by `blueprint_hash`.
+    pub ce: f64,
+    pub cs: f64,
+    /// Kinds that replace `infer_kind`'s guess, keyed by object id or by an
+    /// exact `type` string; an id entry wins over a type entry.
+    pub kind_overrides: HashMap<String, VNodeKind>,
+    /// Per-vnode floors and ceilings applied after `map_to_energy`, before
+    /// the global caps. A floor stops tiny weights from yielding free vnodes.
+    pub min_vnode_auet: Option<u128>,
+    pub max_vnode_auet: Option<u128>,
+    pub min_vnode_csp: Option<u128>,
+    pub max_vnode_csp: Option<u128>,
+}
+
+impl Default for BuildLimits {
+    fn default() -> Self {
+        Self {
+            max_objects: None,
+            max_total_auet: MAX_TOTAL_AUET,
+            max_total_csp: MAX_TOTAL_CSP,
+            ce: CE,
+            cs: CS,
+            kind_overrides: HashMap::new(),
+            min_vnode_auet: None,
+            max_vnode_auet: None,
+            min_vnode_csp: None,
+            max_vnode_csp: None,
+        }
+    }
+}
+
 /// Build a VNodeGraph from MachineObjects and a deterministic weight function.
 pub fn build_vnode_graph(
     origin: &str,
     objects: &[MachineObject],
 ) -> Result<VNodeGraph, EnergyError> {
-    let mut vnodes = Vec::with_capacity(objects.len());
+    build_vnode_graph_with_limits(origin, objects, &BuildLimits::default())
+}
+
+/// `build_vnode_graph` under explicit `limits`. The energy caps are checked
+/// after every object, so an over-cap input fails as soon as its running
+/// total crosses a cap instead of after every vnode has been built.
+pub fn build_vnode_graph_with_limits(
+    origin: &str,
+    objects: &[MachineObject],
+    limits: &BuildLimits,
+) -> Result<VNodeGraph, EnergyError> {
+    if let Some(max) = limits.max_objects {
+        if objects.len() > max {
+            return Err(EnergyError::TooManyObjects {
+                count: objects.len(),
+                max,
+            });
+        }
+    }
+
+    for (field, min, max) in [
+        ("auet", limits.min_vnode_auet, limits.max_vnode_auet),
+        ("csp", limits.min_vnode_csp, limits.max_vnode_csp),
+    ] {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(EnergyError::InvalidVnodeBounds { field, min, max });
+            }
+        }
+    }
+
+    let mut vnodes = Vec::new();
     let mut total_auet: u128 = 0;
     let mut total_csp: u128 = 0;
 
     for obj in objects {
-        let kind = infer_kind(obj);
+        let kind = limits
+            .kind_overrides
+            .get(&obj.id)
+            .or_else(|| limits.kind_overrides.get(&obj.r#type))
+            .cloned()
+            .unwrap_or_else(|| infer_kind(obj));
 
         // Weight function: deterministic, non-negative, based on path length.
         // You can swap this for any policy that produces u128 weights.
@@ -208,10 +652,33 @@ pub fn build_vnode_graph(
             object_id: obj.id.clone(),
             weight,
         };
-        let energy = map_to_energy(&src, CE, CS)?;
+        let mut energy = map_to_energy(&src, limits.ce, limits.cs)?;
+        energy.auet = clamp_energy(
+            "auet",
+            energy.auet,
+            limits.min_vnode_auet,
+            limits.max_vnode_auet,
+            &mut energy.clamps,
+        );
+        energy.csp = clamp_energy(
+            "csp",
+            energy.csp,
+            limits.min_vnode_csp,
+            limits.max_vnode_csp,
+            &mut energy.clamps,
+        );
 
         total_auet = total_auet.saturating_add(energy.auet);
         total_csp = total_csp.saturating_add(energy.csp);
+        // Enforce global caps (non-minting scarcity). [file:5]
+        for (field, total, cap) in [
+            ("total_auet", total_auet, limits.max_total_auet),
+            ("total_csp", total_csp, limits.max_total_csp),
+        ] {
+            if total > cap {
+                return Err(EnergyError::GlobalCapExceeded { field, total, cap });
+            }
+        }
 
         let rad_envelope = default_rad_caps(&kind);
 
@@ -220,30 +687,461 @@ pub fn build_vnode_graph(
             path: obj.path.clone(),
             kind,
             attributes: obj.attributes.clone(),
+            labels: labels_from_attributes(&obj.attributes),
             energy,
             rad_envelope,
         });
     }
 
-    // Enforce global caps (non-minting scarcity). [file:5]
-    assert!(total_auet <= MAX_TOTAL_AUET, "AU.ET cap exceeded");
-    assert!(total_csp <= MAX_TOTAL_CSP, "CSP cap exceeded");
-
-    // Deterministic blueprint hash over canonical JSON.
-    let graph_tmp = serde_json::json!({
-        "vnodes": &vnodes,
-        "total_auet": total_auet.to_string(),
-        "total_csp": total_csp.to_string(),
-    });
-    let blob = graph_tmp.to_string();
-    let mut hasher = Sha256::new();
-    hasher.update(blob.as_bytes());
-    let blueprint_hash = format!("{:x}", hasher.finalize());
+    let blueprint_hash =
+        compute_blueprint_hash(VNODE_GRAPH_SCHEMA_VERSION, &vnodes, total_auet, total_csp);
 
     Ok(VNodeGraph {
+        schema_version: VNODE_GRAPH_SCHEMA_VERSION,
         vnodes,
         total_auet,
         total_csp,
         blueprint_hash,
     })
 }
+
+/// Read a MachineObjects JSON array from `input` and build its graph.
+pub fn build_vnode_graph_file(origin: &str, input: &Path) -> Result<VNodeGraph, VNodeFileError> {
+    let path = input.display().to_string();
+    let data = fs::read_to_string(input).map_err(|source| VNodeFileError::Io {
+        path: path.clone(),
+        source,
+    })?;
+    let objects: Vec<MachineObject> =
+        serde_json::from_str(&data).map_err(|source| VNodeFileError::Json {
+            path: path.clone(),
+            source,
+        })?;
+    build_vnode_graph(origin, &objects).map_err(|source| VNodeFileError::Energy { path, source })
+}
+
+/// Load the graph stored at `graph` and check its `blueprint_hash` against
+/// its contents. A mismatch is `EnergyError::BlueprintHashMismatch`.
+pub fn verify_vnode_graph_file(graph: &Path) -> Result<VNodeGraph, VNodeFileError> {
+    let path = graph.display().to_string();
+    let text = fs::read_to_string(graph).map_err(|source| VNodeFileError::Io {
+        path: path.clone(),
+        source,
+    })?;
+    let graph = VNodeGraph::from_json_str(&text).map_err(|source| VNodeFileError::Load {
+        path: path.clone(),
+        source,
+    })?;
+    graph
+        .verify_blueprint_hash()
+        .map_err(|source| VNodeFileError::Energy { path, source })?;
+    Ok(graph)
+}
+
+/// Summed budget of several graphs admitted together.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CombinedEnergy {
+    pub total_auet: u128,
+    pub total_csp: u128,
+    /// SHA-256 over the sorted child `blueprint_hash`es, so the result does
+    /// not depend on the order graphs are passed in.
+    pub blueprint_hash: String,
+}
+
+/// Sum the stored totals of `graphs`, failing if either combined total
+/// exceeds `MAX_TOTAL_AUET`/`MAX_TOTAL_CSP`.
+pub fn combine_graphs(graphs: &[VNodeGraph]) -> Result<CombinedEnergy, EnergyError> {
+    let mut total_auet: u128 = 0;
+    let mut total_csp: u128 = 0;
+    for g in graphs {
+        total_auet = total_auet.saturating_add(g.total_auet);
+        total_csp = total_csp.saturating_add(g.total_csp);
+    }
+    for (field, total, cap) in [
+        ("total_auet", total_auet, MAX_TOTAL_AUET),
+        ("total_csp", total_csp, MAX_TOTAL_CSP),
+    ] {
+        if total > cap {
+            return Err(EnergyError::GlobalCapExceeded { field, total, cap });
+        }
+    }
+
+    let mut child_hashes: Vec<&str> = graphs.iter().map(|g| g.blueprint_hash.as_str()).collect();
+    child_hashes.sort_unstable();
+    let mut hasher = Sha256::new();
+    for h in child_hashes {
+        hasher.update(h.as_bytes());
+        hasher.update(b"\n");
+    }
+
+    Ok(CombinedEnergy {
+        total_auet,
+        total_csp,
+        blueprint_hash: format!("{:x}", hasher.finalize()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn object(id: &str, path: &str, kind: &str) -> MachineObject {
+        MachineObject {
+            id: id.to_string(),
+            path: path.to_string(),
+            r#type: kind.to_string(),
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    fn sample_graph() -> VNodeGraph {
+        let objects = vec![
+            object("svc-1", "/cluster/api/service", "Service"),
+            object("task-1", "/cluster/jobs/nightly-task", "Task"),
+        ];
+        build_vnode_graph("JavaSpectre", &objects).unwrap()
+    }
+
+    #[test]
+    fn tampered_totals_are_detected_and_recomputed() {
+        let mut graph = sample_graph();
+        graph.verify_totals().unwrap();
+        let original_hash = graph.blueprint_hash.clone();
+        let original_auet = graph.total_auet;
+
+        graph.total_auet += 1;
+        match graph.verify_totals() {
+            Err(EnergyError::TotalsMismatch {
+                field,
+                stored,
+                computed,
+            }) => {
+                assert_eq!(field, "total_auet");
+                assert_eq!(stored, original_auet + 1);
+                assert_eq!(computed, original_auet);
+            }
+            other => panic!("expected TotalsMismatch, got {:?}", other),
+        }
+
+        graph.recompute_totals();
+        graph.verify_totals().unwrap();
+        assert_eq!(graph.total_auet, original_auet);
+        assert_eq!(graph.blueprint_hash, original_hash);
+    }
+
+    #[test]
+    fn labels_are_read_from_attributes_and_filterable() {
+        let mut api = object("svc-1", "/cluster/api/service", "Service");
+        api.attributes.insert(
+            "labels".to_string(),
+            serde_json::json!({"team": "infra", "tier": 1}),
+        );
+        let mut job = object("task-1", "/cluster/jobs/nightly-task", "Task");
+        job.attributes.insert(
+            "labels".to_string(),
+            serde_json::json!(["team:data", "critical"]),
+        );
+        let plain = object("vo-1", "/cluster/misc", "Blob");
+        let mut graph = build_vnode_graph("JavaSpectre", &[api, job, plain]).unwrap();
+
+        assert_eq!(graph.vnodes[0].labels["tier"], "1");
+        assert_eq!(graph.vnodes[1].labels["critical"], "");
+        let infra: Vec<&str> = graph
+            .filter_by_label("team", "infra")
+            .iter()
+            .map(|v| v.vnode_id.as_str())
+            .collect();
+        assert_eq!(infra, ["svc-1"]);
+        assert!(graph.filter_by_label("team", "web").is_empty());
+
+        let before = graph.blueprint_hash.clone();
+        assert!(graph.add_label("vo-1", "team", "infra"));
+        assert_ne!(graph.blueprint_hash, before);
+        graph.verify_blueprint_hash().unwrap();
+        assert_eq!(graph.filter_by_label("team", "infra").len(), 2);
+        assert!(!graph.add_label("missing", "team", "infra"));
+
+        // Unlabelled vnodes serialize without the field.
+        let text = serde_json::to_string(&sample_graph()).unwrap();
+        assert!(!text.contains("labels"));
+    }
+
+    /// `svc-1` built by the code that wrote each version, before the
+    /// compression factors were recorded.
+    const V0_GRAPH: &str = r#"{"vnodes":[{"vnode_id":"svc-1","path":"/cluster/api/service","kind":"Service","attributes":{},"energy":{"auet":0,"csp":0},"rad_envelope":{"dion":0,"srf_mwkg":0,"j_tissue_mam2":0,"dion_max":10000000,"srf_max_mwkg":2000,"j_tissue_max_mam2":10}}],"total_auet":0,"total_csp":0,"blueprint_hash":"7161f2fe4c89f2fd85d6fafa97c59f69d4b1c83376bf4b344f7b97d04fd49a37"}"#;
+    const V1_GRAPH: &str = r#"{"schema_version":1,"vnodes":[{"vnode_id":"svc-1","path":"/cluster/api/service","kind":"Service","attributes":{},"energy":{"auet":0,"csp":0},"rad_envelope":{"dion":0,"srf_mwkg":0,"j_tissue_mam2":0,"dion_max":10000000,"srf_max_mwkg":2000,"j_tissue_max_mam2":10}}],"total_auet":0,"total_csp":0,"blueprint_hash":"8ef7581485e7ea36f78951b2ad23dc7d226dcd55cbe641bed258cee55ad53f51"}"#;
+
+    #[test]
+    fn legacy_graphs_verify_against_the_hash_layout_of_their_version() {
+        for (text, version) in [(V0_GRAPH, 0), (V1_GRAPH, 1)] {
+            let mut graph = VNodeGraph::from_json_str(text).unwrap();
+            assert_eq!(graph.schema_version, version);
+            graph.verify_blueprint_hash().unwrap();
+
+            let stored = graph.blueprint_hash.clone();
+            graph.recompute_totals();
+            assert_eq!(graph.blueprint_hash, stored);
+
+            graph.vnodes[0].path.push_str("-edited");
+            assert!(matches!(
+                graph.verify_blueprint_hash(),
+                Err(EnergyError::BlueprintHashMismatch { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn future_schema_version_is_rejected_on_load() {
+        let graph = sample_graph();
+        assert_eq!(graph.schema_version, VNODE_GRAPH_SCHEMA_VERSION);
+        let text = serde_json::to_string(&graph).unwrap();
+        let loaded = VNodeGraph::from_json_str(&text).unwrap();
+        assert_eq!(loaded.blueprint_hash, graph.blueprint_hash);
+
+        let mut future = serde_json::to_value(&graph).unwrap();
+        future["schema_version"] = serde_json::json!(VNODE_GRAPH_SCHEMA_VERSION + 1);
+        match VNodeGraph::from_json_str(&future.to_string()) {
+            Err(GraphLoadError::UnsupportedSchemaVersion { found, supported }) => {
+                assert_eq!(found, VNODE_GRAPH_SCHEMA_VERSION + 1);
+                assert_eq!(supported, VNODE_GRAPH_SCHEMA_VERSION);
+            }
+            other => panic!(
+                "expected UnsupportedSchemaVersion, got {:?}",
+                other.map(|_| ())
+            ),
+        }
+
+        let mut legacy = serde_json::to_value(&graph).unwrap();
+        legacy.as_object_mut().unwrap().remove("schema_version");
+        assert_eq!(
+            VNodeGraph::from_json_str(&legacy.to_string())
+                .unwrap()
+                .schema_version,
+            0
+        );
+    }
+
+    #[test]
+    fn to_dot_builds_directory_tree_deterministically() {
+        let graph = sample_graph();
+        let dot = graph.to_dot();
+
+        // `/`, `/cluster`, `/cluster/api`, `/cluster/jobs` plus two vnodes.
+        assert_eq!(dot.matches("shape=folder").count(), 4);
+        assert_eq!(dot.matches("shape=box").count(), 2);
+        assert_eq!(dot.matches(" -> ").count(), 5);
+        assert!(dot.contains("\"dir:/cluster/api\" -> \"vnode:svc-1\";"));
+        assert!(dot.contains("Service\\nAU.ET 0.000000000"));
+        assert!(dot.contains("fillcolor=\"palegreen\""));
+
+        let mut reversed = graph.clone();
+        reversed.vnodes.reverse();
+        assert_eq!(reversed.to_dot(), dot);
+    }
+
+    #[test]
+    fn over_cap_input_fails_at_the_crossing_object() {
+        // 5000-char paths weigh 5 AU.ET units and 2 CSP units each.
+        let long_path = format!("/{}", "x".repeat(4999));
+        let objects: Vec<MachineObject> = (0..10)
+            .map(|i| object(&format!("obj-{}", i), &long_path, "Task"))
+            .collect();
+        let limits = BuildLimits {
+            max_total_auet: 12,
+            ..BuildLimits::default()
+        };
+
+        // Built in full, the input would total 50 units; the third object
+        // already crosses the cap.
+        match build_vnode_graph_with_limits("JavaSpectre", &objects, &limits) {
+            Err(EnergyError::GlobalCapExceeded { field, total, cap }) => {
+                assert_eq!(field, "total_auet");
+                assert_eq!(total, 15);
+                assert_eq!(cap, 12);
+            }
+            other => panic!("expected GlobalCapExceeded, got {:?}", other.map(|_| ())),
+        }
+
+        let capped = BuildLimits {
+            max_objects: Some(9),
+            ..BuildLimits::default()
+        };
+        assert!(matches!(
+            build_vnode_graph_with_limits("JavaSpectre", &objects, &capped),
+            Err(EnergyError::TooManyObjects { count: 10, max: 9 })
+        ));
+        let graph = build_vnode_graph_with_limits(
+            "JavaSpectre",
+            &objects,
+            &BuildLimits {
+                max_objects: Some(10),
+                ..BuildLimits::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(graph.total_auet, 50);
+    }
+
+    #[test]
+    fn compression_factors_are_recorded_and_hashed() {
+        let long_path = format!("/{}", "x".repeat(4999));
+        let objects = vec![object("obj-1", &long_path, "Task")];
+        let default = build_vnode_graph("JavaSpectre", &objects).unwrap();
+        let doubled = build_vnode_graph_with_limits(
+            "JavaSpectre",
+            &objects,
+            &BuildLimits {
+                ce: 2e-12,
+                cs: 1e-12,
+                ..BuildLimits::default()
+            },
+        )
+        .unwrap();
+
+        let (a, b) = (&default.vnodes[0].energy, &doubled.vnodes[0].energy);
+        assert_eq!((a.ce_used, a.cs_used), (CE, CS));
+        assert_eq!((b.ce_used, b.cs_used), (2e-12, 1e-12));
+        assert_eq!((a.auet, b.auet), (5, 10));
+        assert_ne!(default.blueprint_hash, doubled.blueprint_hash);
+
+        // The recorded factor alone is enough to change the hash.
+        let mut relabelled = default.clone();
+        relabelled.vnodes[0].energy.ce_used = 2e-12;
+        relabelled.recompute_totals();
+        assert_ne!(relabelled.blueprint_hash, default.blueprint_hash);
+
+        // Graphs written before provenance was recorded load with the defaults.
+        let mut legacy = serde_json::to_value(&doubled).unwrap();
+        let energy = legacy["vnodes"][0]["energy"].as_object_mut().unwrap();
+        energy.remove("ce_used");
+        energy.remove("cs_used");
+        let loaded = VNodeGraph::from_json_str(&legacy.to_string()).unwrap();
+        assert_eq!(loaded.vnodes[0].energy.ce_used, CE);
+        assert_eq!(loaded.vnodes[0].energy.cs_used, CS);
+    }
+
+    #[test]
+    fn edited_graph_fails_blueprint_verification() {
+        let graph = sample_graph();
+        let text = serde_json::to_string(&graph).unwrap();
+        let loaded = VNodeGraph::from_json_str(&text).unwrap();
+        loaded.verify_blueprint_hash().unwrap();
+
+        let mut edited: serde_json::Value = serde_json::from_str(&text).unwrap();
+        edited["vnodes"][0]["path"] = serde_json::json!("/cluster/api/other");
+        let edited = VNodeGraph::from_json_str(&edited.to_string()).unwrap();
+        match edited.verify_blueprint_hash() {
+            Err(EnergyError::BlueprintHashMismatch { expected, stored }) => {
+                assert_eq!(stored, graph.blueprint_hash);
+                assert_ne!(expected, stored);
+            }
+            other => panic!("expected BlueprintHashMismatch, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn kind_overrides_take_precedence_over_inference() {
+        let objects = vec![
+            object("api", "/cluster/api", "svc"),
+            object("worker", "/cluster/worker", "svc"),
+            object("cron", "/cluster/cron", "ServiceTask"),
+        ];
+        let inferred = build_vnode_graph("JavaSpectre", &objects).unwrap();
+        assert_eq!(inferred.vnodes[0].kind, VNodeKind::VirtualObject);
+        assert_eq!(inferred.vnodes[2].kind, VNodeKind::Service);
+
+        let limits = BuildLimits {
+            kind_overrides: HashMap::from([
+                ("svc".to_string(), VNodeKind::Service),
+                ("worker".to_string(), VNodeKind::Task),
+                ("cron".to_string(), VNodeKind::Task),
+            ]),
+            ..BuildLimits::default()
+        };
+        let graph = build_vnode_graph_with_limits("JavaSpectre", &objects, &limits).unwrap();
+        let kinds: Vec<&VNodeKind> = graph.vnodes.iter().map(|v| &v.kind).collect();
+        assert_eq!(kinds, [&VNodeKind::Service, &VNodeKind::Task, &VNodeKind::Task]);
+        assert_ne!(graph.blueprint_hash, inferred.blueprint_hash);
+    }
+
+    #[test]
+    fn per_vnode_floor_and_ceiling_are_applied_and_recorded() {
+        // A 1-char path weighs 1 unit, which maps to 0 AU.ET; a 5000-char
+        // path maps to 5 AU.ET and 2 CSP.
+        let objects = vec![
+            object("tiny", "/", "Task"),
+            object("huge", &format!("/{}", "x".repeat(4999)), "Task"),
+        ];
+        let limits = BuildLimits {
+            min_vnode_auet: Some(1),
+            max_vnode_auet: Some(3),
+            ..BuildLimits::default()
+        };
+        let graph = build_vnode_graph_with_limits("JavaSpectre", &objects, &limits).unwrap();
+
+        let tiny = &graph.vnodes[0].energy;
+        assert_eq!(tiny.auet, 1);
+        assert_eq!(
+            tiny.clamps,
+            [EnergyClamp {
+                field: "auet".to_string(),
+                bound: ClampBound::Floor,
+                unclamped: 0,
+            }]
+        );
+        let huge = &graph.vnodes[1].energy;
+        assert_eq!((huge.auet, huge.csp), (3, 2));
+        assert_eq!(huge.clamps.len(), 1);
+        assert_eq!(huge.clamps[0].bound, ClampBound::Ceiling);
+        assert_eq!(huge.clamps[0].unclamped, 5);
+        assert_eq!(graph.total_auet, 4);
+        graph.verify_blueprint_hash().unwrap();
+
+        // Without bounds nothing is recorded and the hash is unaffected.
+        let plain = build_vnode_graph("JavaSpectre", &objects).unwrap();
+        assert!(plain.vnodes.iter().all(|v| v.energy.clamps.is_empty()));
+        let text = serde_json::to_string(&plain).unwrap();
+        assert!(!text.contains("clamps"));
+
+        let inverted = BuildLimits {
+            min_vnode_csp: Some(5),
+            max_vnode_csp: Some(4),
+            ..BuildLimits::default()
+        };
+        assert!(matches!(
+            build_vnode_graph_with_limits("JavaSpectre", &objects, &inverted),
+            Err(EnergyError::InvalidVnodeBounds {
+                field: "csp",
+                min: 5,
+                max: 4
+            })
+        ));
+    }
+
+    #[test]
+    fn combine_graphs_sums_totals_and_enforces_caps() {
+        let a = sample_graph();
+        let b = build_vnode_graph(
+            "JavaSpectre",
+            &[object("node-1", "/cluster/nodes/node-1", "Node")],
+        )
+        .unwrap();
+
+        let combined = combine_graphs(&[a.clone(), b.clone()]).unwrap();
+        assert_eq!(combined.total_auet, a.total_auet + b.total_auet);
+        assert_eq!(combined.total_csp, a.total_csp + b.total_csp);
+        assert_eq!(combined, combine_graphs(&[b.clone(), a.clone()]).unwrap());
+        assert_ne!(combined.blueprint_hash, a.blueprint_hash);
+
+        let mut heavy = a.clone();
+        heavy.total_auet = MAX_TOTAL_AUET / 2 + 1;
+        match combine_graphs(&[heavy.clone(), heavy]) {
+            Err(EnergyError::GlobalCapExceeded { field, total, cap }) => {
+                assert_eq!(field, "total_auet");
+                assert_eq!(total, MAX_TOTAL_AUET + 2);
+                assert_eq!(cap, MAX_TOTAL_AUET);
+            }
+            other => panic!("expected GlobalCapExceeded, got {:?}", other),
+        }
+    }
+}
@@ -1,24 +1,53 @@
 // src/bin/javaspectre_vnodes.rs
 
-use aln_vnodes::{build_vnode_graph, MachineObject};
+use aln_orchestrator::exit::Exit;
+use aln_vnodes::{build_vnode_graph_file, verify_vnode_graph_file, VNodeFileError};
 use clap::Parser;
-use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 struct Cli {
     /// Path to MachineObjects JSON file (array of MachineObject)
-    #[arg(long)]
-    input: String,
+    #[arg(long, required_unless_present = "verify")]
+    input: Option<String>,
+    /// Instead of building, re-derive the blueprint hash of a stored graph
+    /// and fail if it does not match.
+    #[arg(long, value_name = "GRAPH_JSON", conflicts_with = "input")]
+    verify: Option<String>,
     /// Origin tag, e.g. "JavaSpectre-0.1.0"
     #[arg(long, default_value = "JavaSpectre")]
     origin: String,
 }
 
-fn main() -> anyhow::Result<()> {
+// Exit codes follow the workspace convention (see `aln_orchestrator::exit`):
+// 0 ok, 1 energy or blueprint-hash check failed, 2 usage, 3 IO error,
+// 4 malformed JSON.
+fn main() -> ExitCode {
     let cli = Cli::parse();
-    let data = fs::read_to_string(&cli.input)?;
-    let objs: Vec<MachineObject> = serde_json::from_str(&data)?;
-    let graph = build_vnode_graph(&cli.origin, &objs)?;
+    match run(&cli) {
+        Ok(()) => Exit::Ok.into(),
+        Err(e) => {
+            eprintln!("Error: {:#}", e);
+            let exit = match e.downcast_ref::<VNodeFileError>() {
+                Some(VNodeFileError::Io { source, .. }) => Exit::from(source),
+                Some(VNodeFileError::Json { .. } | VNodeFileError::Load { .. }) => Exit::Data,
+                Some(VNodeFileError::Energy { .. }) => Exit::ValidationFailed,
+                None => Exit::Internal,
+            };
+            exit.into()
+        }
+    }
+}
+
+fn run(cli: &Cli) -> anyhow::Result<()> {
+    if let Some(path) = &cli.verify {
+        let graph = verify_vnode_graph_file(Path::new(path))?;
+        println!("BLUEPRINT_HASH_VERIFIED {}", graph.blueprint_hash);
+        return Ok(());
+    }
+    let input = cli.input.as_deref().expect("clap requires --input without --verify");
+    let graph = build_vnode_graph_file(&cli.origin, Path::new(input))?;
 
     println!("{}", serde_json::to_string_pretty(&graph)?);
     eprintln!("BLUEPRINT_HASH {}", graph.blueprint_hash);
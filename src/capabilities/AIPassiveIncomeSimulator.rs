@@ -4,6 +4,7 @@
 // Models passive yields (e.g., 1000+ USD/month patterns) with adaptive ROI logic.
 // Ready for integration with XR / Web frontends via JSON or WASM.
 
+use ledger_core::ids::IdSource;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
@@ -12,6 +13,28 @@ use std::collections::HashMap;
 pub struct SimOptions {
     pub months: u32,
     pub initial_investment: f64,
+    /// Halt after this many consecutive unprofitable months. `Some(0)` is
+    /// treated as `None`: there is no limit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_on_consecutive_failures: Option<u32>,
+    /// Where the month-zero yield that month 1 grows from comes from.
+    #[serde(default)]
+    pub initial_yield: InitialYield,
+}
+
+/// Seed for the yield before any growth is applied. Month `m` reports
+/// `seed * (1 + cagr)^m`, adjusted by the adaptation term each month.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InitialYield {
+    /// The selected strategy's `base_yield`.
+    #[default]
+    BaseYield,
+    /// `SimOptions::initial_investment`.
+    InitialInvestment,
+    /// A fixed `1000.0` regardless of strategy, matching results produced
+    /// before the seed was configurable.
+    Legacy,
 }
 
 impl Default for SimOptions {
@@ -19,6 +42,8 @@ impl Default for SimOptions {
         Self {
             months: 12,
             initial_investment: 1000.0,
+            stop_on_consecutive_failures: None,
+            initial_yield: InitialYield::default(),
         }
     }
 }
@@ -53,6 +78,17 @@ pub enum XRPrimitive {
         content: String,
         position: [f32; 3],
     },
+    /// Dial showing `value` on a `0..=max` scale.
+    Gauge {
+        value: f64,
+        position: [f32; 3],
+        max: f64,
+    },
+    /// Ordered series, one entry per month.
+    Timeline {
+        steps: Vec<f64>,
+        position: [f32; 3],
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -86,6 +122,7 @@ pub struct AIPassiveIncomeSimulator {
     xr_enabled: bool,
     schemas: HashMap<String, StrategySchema>,
     weights: f64,
+    ids: Option<Box<dyn IdSource + Send>>,
 }
 
 impl AIPassiveIncomeSimulator {
@@ -118,33 +155,60 @@ impl AIPassiveIncomeSimulator {
             xr_enabled,
             schemas,
             weights: 1.0,
+            ids: None,
         }
     }
 
+    /// Draw `sim_id`s from `ids` instead of hashing the run's `SimOptions`,
+    /// e.g. to tell apart repeated runs with identical options.
+    pub fn with_id_source(mut self, ids: impl IdSource + Send + 'static) -> Self {
+        self.ids = Some(Box::new(ids));
+        self
+    }
+
     pub fn simulate(&mut self, options: Option<SimOptions>) -> SimResult {
         let opts = options.unwrap_or_default();
-        let sim_id = Self::compute_sim_id(&opts);
+        let sim_id = match self.ids.as_mut() {
+            Some(ids) => ids.next(),
+            None => Self::compute_sim_id(&opts),
+        };
 
+        // Cloned so the per-month loop can take `&mut self`.
         let schema = self
             .schemas
             .get(&self.strategy)
-            .unwrap_or_else(|| self.schemas.get("ai-bots").unwrap());
+            .or_else(|| self.schemas.get("ai-bots"))
+            .cloned()
+            .unwrap();
 
         let mut path: Vec<PathStep> = Vec::new();
-        let mut yield_val = 1000.0_f64;
+        let mut yield_val = match opts.initial_yield {
+            InitialYield::BaseYield => schema.base_yield,
+            InitialYield::InitialInvestment => opts.initial_investment,
+            InitialYield::Legacy => 1000.0_f64,
+        };
         let mut roi_acc = 0.0_f64;
+        let mut peak_eff = 0.0_f64;
+        let mut consecutive_failures = 0_u32;
+        let mut stopped_at = None;
 
         for month in 1..=opts.months {
-            let scaled_yield = self.calc_scaled_yield(schema, yield_val, month);
+            let scaled_yield = self.calc_scaled_yield(&schema, yield_val, month);
             let cost = opts.initial_investment / opts.months as f64;
             let eff = self.calc_efficiency(scaled_yield, cost);
+            peak_eff = peak_eff.max(eff);
             roi_acc += self.calc_roi(scaled_yield, eff);
 
             let feedback = self.cybernetic_feedback(roi_acc, 0.3);
+            if feedback.success {
+                consecutive_failures = 0;
+            } else {
+                consecutive_failures += 1;
+            }
             let adapt = self.neuromorphic_update(feedback.success);
 
             let xr_blueprint = if self.xr_enabled {
-                Some(self.generate_xr_blueprint(month, roi_acc))
+                Some(self.generate_xr_blueprint(month, roi_acc, eff, peak_eff))
             } else {
                 None
             };
@@ -160,6 +224,14 @@ impl AIPassiveIncomeSimulator {
             });
 
             yield_val += adapt * 100.0;
+
+            if opts
+                .stop_on_consecutive_failures
+                .is_some_and(|limit| limit > 0 && consecutive_failures >= limit)
+            {
+                stopped_at = Some(month);
+                break;
+            }
         }
 
         let proofs = Self::generate_proofs(&path);
@@ -169,7 +241,10 @@ impl AIPassiveIncomeSimulator {
             path,
             final_roi: roi_acc,
             proofs,
-            summary: "Simulation complete; scale for 2026 income.".to_string(),
+            summary: match stopped_at {
+                Some(month) => format!("Simulation early-stopped at month {}.", month),
+                None => "Simulation complete; scale for 2026 income.".to_string(),
+            },
         }
     }
 
@@ -229,18 +304,30 @@ impl AIPassiveIncomeSimulator {
         self.weights
     }
 
-    fn generate_xr_blueprint(&self, month: u32, roi: f64) -> XRBlueprint {
+    /// `efficiency` is drawn on a gauge scaled to the best month so far.
+    fn generate_xr_blueprint(
+        &self,
+        month: u32,
+        roi: f64,
+        efficiency: f64,
+        peak_efficiency: f64,
+    ) -> XRBlueprint {
         XRBlueprint {
             scene: "Income Simulator".to_string(),
             elements: vec![
                 XRPrimitive::Chart {
-                    data: format!("Month {} ROI: {:.2}", month, roi),
+                    data: format!("Month {} ROI: {}", month, format_fixed(roi, 2)),
                     position: [0.0, 1.5, -2.0],
                 },
                 XRPrimitive::Text {
                     content: "Strategy Path".to_string(),
                     position: [0.0, 2.0, -2.0],
                 },
+                XRPrimitive::Gauge {
+                    value: efficiency,
+                    position: [1.0, 1.5, -2.0],
+                    max: peak_efficiency.max(efficiency),
+                },
             ],
             interactions: "Gesture-based projection tweaks".to_string(),
         }
@@ -259,12 +346,30 @@ impl AIPassiveIncomeSimulator {
         let final_roi = path.last().map(|p| p.cumulative_roi).unwrap_or(0.0);
 
         Proofs {
-            yield_proof: format!("Avg Yield={:.2}; Matches >=1000 pattern", avg_yield),
-            roi_proof: format!("Cumulative ROI≈{:.2}; ~30% growth band", final_roi),
+            yield_proof: format!(
+                "Avg Yield={}; Matches >=1000 pattern",
+                format_fixed(avg_yield, 2)
+            ),
+            roi_proof: format!(
+                "Cumulative ROI~={}; ~30% growth band",
+                format_fixed(final_roi, 2)
+            ),
         }
     }
 }
 
+/// ASCII fixed-point text for proof strings. Core's `{:.N}` rounds the exact
+/// binary value half-to-even in software, independent of the platform's C
+/// library; `-0.00` is folded to `0.00` so a tiny negative doesn't change
+/// the proof text.
+fn format_fixed(value: f64, decimals: usize) -> String {
+    let text = format!("{:.*}", decimals, value);
+    match text.strip_prefix('-') {
+        Some(digits) if digits.bytes().all(|b| b == b'0' || b == b'.') => digits.to_string(),
+        _ => text,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -275,11 +380,154 @@ mod tests {
         let result = sim.simulate(Some(SimOptions {
             months: 6,
             initial_investment: 1200.0,
+            ..SimOptions::default()
         }));
 
         assert_eq!(result.path.len(), 6);
         assert!(!result.sim_id.is_empty());
     }
+
+    #[test]
+    fn default_sim_id_is_derived_from_options() {
+        let mut sim = AIPassiveIncomeSimulator::new(None, false);
+        let first = sim.simulate(None).sim_id;
+        assert_eq!(sim.simulate(None).sim_id, first);
+        let longer = SimOptions {
+            months: 24,
+            ..SimOptions::default()
+        };
+        assert_ne!(sim.simulate(Some(longer)).sim_id, first);
+    }
+
+    #[test]
+    fn seeded_id_source_makes_sim_ids_reproducible() {
+        let sim_ids = || {
+            let mut sim = AIPassiveIncomeSimulator::new(None, false)
+                .with_id_source(ledger_core::ids::SeededIdSource::new(7));
+            let first = sim.simulate(None).sim_id;
+            (first, sim.simulate(None).sim_id)
+        };
+        let (a, b) = sim_ids();
+        assert_ne!(a, b);
+        assert_eq!(sim_ids(), (a, b));
+    }
+
+    #[test]
+    fn stops_after_consecutive_failures() {
+        // Cost per month dwarfs the yield, so every month reports a loss.
+        let mut sim = AIPassiveIncomeSimulator::new(Some("ai-bots"), false);
+        let result = sim.simulate(Some(SimOptions {
+            months: 12,
+            initial_investment: 1.0e12,
+            stop_on_consecutive_failures: Some(3),
+            ..SimOptions::default()
+        }));
+
+        assert_eq!(result.path.len(), 3);
+        assert!(result.path.iter().all(|step| !step.feedback.success));
+        assert_eq!(result.summary, "Simulation early-stopped at month 3.");
+        assert_eq!(result.final_roi, result.path[2].cumulative_roi);
+
+        let unlimited = sim.simulate(Some(SimOptions {
+            months: 12,
+            initial_investment: 1.0e12,
+            stop_on_consecutive_failures: Some(0),
+            ..SimOptions::default()
+        }));
+        assert_eq!(unlimited.path.len(), 12);
+        assert_eq!(
+            unlimited.summary,
+            "Simulation complete; scale for 2026 income."
+        );
+    }
+
+    #[test]
+    fn first_month_grows_from_the_configured_seed() {
+        let first_yield = |initial_yield| {
+            let mut sim = AIPassiveIncomeSimulator::new(Some("affiliates"), false);
+            sim.simulate(Some(SimOptions {
+                months: 1,
+                initial_investment: 500.0,
+                initial_yield,
+                ..SimOptions::default()
+            }))
+            .path[0]
+                .yield_val
+        };
+
+        // affiliates: base_yield 2000, cagr 0.40.
+        assert!((first_yield(InitialYield::BaseYield) - 2000.0 * 1.4).abs() < 1e-9);
+        assert!((first_yield(InitialYield::InitialInvestment) - 500.0 * 1.4).abs() < 1e-9);
+        assert!((first_yield(InitialYield::Legacy) - 1000.0 * 1.4).abs() < 1e-9);
+
+        let opts: SimOptions =
+            serde_json::from_str(r#"{"months": 3, "initial_investment": 10.0}"#).unwrap();
+        assert_eq!(opts.initial_yield, InitialYield::BaseYield);
+    }
+
+    #[test]
+    fn proof_strings_are_exact_and_ascii() {
+        let mut sim = AIPassiveIncomeSimulator::new(Some("ai-bots"), false);
+        let result = sim.simulate(Some(SimOptions {
+            months: 3,
+            initial_investment: 1200.0,
+            ..SimOptions::default()
+        }));
+        assert_eq!(result.proofs.yield_proof, "Avg Yield=719.23; Matches >=1000 pattern");
+        assert_eq!(result.proofs.roi_proof, "Cumulative ROI~=4398.06; ~30% growth band");
+        assert!(result.proofs.roi_proof.is_ascii());
+
+        assert_eq!(format_fixed(0.125, 2), "0.12");
+        assert_eq!(format_fixed(0.375, 2), "0.38");
+        assert_eq!(format_fixed(-0.001, 2), "0.00");
+        assert_eq!(format_fixed(-1.5, 0), "-2");
+    }
+
+    #[test]
+    fn blueprint_with_new_primitives_round_trips() {
+        let json = r#"{
+            "scene": "Income Simulator",
+            "elements": [
+                {"type": "Chart", "data": "Month 1 ROI: 0.50", "position": [0.0, 1.5, -2.0]},
+                {"type": "Text", "content": "Strategy Path", "position": [0.0, 2.0, -2.0]},
+                {"type": "Gauge", "value": 0.75, "position": [1.0, 1.5, -2.0], "max": 1.0},
+                {"type": "Timeline", "steps": [1.0, 2.5, 4.0], "position": [0.0, 0.5, -2.0]}
+            ],
+            "interactions": "Gesture-based projection tweaks"
+        }"#;
+        let blueprint: XRBlueprint = serde_json::from_str(json).unwrap();
+        assert!(matches!(
+            blueprint.elements[2],
+            XRPrimitive::Gauge { value, max, .. } if value == 0.75 && max == 1.0
+        ));
+        match &blueprint.elements[3] {
+            XRPrimitive::Timeline { steps, .. } => assert_eq!(steps, &vec![1.0, 2.5, 4.0]),
+            other => panic!("expected Timeline, got {:?}", other),
+        }
+
+        let reencoded = serde_json::to_value(&blueprint).unwrap();
+        assert_eq!(
+            reencoded,
+            serde_json::from_str::<serde_json::Value>(json).unwrap()
+        );
+
+        let mut sim = AIPassiveIncomeSimulator::new(Some("content"), true);
+        let result = sim.simulate(Some(SimOptions::default()));
+        let gauges: Vec<(f64, f64)> = result
+            .path
+            .iter()
+            .flat_map(|step| step.xr_blueprint.as_ref().unwrap().elements.clone())
+            .filter_map(|el| match el {
+                XRPrimitive::Gauge { value, max, .. } => Some((value, max)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(gauges.len(), result.path.len());
+        for ((value, max), step) in gauges.iter().zip(&result.path) {
+            assert_eq!(*value, step.efficiency);
+            assert!(value <= max);
+        }
+    }
 }
 
 // Example CLI usage (put in main.rs or a separate binary):
@@ -289,6 +537,7 @@ mod tests {
 //     let result = sim.simulate(Some(SimOptions {
 //         months: 6,
 //         initial_investment: 1200.0,
+//         ..SimOptions::default()
 //     }));
 //     println!("{}", serde_json::to_string_pretty(&result).unwrap());
 // }
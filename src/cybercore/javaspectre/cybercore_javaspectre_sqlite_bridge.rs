@@ -1,5 +1,10 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::num::NonZeroUsize;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use lru::LruCache;
+use regex::RegexSet;
 use rusqlite::{params, Connection, OpenFlags, Row, NO_PARAMS};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -16,6 +21,16 @@ pub enum JavaspectreError {
     Hash(String),
     #[error("Invalid schema: {0}")]
     Schema(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Invalid config: {0}")]
+    Config(String),
+    #[error("Database busy: write still locked after {attempts} attempts")]
+    Busy { attempts: u32 },
+    #[error("Store not closed: {handles} other handle(s) still share the connection")]
+    StillShared { handles: usize },
+    #[error("Cancelled after committing {completed} row(s)")]
+    Cancelled { completed: usize },
 }
 
 /// Span representation in the Cybercore-Javaspectre bridge.
@@ -29,6 +44,16 @@ pub struct SpanRecord {
     pub span_name: String,
     pub span_kind: Option<String>,
     pub status_code: Option<String>,
+    /// Numeric `http.status_code`, kept separately from the OTel status
+    /// string so range queries (`>= 500`) can use an index.
+    #[serde(default)]
+    pub http_status_code: Option<i64>,
+    /// `rpc.method` for gRPC and other RPC spans.
+    #[serde(default)]
+    pub rpc_method: Option<String>,
+    /// `messaging.destination` (or `.name`) for queue/topic spans.
+    #[serde(default)]
+    pub messaging_destination: Option<String>,
     pub service_name: Option<String>,
     pub http_method: Option<String>,
     pub http_route: Option<String>,
@@ -95,14 +120,56 @@ pub struct SnapshotV1Record {
 }
 
 /// Bridge-level configuration.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct JavaspectreConfig {
     pub path: String,
     pub read_only: bool,
     pub foreign_keys: bool,
     pub wal_mode: bool,
+    /// Never overwrite an existing span; `upsert_span` reports the skipped write.
+    pub append_only: bool,
+    /// SQLite `busy_timeout` applied to the connection, in milliseconds.
+    pub busy_timeout_ms: u64,
+    /// Extra attempts for a write that still fails with `SQLITE_BUSY` or
+    /// `SQLITE_LOCKED` after the busy timeout, with doubling backoff.
+    pub busy_retries: u32,
+    /// Make `ingest_otel_span` a no-op for spans whose content hash is
+    /// already stored, so ingestion can be retried safely.
+    pub dedup_ingest: bool,
+    /// Pass span `attributes`/`resource` through `canonical_json` before
+    /// storing, so numbers that compare equal store identical TEXT (`2.0` is
+    /// stored as `2`). This rewrites values, not just formatting: other
+    /// floats are rounded to 15 significant digits and subnormals become `0`,
+    /// so it is off by default. Key order needs no help; objects are always
+    /// stored with sorted keys.
+    pub canonical_attributes: bool,
+    /// Span attribute keys (e.g. `db.system`) that get an expression index
+    /// on `json_extract(attributes, ...)` for `spans_with_attribute`.
+    pub indexed_attributes: Vec<String>,
+    /// Keep up to this many parsed `dom_tree` values in an LRU keyed by
+    /// `sheet_id`; 0 disables the cache.
+    pub dom_tree_cache_size: usize,
+    /// Rows per transaction for batch maintenance (`recompute_dom_stability_scores`,
+    /// `flatten_span_attributes`); cancellation is checked between chunks.
+    pub maintenance_chunk_size: usize,
+    /// Regexes (any match) marking a DOM element id as regenerated per render.
+    /// Such ids lower the stability score, count toward `dynamic_id_count`
+    /// and are left out of selectors. Defaults to
+    /// `DEFAULT_DYNAMIC_ID_PATTERNS`.
+    pub dynamic_id_patterns: Vec<String>,
 }
 
+/// Default `dynamic_id_patterns`: session/A-B markers, UUIDs, runs of 8+ hex
+/// digits, and 10+ digit (timestamp-like) suffixes. Plain numbered ids such
+/// as `section2` are not dynamic.
+pub const DEFAULT_DYNAMIC_ID_PATTERNS: &[&str] = &[
+    "uuid|session|abtest",
+    "(?i)[0-9a-f]{8}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{4}-[0-9a-f]{12}",
+    "(?i)(?:^|[^0-9a-z])[0-9a-f]{8,}(?:$|[^0-9a-z])",
+    "[0-9]{10,}$",
+];
+
 impl Default for JavaspectreConfig {
     fn default() -> Self {
         Self {
@@ -110,14 +177,186 @@ impl Default for JavaspectreConfig {
             read_only: false,
             foreign_keys: true,
             wal_mode: true,
+            append_only: false,
+            busy_timeout_ms: 5_000,
+            busy_retries: 3,
+            dedup_ingest: false,
+            canonical_attributes: false,
+            indexed_attributes: Vec::new(),
+            dom_tree_cache_size: 0,
+            maintenance_chunk_size: 1_000,
+            dynamic_id_patterns: DEFAULT_DYNAMIC_ID_PATTERNS
+                .iter()
+                .map(|p| p.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl JavaspectreConfig {
+    /// Load a config from a `.toml` file, or JSON for any other extension.
+    /// Omitted fields take their `Default` values.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, JavaspectreError> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)?;
+        let is_toml = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.eq_ignore_ascii_case("toml"))
+            .unwrap_or(false);
+        let config: Self = if is_toml {
+            toml::from_str(&text)
+                .map_err(|e| JavaspectreError::Config(format!("{}: {}", path.display(), e)))?
+        } else {
+            serde_json::from_str(&text)?
+        };
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject settings `open` cannot honor.
+    pub fn validate(&self) -> Result<(), JavaspectreError> {
+        if self.path.trim().is_empty() {
+            return Err(JavaspectreError::Config("path must not be empty".into()));
+        }
+        if self.maintenance_chunk_size == 0 {
+            return Err(JavaspectreError::Config(
+                "maintenance_chunk_size must be at least 1".into(),
+            ));
+        }
+        if self.read_only && self.wal_mode {
+            return Err(JavaspectreError::Config(
+                "wal_mode requires write access; disable it for read_only stores".into(),
+            ));
         }
+        for key in &self.indexed_attributes {
+            attribute_json_path(key)?;
+        }
+        compile_dynamic_id_patterns(&self.dynamic_id_patterns)?;
+        Ok(())
+    }
+}
+
+fn compile_dynamic_id_patterns(patterns: &[String]) -> Result<RegexSet, JavaspectreError> {
+    for pattern in patterns {
+        regex::Regex::new(pattern).map_err(|e| {
+            JavaspectreError::Config(format!("dynamic_id_patterns entry {:?}: {}", pattern, e))
+        })?;
     }
+    RegexSet::new(patterns).map_err(|e| JavaspectreError::Config(e.to_string()))
 }
 
+/// Columns the queries in this module rely on, per table. Keep in step with
+/// `init_schema`, `ensure_column` migrations and `init_score_table`.
+const EXPECTED_COLUMNS: &[(&str, &[&str])] = &[
+    (
+        "spans",
+        &[
+            "span_id",
+            "trace_id",
+            "parent_span_id",
+            "start_time_ns",
+            "end_time_ns",
+            "span_name",
+            "span_kind",
+            "status_code",
+            "http_status_code",
+            "service_name",
+            "http_method",
+            "http_route",
+            "correlation_id",
+            "attributes",
+            "resource",
+            "raw_span",
+            "content_hash",
+            "rpc_method",
+            "messaging_destination",
+        ],
+    ),
+    ("span_attributes", &["span_id", "key", "value_text", "value_num"]),
+    ("span_attributes_state", &["span_id", "attributes_hash"]),
+    (
+        "dom_snapshots",
+        &["snapshot_id", "trace_id", "correlation_id", "captured_at_ns", "raw_dom"],
+    ),
+    (
+        "dom_sheets",
+        &[
+            "sheet_id",
+            "snapshot_id",
+            "trace_id",
+            "correlation_id",
+            "dom_stability_score",
+            "dom_tree",
+            "noise_stats",
+        ],
+    ),
+    (
+        "har_entries",
+        &[
+            "entry_id",
+            "correlation_id",
+            "started_at_ns",
+            "method",
+            "url",
+            "status",
+            "request_json",
+            "response_json",
+            "raw_entry",
+        ],
+    ),
+    (
+        "json_schemas",
+        &[
+            "schema_id",
+            "endpoint_key",
+            "version",
+            "inferred_at_ns",
+            "confidence",
+            "schema_json",
+        ],
+    ),
+    (
+        "snapshots_v1",
+        &["snapshot_hash", "created_at_ns", "kind", "payload"],
+    ),
+    (
+        "cluster_summaries",
+        &[
+            "correlation_id",
+            "span_count",
+            "error_count",
+            "total_duration_ns",
+            "earliest_ns",
+            "latest_ns",
+            "dom_sheet_count",
+        ],
+    ),
+    (
+        "cluster_scores",
+        &[
+            "correlation_id",
+            "stability_score",
+            "novelty_score",
+            "drift_score",
+            "updated_at_ns",
+        ],
+    ),
+];
+
 /// Main handle into the cybernetic storage core for Javaspectre.
 #[derive(Clone)]
 pub struct JavaspectreStore {
     conn: Arc<Connection>,
+    append_only: bool,
+    busy_retries: u32,
+    dedup_ingest: bool,
+    canonical_attributes: bool,
+    indexed_attributes: Vec<String>,
+    dom_trees: Option<Arc<Mutex<LruCache<String, Value>>>>,
+    dom_tree_parses: Arc<AtomicUsize>,
+    maintenance_chunk_size: usize,
+    dynamic_ids: Arc<RegexSet>,
 }
 
 impl JavaspectreStore {
@@ -137,15 +376,72 @@ impl JavaspectreStore {
         if config.wal_mode {
             conn.pragma_update(None, "journal_mode", &"WAL")?;
         }
+        conn.busy_timeout(std::time::Duration::from_millis(config.busy_timeout_ms))?;
 
         let store = Self {
             conn: Arc::new(conn),
+            append_only: config.append_only,
+            busy_retries: config.busy_retries,
+            dedup_ingest: config.dedup_ingest,
+            canonical_attributes: config.canonical_attributes,
+            indexed_attributes: config.indexed_attributes.clone(),
+            dom_trees: NonZeroUsize::new(config.dom_tree_cache_size)
+                .map(|size| Arc::new(Mutex::new(LruCache::new(size)))),
+            dom_tree_parses: Arc::new(AtomicUsize::new(0)),
+            maintenance_chunk_size: config.maintenance_chunk_size.max(1),
+            dynamic_ids: Arc::new(compile_dynamic_id_patterns(&config.dynamic_id_patterns)?),
         };
 
         store.init_schema()?;
+        store.check_schema()?;
         Ok(store)
     }
 
+    /// Compare every table's columns (`PRAGMA table_info`) with
+    /// `EXPECTED_COLUMNS`, failing with a `Schema` error that lists each
+    /// missing `table.column`. Catches databases created by an older build
+    /// whose tables `CREATE TABLE IF NOT EXISTS` left untouched. Optional
+    /// tables (e.g. `cluster_scores`) are only checked once they exist.
+    pub fn check_schema(&self) -> Result<(), JavaspectreError> {
+        let conn = &*self.conn;
+        let mut missing = Vec::new();
+        for (table, expected) in EXPECTED_COLUMNS {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let present = stmt
+                .query_map(NO_PARAMS, |row| row.get::<_, String>(1))?
+                .collect::<rusqlite::Result<BTreeSet<String>>>()?;
+            if present.is_empty() {
+                continue;
+            }
+            missing.extend(
+                expected
+                    .iter()
+                    .filter(|column| !present.contains(**column))
+                    .map(|column| format!("{}.{}", table, column)),
+            );
+        }
+        if missing.is_empty() {
+            Ok(())
+        } else {
+            Err(JavaspectreError::Schema(format!(
+                "database is missing columns: {}",
+                missing.join(", ")
+            )))
+        }
+    }
+
+    /// Checkpoint the WAL into the main database, truncating the `-wal` file,
+    /// and close the connection. Fails with `StillShared` (leaving the
+    /// connection open for the remaining clones) unless this is the last
+    /// handle.
+    pub fn close(self) -> Result<(), JavaspectreError> {
+        let conn = Arc::try_unwrap(self.conn).map_err(|conn| JavaspectreError::StillShared {
+            handles: Arc::strong_count(&conn) - 1,
+        })?;
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", NO_PARAMS, |_| Ok(()))?;
+        conn.close().map_err(|(_, e)| JavaspectreError::from(e))
+    }
+
     fn init_schema(&self) -> Result<(), JavaspectreError> {
         let conn = &*self.conn;
 
@@ -161,6 +457,7 @@ impl JavaspectreStore {
               span_name      TEXT NOT NULL,
               span_kind      TEXT,
               status_code    TEXT,
+              http_status_code INTEGER,
               service_name   TEXT,
               http_method    TEXT,
               http_route     TEXT,
@@ -190,6 +487,51 @@ impl JavaspectreStore {
             "#,
         )?;
 
+        // Databases created before http_status_code existed.
+        Self::ensure_column(conn, "spans", "http_status_code", "INTEGER")?;
+        Self::ensure_column(conn, "spans", "content_hash", "TEXT")?;
+        Self::ensure_column(conn, "spans", "rpc_method", "TEXT")?;
+        Self::ensure_column(conn, "spans", "messaging_destination", "TEXT")?;
+        conn.execute_batch(
+            r#"
+            CREATE INDEX IF NOT EXISTS idx_spans_http_status_code
+              ON spans(http_status_code);
+
+            CREATE INDEX IF NOT EXISTS idx_spans_rpc_method
+              ON spans(rpc_method);
+
+            CREATE INDEX IF NOT EXISTS idx_spans_messaging_destination
+              ON spans(messaging_destination);
+
+            CREATE INDEX IF NOT EXISTS idx_spans_content_hash
+              ON spans(content_hash);
+            "#,
+        )?;
+
+        // Flattened span attributes (EAV), filled by flatten_span_attributes.
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS span_attributes (
+              span_id    TEXT NOT NULL,
+              key        TEXT NOT NULL,
+              value_text TEXT,
+              value_num  REAL,
+              PRIMARY KEY (span_id, key)
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_span_attributes_key_num
+              ON span_attributes(key, value_num);
+
+            CREATE INDEX IF NOT EXISTS idx_span_attributes_key_text
+              ON span_attributes(key, value_text);
+
+            CREATE TABLE IF NOT EXISTS span_attributes_state (
+              span_id         TEXT PRIMARY KEY,
+              attributes_hash TEXT NOT NULL
+            );
+            "#,
+        )?;
+
         // DOM snapshots
         conn.execute_batch(
             r#"
@@ -302,26 +644,93 @@ impl JavaspectreStore {
             "#,
         )?;
 
+        // Materialized per-correlation summaries (see `materialize_cluster_summary`)
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS cluster_summaries (
+              correlation_id     TEXT PRIMARY KEY,
+              span_count         INTEGER NOT NULL,
+              error_count        INTEGER NOT NULL,
+              total_duration_ns  INTEGER NOT NULL,
+              earliest_ns        INTEGER,
+              latest_ns          INTEGER,
+              dom_sheet_count    INTEGER NOT NULL
+            );
+            "#,
+        )?;
+
+        for key in &self.indexed_attributes {
+            conn.execute_batch(&format!(
+                "CREATE INDEX IF NOT EXISTS {} ON spans({});",
+                attribute_index_name(key),
+                attribute_extract_sql(key)?
+            ))?;
+        }
+
         Ok(())
     }
 
-    /// Insert or upsert a span.
-    pub fn upsert_span(&self, span: &SpanRecord) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
-            r#"
-            INSERT INTO spans (
-              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
-              span_name, span_kind, status_code, service_name,
-              http_method, http_route, correlation_id,
-              attributes, resource, raw_span
-            ) VALUES (
-              ?1, ?2, ?3, ?4, ?5,
-              ?6, ?7, ?8, ?9,
-              ?10, ?11, ?12,
-              ?13, ?14, ?15
-            )
-            ON CONFLICT(span_id) DO UPDATE SET
+    /// Run `op`, retrying with doubling backoff (10ms, 20ms, ...) while SQLite
+    /// reports the database busy or locked. Gives up with
+    /// `JavaspectreError::Busy` after `busy_retries` extra attempts.
+    fn retry_busy<T>(
+        &self,
+        mut op: impl FnMut() -> Result<T, JavaspectreError>,
+    ) -> Result<T, JavaspectreError> {
+        let mut attempt = 0;
+        loop {
+            match op() {
+                Err(e) if is_busy(&e) => {
+                    if attempt >= self.busy_retries {
+                        return Err(JavaspectreError::Busy {
+                            attempts: attempt + 1,
+                        });
+                    }
+                    std::thread::sleep(std::time::Duration::from_millis(10 << attempt.min(6)));
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    fn execute_write<P>(&self, sql: &str, params: P) -> Result<usize, JavaspectreError>
+    where
+        P: IntoIterator + Clone,
+        P::Item: rusqlite::ToSql,
+    {
+        self.retry_busy(|| Ok(self.conn.execute(sql, params.clone())?))
+    }
+
+    /// Add `column` to `table` unless it is already present.
+    fn ensure_column(
+        conn: &Connection,
+        table: &str,
+        column: &str,
+        decl: &str,
+    ) -> Result<(), JavaspectreError> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+        let names = stmt.query_map(NO_PARAMS, |row| row.get::<_, String>(1))?;
+        for name in names {
+            if name? == column {
+                return Ok(());
+            }
+        }
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {};",
+            table, column, decl
+        ))?;
+        Ok(())
+    }
+
+    /// Insert or upsert a span. Returns whether the row was written: in
+    /// `append_only` mode an existing `span_id` is left intact and `false` is
+    /// returned so callers can log the attempted overwrite.
+    pub fn upsert_span(&self, span: &SpanRecord) -> Result<bool, JavaspectreError> {
+        let on_conflict = if self.append_only {
+            "ON CONFLICT(span_id) DO NOTHING"
+        } else {
+            r#"ON CONFLICT(span_id) DO UPDATE SET
               trace_id = excluded.trace_id,
               parent_span_id = excluded.parent_span_id,
               start_time_ns = excluded.start_time_ns,
@@ -335,8 +744,61 @@ impl JavaspectreStore {
               correlation_id = excluded.correlation_id,
               attributes = excluded.attributes,
               resource = excluded.resource,
-              raw_span = excluded.raw_span
+              raw_span = excluded.raw_span,
+              http_status_code = excluded.http_status_code,
+              content_hash = excluded.content_hash,
+              rpc_method = excluded.rpc_method,
+              messaging_destination = excluded.messaging_destination"#
+        };
+        self.retry_busy(|| Ok(Self::write_span(&self.conn, span, on_conflict)? > 0))
+    }
+
+    /// Insert `span` unless a span with the same `span_content_hash` is
+    /// already stored. Returns `false` for a duplicate; nothing is rewritten.
+    pub fn insert_span_if_absent(&self, span: &SpanRecord) -> Result<bool, JavaspectreError> {
+        let hash = span_content_hash(span);
+        self.retry_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            let present: bool = tx.query_row(
+                "SELECT EXISTS(SELECT 1 FROM spans WHERE content_hash = ?1)",
+                params![hash],
+                |row| row.get(0),
+            )?;
+            if present {
+                return Ok(false);
+            }
+            let written = Self::write_span(&tx, span, "ON CONFLICT(span_id) DO NOTHING")?;
+            tx.commit()?;
+            Ok(written > 0)
+        })
+    }
+
+    fn write_span(
+        conn: &Connection,
+        span: &SpanRecord,
+        on_conflict: &str,
+    ) -> Result<usize, rusqlite::Error> {
+        let sql = format!(
+            r#"
+            INSERT INTO spans (
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              span_name, span_kind, status_code, service_name,
+              http_method, http_route, correlation_id,
+              attributes, resource, raw_span, http_status_code, content_hash,
+              rpc_method, messaging_destination
+            ) VALUES (
+              ?1, ?2, ?3, ?4, ?5,
+              ?6, ?7, ?8, ?9,
+              ?10, ?11, ?12,
+              ?13, ?14, ?15, ?16, ?17,
+              ?18, ?19
+            )
+            {}
             "#,
+            on_conflict
+        );
+        conn.execute(
+            &sql,
             params![
                 span.span_id,
                 span.trace_id,
@@ -352,15 +814,17 @@ impl JavaspectreStore {
                 span.correlation_id,
                 span.attributes.to_string(),
                 span.resource.to_string(),
-                span.raw_span.to_string()
+                span.raw_span.to_string(),
+                span.http_status_code,
+                span_content_hash(span),
+                span.rpc_method,
+                span.messaging_destination
             ],
-        )?;
-        Ok(())
+        )
     }
 
     pub fn insert_dom_snapshot(&self, snap: &DomSnapshotRecord) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
+        self.execute_write(
             r#"
             INSERT OR REPLACE INTO dom_snapshots (
               snapshot_id, trace_id, correlation_id, captured_at_ns, raw_dom
@@ -378,8 +842,7 @@ impl JavaspectreStore {
     }
 
     pub fn insert_dom_sheet(&self, sheet: &DomSheetRecord) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
+        self.execute_write(
             r#"
             INSERT OR REPLACE INTO dom_sheets (
               sheet_id, snapshot_id, trace_id, correlation_id,
@@ -396,12 +859,17 @@ impl JavaspectreStore {
                 sheet.noise_stats.as_ref().map(|v| v.to_string())
             ],
         )?;
+        if let Some(cache) = &self.dom_trees {
+            cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .pop(&sheet.sheet_id);
+        }
         Ok(())
     }
 
     pub fn insert_har_entry(&self, entry: &HarEntryRecord) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
+        self.execute_write(
             r#"
             INSERT OR REPLACE INTO har_entries (
               entry_id, correlation_id, started_at_ns, method,
@@ -424,8 +892,7 @@ impl JavaspectreStore {
     }
 
     pub fn insert_json_schema(&self, schema: &JsonSchemaRecord) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
+        self.execute_write(
             r#"
             INSERT OR REPLACE INTO json_schemas (
               schema_id, endpoint_key, version,
@@ -445,8 +912,7 @@ impl JavaspectreStore {
     }
 
     pub fn insert_snapshot_v1(&self, snap: &SnapshotV1Record) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
-        conn.execute(
+        self.execute_write(
             r#"
             INSERT OR REPLACE INTO snapshots_v1 (
               snapshot_hash, created_at_ns, kind, payload
@@ -462,6 +928,47 @@ impl JavaspectreStore {
         Ok(())
     }
 
+    /// Deep-merge `patch` into a stored span's `attributes` for post-ingest
+    /// enrichment. Nested objects are merged key by key; any other value in
+    /// `patch` (including `null`) replaces the stored one. Other span columns
+    /// are left untouched.
+    pub fn enrich_span_attributes(
+        &self,
+        span_id: &str,
+        patch: &Value,
+    ) -> Result<(), JavaspectreError> {
+        self.retry_busy(|| self.enrich_span_attributes_once(span_id, patch))
+    }
+
+    fn enrich_span_attributes_once(
+        &self,
+        span_id: &str,
+        patch: &Value,
+    ) -> Result<(), JavaspectreError> {
+        let conn = &*self.conn;
+        let tx = conn.unchecked_transaction()?;
+        let mut attributes = {
+            let mut stmt = tx.prepare("SELECT attributes FROM spans WHERE span_id = ?1")?;
+            let mut rows = stmt.query(params![span_id])?;
+            match rows.next()? {
+                Some(row) => json_column(row, 0)?,
+                None => {
+                    return Err(JavaspectreError::Schema(format!(
+                        "no span with span_id {}",
+                        span_id
+                    )))
+                }
+            }
+        };
+        merge_json(&mut attributes, patch);
+        tx.execute(
+            "UPDATE spans SET attributes = ?2 WHERE span_id = ?1",
+            params![span_id, attributes.to_string()],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
     /// Example query: find slow spans with related DOM sheets.
     pub fn find_slow_spans_with_dom(
         &self,
@@ -475,11 +982,12 @@ impl JavaspectreStore {
               span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
               span_name, span_kind, status_code, service_name,
               http_method, http_route, correlation_id,
-              attributes, resource, raw_span
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
             FROM spans
             WHERE
               (end_time_ns - start_time_ns) >= ?1
-            ORDER BY (end_time_ns - start_time_ns) DESC
+            ORDER BY (end_time_ns - start_time_ns) DESC, span_id ASC
             LIMIT ?2
             "#,
         )?;
@@ -497,6 +1005,263 @@ impl JavaspectreStore {
         Ok(results)
     }
 
+    /// `find_slow_spans_with_dom` with a per-`span_kind` duration threshold
+    /// (e.g. `{"client": 20ms, "server": 200ms}`); spans whose kind is absent
+    /// from `thresholds`, or unset, use `default_ns`.
+    pub fn find_slow_spans_by_kind(
+        &self,
+        thresholds: &HashMap<String, i64>,
+        default_ns: i64,
+        limit: i64,
+    ) -> Result<Vec<(SpanRecord, Vec<DomSheetRecord>)>, JavaspectreError> {
+        let mut kinds: Vec<(&String, &i64)> = thresholds.iter().collect();
+        kinds.sort();
+        let mut threshold = String::from("?1");
+        if !kinds.is_empty() {
+            threshold = String::from("CASE span_kind");
+            for i in 0..kinds.len() {
+                threshold.push_str(&format!(" WHEN ?{} THEN ?{}", 3 + 2 * i, 4 + 2 * i));
+            }
+            threshold.push_str(" ELSE ?1 END");
+        }
+        let mut values: Vec<&dyn rusqlite::ToSql> = vec![&default_ns, &limit];
+        for (kind, ns) in &kinds {
+            values.push(*kind);
+            values.push(*ns);
+        }
+
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              span_name, span_kind, status_code, service_name,
+              http_method, http_route, correlation_id,
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
+            FROM spans
+            WHERE
+              (end_time_ns - start_time_ns) >= {}
+            ORDER BY (end_time_ns - start_time_ns) DESC, span_id ASC
+            LIMIT ?2
+            "#,
+            threshold
+        ))?;
+        let spans = stmt.query_map(&values[..], Self::row_to_span)?;
+
+        let mut results = Vec::new();
+        for span in spans {
+            let span = span?;
+            let dom_sheets = self.load_dom_sheets_for_correlation(span.correlation_id.clone())?;
+            results.push((span, dom_sheets));
+        }
+        Ok(results)
+    }
+
+    /// Spans whose numeric HTTP status lies in `min..=max` (e.g. `500, 599`
+    /// for 5xx), ordered by start time.
+    pub fn find_spans_by_http_status(
+        &self,
+        min: i64,
+        max: i64,
+    ) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              span_name, span_kind, status_code, service_name,
+              http_method, http_route, correlation_id,
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
+            FROM spans
+            WHERE http_status_code BETWEEN ?1 AND ?2
+            ORDER BY start_time_ns ASC, span_id ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![min, max], Self::row_to_span)?;
+        let mut spans = Vec::new();
+        for span in rows {
+            spans.push(span?);
+        }
+        Ok(spans)
+    }
+
+    /// Spans whose extracted `rpc.method` equals `method`, in start order.
+    pub fn find_spans_by_rpc_method(
+        &self,
+        method: &str,
+    ) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        self.find_spans_by_column("rpc_method", method)
+    }
+
+    /// Spans published to or consumed from `destination`, in start order.
+    pub fn find_spans_by_messaging_destination(
+        &self,
+        destination: &str,
+    ) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        self.find_spans_by_column("messaging_destination", destination)
+    }
+
+    /// Up to `limit` spans whose string attribute `key` equals `value`
+    /// exactly (e.g. `db.system` = `postgresql`), in start order. Keys listed
+    /// in `indexed_attributes` are matched through their expression index;
+    /// any other key binds its JSON path as a parameter and scans.
+    pub fn spans_with_attribute(
+        &self,
+        key: &str,
+        value: &str,
+        limit: i64,
+    ) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        let json_path = attribute_json_path(key)?;
+        // SQLite only uses an expression index when the query repeats the
+        // indexed expression verbatim, so indexed keys are inlined.
+        let indexed = self.indexed_attributes.iter().any(|k| k == key);
+        let extract = if indexed {
+            attribute_extract_sql(key)?
+        } else {
+            "json_extract(attributes, ?3)".to_string()
+        };
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              span_name, span_kind, status_code, service_name,
+              http_method, http_route, correlation_id,
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
+            FROM spans
+            WHERE {} = ?1
+            ORDER BY start_time_ns ASC, span_id ASC
+            LIMIT ?2
+            "#,
+            extract
+        ))?;
+        let rows = if indexed {
+            stmt.query_map(params![value, limit], Self::row_to_span)?
+        } else {
+            stmt.query_map(params![value, limit, json_path], Self::row_to_span)?
+        };
+        let mut spans = Vec::new();
+        for span in rows {
+            spans.push(span?);
+        }
+        Ok(spans)
+    }
+
+    /// `column` must be a fixed, indexed spans column name, never user input.
+    fn find_spans_by_column(
+        &self,
+        column: &str,
+        value: &str,
+    ) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              span_name, span_kind, status_code, service_name,
+              http_method, http_route, correlation_id,
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
+            FROM spans
+            WHERE {} = ?1
+            ORDER BY start_time_ns ASC, span_id ASC
+            "#,
+            column
+        ))?;
+        let rows = stmt.query_map(params![value], Self::row_to_span)?;
+        let mut spans = Vec::new();
+        for span in rows {
+            spans.push(span?);
+        }
+        Ok(spans)
+    }
+
+    /// The chain of spans that determines the duration of `trace_id`: from
+    /// the root, repeatedly step into the child whose subtree finishes last,
+    /// so among parallel siblings the one on the longest path wins (ties go
+    /// to the earlier-starting span). With several roots, e.g. when a parent
+    /// was never exported, the walk starts from the root whose subtree ends
+    /// latest. Empty for an unknown trace.
+    pub fn critical_path(&self, trace_id: &str) -> Result<Vec<SpanRecord>, JavaspectreError> {
+        let spans = self.find_spans_by_column("trace_id", trace_id)?;
+        let index: HashMap<&str, usize> = spans
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (s.span_id.as_str(), i))
+            .collect();
+
+        // Spans arrive in start order, so each child list is too.
+        let mut roots = Vec::new();
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); spans.len()];
+        for (i, span) in spans.iter().enumerate() {
+            match span.parent_span_id.as_deref().and_then(|p| index.get(p)) {
+                Some(&parent) if parent != i => children[parent].push(i),
+                _ => roots.push(i),
+            }
+        }
+
+        // Parents precede children in `order`, so a reverse sweep sees every
+        // child's subtree end before its parent's. Spans caught in a parent
+        // cycle are unreachable from any root and never visited.
+        let mut order = roots.clone();
+        let mut next = 0;
+        while next < order.len() {
+            order.extend_from_slice(&children[order[next]]);
+            next += 1;
+        }
+        let mut subtree_end: Vec<i64> = spans.iter().map(|s| s.end_time_ns).collect();
+        for &i in order.iter().rev() {
+            for &c in &children[i] {
+                subtree_end[i] = subtree_end[i].max(subtree_end[c]);
+            }
+        }
+
+        // `max_by_key` keeps the last maximum; iterate in reverse so the
+        // earliest-starting candidate wins ties.
+        let latest = |candidates: &[usize]| {
+            candidates
+                .iter()
+                .rev()
+                .copied()
+                .max_by_key(|&i| subtree_end[i])
+        };
+        let mut path = Vec::new();
+        let mut current = latest(&roots);
+        while let Some(i) = current {
+            path.push(i);
+            current = latest(&children[i]);
+        }
+
+        let mut spans: Vec<Option<SpanRecord>> = spans.into_iter().map(Some).collect();
+        Ok(path.into_iter().filter_map(|i| spans[i].take()).collect())
+    }
+
+    /// Span names whose earliest span starts at or after `since_ns`, i.e.
+    /// operations never seen before the cutoff, with their first-seen start
+    /// time. Ordered by first appearance, then name. A cutoff earlier than
+    /// all stored spans makes every name novel.
+    pub fn novel_span_names(&self, since_ns: i64) -> Result<Vec<(String, i64)>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT span_name, MIN(start_time_ns) AS first_seen_ns
+            FROM spans
+            GROUP BY span_name
+            HAVING MIN(start_time_ns) >= ?1
+            ORDER BY first_seen_ns ASC, span_name ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![since_ns], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        let mut names = Vec::new();
+        for name in rows {
+            names.push(name?);
+        }
+        Ok(names)
+    }
+
     fn row_to_span(row: &Row<'_>) -> Result<SpanRecord, rusqlite::Error> {
         Ok(SpanRecord {
             span_id: row.get(0)?,
@@ -511,27 +1276,66 @@ impl JavaspectreStore {
             http_method: row.get(9)?,
             http_route: row.get(10)?,
             correlation_id: row.get(11)?,
-            attributes: serde_json::from_str::<Value>(&row.get::<_, String>(12)?)?,
-            resource: serde_json::from_str::<Value>(&row.get::<_, String>(13)?)?,
-            raw_span: serde_json::from_str::<Value>(&row.get::<_, String>(14)?)?,
+            attributes: json_column(row, 12)?,
+            resource: json_column(row, 13)?,
+            raw_span: json_column(row, 14)?,
+            http_status_code: row.get(15)?,
+            rpc_method: row.get(16)?,
+            messaging_destination: row.get(17)?,
         })
     }
 
-    fn row_to_dom_sheet(row: &Row<'_>) -> Result<DomSheetRecord, rusqlite::Error> {
+    fn row_to_dom_sheet(&self, row: &Row<'_>) -> Result<DomSheetRecord, rusqlite::Error> {
+        let sheet_id: String = row.get(0)?;
+        let dom_tree_text: String = row.get(5)?;
+        let dom_tree = self
+            .parse_dom_tree(&sheet_id, &dom_tree_text)
+            .map_err(|e| {
+                rusqlite::Error::FromSqlConversionFailure(
+                    5,
+                    rusqlite::types::Type::Text,
+                    Box::new(e),
+                )
+            })?;
         Ok(DomSheetRecord {
-            sheet_id: row.get(0)?,
+            sheet_id,
             snapshot_id: row.get(1)?,
             trace_id: row.get(2)?,
             correlation_id: row.get(3)?,
             dom_stability_score: row.get(4)?,
-            dom_tree: serde_json::from_str::<Value>(&row.get::<_, String>(5)?)?,
-            noise_stats: match row.get::<_, Option<String>>(6)? {
-                Some(s) => Some(serde_json::from_str::<Value>(&s)?),
-                None => None,
-            },
+            dom_tree,
+            noise_stats: opt_json_column(row, 6)?,
         })
     }
 
+    /// Parse a stored `dom_tree`, serving repeat reads of the same sheet from
+    /// the LRU when `dom_tree_cache_size` is set.
+    fn parse_dom_tree(&self, sheet_id: &str, text: &str) -> Result<Value, serde_json::Error> {
+        if let Some(cache) = &self.dom_trees {
+            if let Some(tree) = cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .get(sheet_id)
+            {
+                return Ok(tree.clone());
+            }
+        }
+        self.dom_tree_parses.fetch_add(1, Ordering::Relaxed);
+        let tree: Value = serde_json::from_str(text)?;
+        if let Some(cache) = &self.dom_trees {
+            cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .put(sheet_id.to_string(), tree.clone());
+        }
+        Ok(tree)
+    }
+
+    /// How many `dom_tree` texts this store (and its clones) has parsed.
+    pub fn dom_tree_parse_count(&self) -> usize {
+        self.dom_tree_parses.load(Ordering::Relaxed)
+    }
+
     fn load_dom_sheets_for_correlation(
         &self,
         correlation_id: Option<String>,
@@ -547,10 +1351,10 @@ impl JavaspectreStore {
               dom_stability_score, dom_tree, noise_stats
             FROM dom_sheets
             WHERE correlation_id = ?1
-            ORDER BY dom_stability_score DESC
+            ORDER BY dom_stability_score DESC, sheet_id ASC
             "#,
         )?;
-        let iter = stmt.query_map(params![cid], |row| Self::row_to_dom_sheet(row))?;
+        let iter = stmt.query_map(params![cid], |row| self.row_to_dom_sheet(row))?;
         let mut out = Vec::new();
         for item in iter {
             out.push(item?);
@@ -561,53 +1365,206 @@ impl JavaspectreStore {
     /// Compute a simple DOM stability score and persist back into dom_sheets.
     /// This is a placeholder scoring engine that can be replaced by Cybercore-Brain logic.
     pub fn recompute_dom_stability_scores(&self) -> Result<(), JavaspectreError> {
-        let conn = &*self.conn;
+        self.recompute_dom_stability_scores_cancellable(|| true).map(|_| ())
+    }
 
-        let mut stmt = conn.prepare(
-            r#"
-            SELECT sheet_id, dom_tree
-            FROM dom_sheets
-            "#,
-        )?;
+    /// `recompute_dom_stability_scores` in `maintenance_chunk_size` chunks of
+    /// sheets (by `sheet_id`), one transaction each. `should_continue` is
+    /// asked between chunks; when it returns false the committed chunks are
+    /// kept and `Cancelled` reports how many sheets were scored. Returns the
+    /// number of sheets scored.
+    pub fn recompute_dom_stability_scores_cancellable(
+        &self,
+        should_continue: impl FnMut() -> bool,
+    ) -> Result<usize, JavaspectreError> {
+        self.recompute_dom_stability_scores_chunked(self.maintenance_chunk_size, should_continue)
+    }
 
-        let mut to_update: Vec<(String, f64)> = Vec::new();
-        let mut rows = stmt.query(NO_PARAMS)?;
-        while let Some(row) = rows.next()? {
-            let sheet_id: String = row.get(0)?;
-            let dom_tree_str: String = row.get(1)?;
-            let dom_tree: Value = serde_json::from_str(&dom_tree_str)?;
-            let score = Self::compute_dom_stability(&dom_tree);
-            to_update.push((sheet_id, score));
+    /// `recompute_dom_stability_scores_cancellable` with an explicit
+    /// `chunk_size` in place of the configured `maintenance_chunk_size`:
+    /// larger chunks mean fewer transactions, smaller ones bound memory and
+    /// the work a crash can lose. `chunk_size` must be at least 1.
+    pub fn recompute_dom_stability_scores_chunked(
+        &self,
+        chunk_size: usize,
+        mut should_continue: impl FnMut() -> bool,
+    ) -> Result<usize, JavaspectreError> {
+        if chunk_size == 0 {
+            return Err(JavaspectreError::Config(
+                "chunk_size must be at least 1".into(),
+            ));
         }
+        let conn = &*self.conn;
+        let mut after = String::new();
+        let mut completed = 0;
+        loop {
+            let mut to_update: Vec<(String, f64)> = Vec::new();
+            {
+                let mut stmt = conn.prepare(
+                    r#"
+                    SELECT sheet_id, dom_tree
+                    FROM dom_sheets
+                    WHERE sheet_id > ?1
+                    ORDER BY sheet_id ASC
+                    LIMIT ?2
+                    "#,
+                )?;
+                let mut rows = stmt.query(params![after, chunk_size as i64])?;
+                while let Some(row) = rows.next()? {
+                    let sheet_id: String = row.get(0)?;
+                    let dom_tree_str: String = row.get(1)?;
+                    let dom_tree = self.parse_dom_tree(&sheet_id, &dom_tree_str)?;
+                    let score = self.compute_dom_stability(&dom_tree);
+                    to_update.push((sheet_id, score));
+                }
+            }
+            let Some((last, _)) = to_update.last() else {
+                return Ok(completed);
+            };
+            after = last.clone();
+            let chunk_len = to_update.len();
 
-        let tx = conn.unchecked_transaction()?;
-        {
-            let mut upd = tx.prepare(
-                r#"
-                UPDATE dom_sheets
-                SET dom_stability_score = ?2
-                WHERE sheet_id = ?1
-                "#,
-            )?;
-            for (sheet_id, score) in to_update {
-                upd.execute(params![sheet_id, score])?;
+            self.retry_busy(|| {
+                let tx = conn.unchecked_transaction()?;
+                {
+                    let mut upd = tx.prepare(
+                        r#"
+                        UPDATE dom_sheets
+                        SET dom_stability_score = ?2
+                        WHERE sheet_id = ?1
+                        "#,
+                    )?;
+                    for (sheet_id, score) in &to_update {
+                        upd.execute(params![sheet_id, score])?;
+                    }
+                }
+                tx.commit()?;
+                Ok(())
+            })?;
+            completed += chunk_len;
+
+            if chunk_len < chunk_size {
+                return Ok(completed);
+            }
+            if !should_continue() {
+                return Err(JavaspectreError::Cancelled { completed });
             }
         }
-        tx.commit()?;
-        Ok(())
+    }
+
+    /// Sheet ids whose stability score is at or below the `percentile`-th
+    /// (0-100, nearest-rank) score, least stable first. Every sheet tied with
+    /// the boundary score is included; unscored sheets are ignored.
+    pub fn flag_unstable_sheets(&self, percentile: f64) -> Result<Vec<String>, JavaspectreError> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return Err(JavaspectreError::Schema(format!(
+                "percentile must be within 0..=100, got {}",
+                percentile
+            )));
+        }
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT sheet_id, dom_stability_score
+            FROM dom_sheets
+            WHERE dom_stability_score IS NOT NULL
+            ORDER BY dom_stability_score ASC, sheet_id ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(NO_PARAMS, |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?))
+        })?;
+        let scored = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let rank = (percentile / 100.0 * scored.len() as f64).ceil() as usize;
+        let Some(&(_, threshold)) = rank.checked_sub(1).and_then(|i| scored.get(i)) else {
+            return Ok(Vec::new());
+        };
+        Ok(scored
+            .into_iter()
+            .take_while(|(_, score)| *score <= threshold)
+            .map(|(sheet_id, _)| sheet_id)
+            .collect())
+    }
+
+    /// Sheet ids, sorted, whose `dom_tree.grid` assigns `role` to the cell at
+    /// `row`/`col`. Sheets derived without geometry never match.
+    pub fn sheets_with_element_at(
+        &self,
+        row: i64,
+        col: i64,
+        role: &str,
+    ) -> Result<Vec<String>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT DISTINCT s.sheet_id
+            FROM dom_sheets s, json_each(s.dom_tree, '$.grid.cells') cell
+            WHERE json_extract(cell.value, '$.row') = ?1
+              AND json_extract(cell.value, '$.col') = ?2
+              AND json_extract(cell.value, '$.role') = ?3
+            ORDER BY s.sheet_id ASC
+            "#,
+        )?;
+        let rows = stmt.query_map(params![row, col, role], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+
+    /// Interactive-element selectors present in every sheet of
+    /// `correlation_id`, sorted; good anchors for UI automation. Each sheet's
+    /// selectors come from its `paths` feature, or from its source snapshot's
+    /// DOM for sheets derived before that feature existed.
+    pub fn stable_selectors(&self, correlation_id: &str) -> Result<Vec<String>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT s.sheet_id, s.dom_tree, snap.raw_dom
+            FROM dom_sheets s
+            LEFT JOIN dom_snapshots snap ON snap.snapshot_id = s.snapshot_id
+            WHERE s.correlation_id = ?1
+            ORDER BY s.sheet_id ASC
+            "#,
+        )?;
+        let mut rows = stmt.query(params![correlation_id])?;
+        let mut stable: Option<BTreeSet<String>> = None;
+        while let Some(row) = rows.next()? {
+            let sheet_id: String = row.get(0)?;
+            let dom_tree = self.parse_dom_tree(&sheet_id, &row.get::<_, String>(1)?)?;
+            let selectors: BTreeSet<String> = match dom_tree.get("paths").and_then(Value::as_array)
+            {
+                Some(paths) => paths
+                    .iter()
+                    .filter_map(|p| p.as_str().map(str::to_string))
+                    .collect(),
+                None => match row.get::<_, Option<String>>(2)? {
+                    Some(raw_dom) => {
+                        interactive_selectors(&serde_json::from_str(&raw_dom)?, &self.dynamic_ids)
+                            .into_iter()
+                            .collect()
+                    }
+                    None => BTreeSet::new(),
+                },
+            };
+            stable = Some(match stable {
+                Some(acc) => acc.intersection(&selectors).cloned().collect(),
+                None => selectors,
+            });
+        }
+        Ok(stable.unwrap_or_default().into_iter().collect())
     }
 
     /// Simple stability heuristic: fewer dynamic classes/ids => higher score.
-    fn compute_dom_stability(dom_tree: &Value) -> f64 {
-        fn count_dynamic(v: &Value, dynamic_ids: &mut i64, total_nodes: &mut i64) {
+    fn compute_dom_stability(&self, dom_tree: &Value) -> f64 {
+        fn count_dynamic(
+            v: &Value,
+            patterns: &RegexSet,
+            dynamic_ids: &mut i64,
+            total_nodes: &mut i64,
+        ) {
             match v {
                 Value::Object(map) => {
                     if let Some(Value::String(id)) = map.get("id") {
-                        if id.contains("uuid")
-                            || id.contains("session")
-                            || id.contains("abtest")
-                            || id.chars().any(|c| c.is_ascii_digit())
-                        {
+                        if patterns.is_match(id) {
                             *dynamic_ids += 1;
                         }
                     }
@@ -621,12 +1578,12 @@ impl JavaspectreStore {
                     }
                     *total_nodes += 1;
                     for (_, child) in map {
-                        count_dynamic(child, dynamic_ids, total_nodes);
+                        count_dynamic(child, patterns, dynamic_ids, total_nodes);
                     }
                 }
                 Value::Array(arr) => {
                     for child in arr {
-                        count_dynamic(child, dynamic_ids, total_nodes);
+                        count_dynamic(child, patterns, dynamic_ids, total_nodes);
                     }
                 }
                 _ => {}
@@ -635,7 +1592,12 @@ impl JavaspectreStore {
 
         let mut dynamic_ids = 0;
         let mut total_nodes = 0;
-        count_dynamic(dom_tree, &mut dynamic_ids, &mut total_nodes);
+        count_dynamic(
+            dom_tree,
+            &self.dynamic_ids,
+            &mut dynamic_ids,
+            &mut total_nodes,
+        );
 
         if total_nodes == 0 {
             return 0.0;
@@ -659,7 +1621,8 @@ impl JavaspectreStore {
               span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
               span_name, span_kind, status_code, service_name,
               http_method, http_route, correlation_id,
-              attributes, resource, raw_span
+              attributes, resource, raw_span, http_status_code,
+              rpc_method, messaging_destination
             FROM spans
             WHERE correlation_id = ?1
             ORDER BY start_time_ns ASC
@@ -681,12 +1644,11 @@ impl JavaspectreStore {
               dom_stability_score, dom_tree, noise_stats
             FROM dom_sheets
             WHERE correlation_id = ?1
-            ORDER BY dom_stability_score DESC
+            ORDER BY dom_stability_score DESC, sheet_id ASC
             "#,
         )?;
-        let dom_iter = dom_stmt.query_map(params![correlation_id], |row| {
-            Self::row_to_dom_sheet(row)
-        })?;
+        let dom_iter =
+            dom_stmt.query_map(params![correlation_id], |row| self.row_to_dom_sheet(row))?;
         let mut dom_sheets = Vec::new();
         for d in dom_iter {
             dom_sheets.push(d?);
@@ -711,15 +1673,9 @@ impl JavaspectreStore {
                 method: row.get(3)?,
                 url: row.get(4)?,
                 status: row.get(5)?,
-                request_json: match row.get::<_, Option<String>>(6)? {
-                    Some(s) => Some(serde_json::from_str::<Value>(&s)?),
-                    None => None,
-                },
-                response_json: match row.get::<_, Option<String>>(7)? {
-                    Some(s) => Some(serde_json::from_str::<Value>(&s)?),
-                    None => None,
-                },
-                raw_entry: serde_json::from_str::<Value>(&row.get::<_, String>(8)?)?,
+                request_json: opt_json_column(row, 6)?,
+                response_json: opt_json_column(row, 7)?,
+                raw_entry: json_column(row, 8)?,
             })
         })?;
         let mut har_entries = Vec::new();
@@ -734,92 +1690,164 @@ impl JavaspectreStore {
             har_entries,
         })
     }
-}
-
-/// Represents a Javaspectre "virtual object" cluster across traces, DOM, and HAR.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct VirtualObjectCluster {
-    pub correlation_id: String,
-    pub spans: Vec<SpanRecord>,
-    pub dom_sheets: Vec<DomSheetRecord>,
-    pub har_entries: Vec<HarEntryRecord>,
-}
-
-/// Example integration point with a higher-level ScoreEngine.
-/// Scores can be computed by Cybercore-Brain and persisted into auxiliary tables.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ClusterScore {
-    pub correlation_id: String,
-    pub stability_score: f64,
-    pub novelty_score: f64,
-    pub drift_score: f64,
-}
 
-impl JavaspectreStore {
-    pub fn init_score_table(&self) -> Result<(), JavaspectreError> {
+    /// List correlation ids with per-table counts, most recently active first.
+    ///
+    /// Rows whose `correlation_id` is NULL are excluded: they cannot be joined
+    /// into a virtual-object cluster, so they have no meaningful summary.
+    /// `first_ns`/`last_ns` span span start/end times and HAR start times, and are
+    /// `None` for correlations that only have DOM sheets.
+    pub fn list_correlations(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<CorrelationSummary>, JavaspectreError> {
         let conn = &*self.conn;
-        conn.execute_batch(
+        let mut stmt = conn.prepare(
             r#"
-            CREATE TABLE IF NOT EXISTS cluster_scores (
-              correlation_id TEXT PRIMARY KEY,
-              stability_score REAL NOT NULL,
-              novelty_score REAL NOT NULL,
-              drift_score REAL NOT NULL,
-              updated_at_ns INTEGER NOT NULL
-            );
+            SELECT
+              correlation_id,
+              SUM(span_count), SUM(dom_sheet_count), SUM(har_count),
+              MIN(first_ns), MAX(last_ns)
+            FROM (
+              SELECT correlation_id, COUNT(*) AS span_count, 0 AS dom_sheet_count,
+                     0 AS har_count, MIN(start_time_ns) AS first_ns,
+                     MAX(end_time_ns) AS last_ns
+              FROM spans
+              WHERE correlation_id IS NOT NULL
+              GROUP BY correlation_id
+              UNION ALL
+              SELECT correlation_id, 0, COUNT(*), 0, NULL, NULL
+              FROM dom_sheets
+              WHERE correlation_id IS NOT NULL
+              GROUP BY correlation_id
+              UNION ALL
+              SELECT correlation_id, 0, 0, COUNT(*),
+                     MIN(started_at_ns), MAX(started_at_ns)
+              FROM har_entries
+              WHERE correlation_id IS NOT NULL
+              GROUP BY correlation_id
+            )
+            GROUP BY correlation_id
+            ORDER BY MAX(last_ns) IS NULL, MAX(last_ns) DESC, correlation_id ASC
+            LIMIT ?1 OFFSET ?2
             "#,
         )?;
-        Ok(())
+        let iter = stmt.query_map(params![limit, offset], |row| {
+            Ok(CorrelationSummary {
+                correlation_id: row.get(0)?,
+                span_count: row.get(1)?,
+                dom_sheet_count: row.get(2)?,
+                har_count: row.get(3)?,
+                first_ns: row.get(4)?,
+                last_ns: row.get(5)?,
+            })
+        })?;
+        let mut out = Vec::new();
+        for item in iter {
+            out.push(item?);
+        }
+        Ok(out)
     }
 
-    pub fn upsert_cluster_score(
+    /// Compute a correlation's `ClusterSummary` from the live `spans` and
+    /// `dom_sheets` rows.
+    pub fn compute_cluster_summary(
         &self,
-        score: &ClusterScore,
-        updated_at_ns: i64,
-    ) -> Result<(), JavaspectreError> {
+        correlation_id: &str,
+    ) -> Result<ClusterSummary, JavaspectreError> {
+        let conn = &*self.conn;
+        let (span_count, error_count, total_duration_ns, earliest_ns, latest_ns) = conn
+            .query_row(
+                &format!(
+                    r#"
+                    SELECT
+                      COUNT(*),
+                      COALESCE(SUM({}), 0),
+                      COALESCE(SUM(end_time_ns - start_time_ns), 0),
+                      MIN(start_time_ns),
+                      MAX(end_time_ns)
+                    FROM spans
+                    WHERE correlation_id = ?1
+                    "#,
+                    SPAN_IS_ERROR_SQL
+                ),
+                params![correlation_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?)),
+            )?;
+        let dom_sheet_count = conn.query_row(
+            "SELECT COUNT(*) FROM dom_sheets WHERE correlation_id = ?1",
+            params![correlation_id],
+            |row| row.get(0),
+        )?;
+        Ok(ClusterSummary {
+            correlation_id: correlation_id.to_string(),
+            span_count,
+            error_count,
+            total_duration_ns,
+            earliest_ns,
+            latest_ns,
+            dom_sheet_count,
+        })
+    }
+
+    /// Recompute a correlation's summary and store it in `cluster_summaries`,
+    /// replacing any earlier row. Summaries are only refreshed when this is
+    /// called; ingest does not keep them current.
+    pub fn materialize_cluster_summary(&self, correlation_id: &str) -> Result<(), JavaspectreError> {
+        let summary = self.compute_cluster_summary(correlation_id)?;
         let conn = &*self.conn;
         conn.execute(
             r#"
-            INSERT INTO cluster_scores (
-              correlation_id, stability_score, novelty_score,
-              drift_score, updated_at_ns
-            ) VALUES (?1, ?2, ?3, ?4, ?5)
+            INSERT INTO cluster_summaries (
+              correlation_id, span_count, error_count, total_duration_ns,
+              earliest_ns, latest_ns, dom_sheet_count
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
             ON CONFLICT(correlation_id) DO UPDATE SET
-              stability_score = excluded.stability_score,
-              novelty_score = excluded.novelty_score,
-              drift_score = excluded.drift_score,
-              updated_at_ns = excluded.updated_at_ns
+              span_count = excluded.span_count,
+              error_count = excluded.error_count,
+              total_duration_ns = excluded.total_duration_ns,
+              earliest_ns = excluded.earliest_ns,
+              latest_ns = excluded.latest_ns,
+              dom_sheet_count = excluded.dom_sheet_count
             "#,
             params![
-                score.correlation_id,
-                score.stability_score,
-                score.novelty_score,
-                score.drift_score,
-                updated_at_ns
+                summary.correlation_id,
+                summary.span_count,
+                summary.error_count,
+                summary.total_duration_ns,
+                summary.earliest_ns,
+                summary.latest_ns,
+                summary.dom_sheet_count
             ],
         )?;
         Ok(())
     }
 
-    pub fn load_cluster_score(
+    /// The stored summary for `correlation_id`, if one was materialized.
+    pub fn load_cluster_summary(
         &self,
         correlation_id: &str,
-    ) -> Result<Option<ClusterScore>, JavaspectreError> {
+    ) -> Result<Option<ClusterSummary>, JavaspectreError> {
         let conn = &*self.conn;
         let mut stmt = conn.prepare(
             r#"
-            SELECT correlation_id, stability_score, novelty_score, drift_score
-            FROM cluster_scores
+            SELECT correlation_id, span_count, error_count, total_duration_ns,
+                   earliest_ns, latest_ns, dom_sheet_count
+            FROM cluster_summaries
             WHERE correlation_id = ?1
             "#,
         )?;
         let mut rows = stmt.query(params![correlation_id])?;
         if let Some(row) = rows.next()? {
-            Ok(Some(ClusterScore {
+            Ok(Some(ClusterSummary {
                 correlation_id: row.get(0)?,
-                stability_score: row.get(1)?,
-                novelty_score: row.get(2)?,
-                drift_score: row.get(3)?,
+                span_count: row.get(1)?,
+                error_count: row.get(2)?,
+                total_duration_ns: row.get(3)?,
+                earliest_ns: row.get(4)?,
+                latest_ns: row.get(5)?,
+                dom_sheet_count: row.get(6)?,
             }))
         } else {
             Ok(None)
@@ -827,280 +1855,3364 @@ impl JavaspectreStore {
     }
 }
 
-/// Optional helper to build endpoint_key from method and normalized route.
-pub fn build_endpoint_key(method: &str, route: &str) -> String {
-    format!("{} {}", method.to_uppercase(), route)
+/// Default span-duration histogram bucket upper bounds, in seconds.
+pub const DEFAULT_DURATION_BUCKETS_SECS: &[f64] =
+    &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// SQL predicate over a `spans` row: a 5xx HTTP status or an OTel `ERROR`
+/// status. 4xx responses are client errors and do not count.
+const SPAN_IS_ERROR_SQL: &str = "COALESCE(http_status_code >= 500, 0) \
+     OR UPPER(COALESCE(status_code, '')) IN ('ERROR', 'STATUS_CODE_ERROR')";
+
+/// Rate, errors and duration for one `http_route` over a time window.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedMetric {
+    pub http_route: String,
+    pub request_count: i64,
+    pub error_count: i64,
+    /// Requests per second of window length.
+    pub request_rate: f64,
+    /// `error_count / request_count`.
+    pub error_rate: f64,
+    /// Nearest-rank duration percentiles, in nanoseconds.
+    pub p50_ns: i64,
+    pub p90_ns: i64,
+    pub p99_ns: i64,
 }
 
-/// A minimal hash helper for content-addressed snapshots.
-/// In a production system, this should use a proven SHA-256 implementation.
-pub fn stable_snapshot_hash(payload: &Value) -> Result<String, JavaspectreError> {
-    use sha2::{Digest, Sha256};
+/// Error-budget consumption for one route, for SLO burn-rate alerts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BurnRate {
+    pub http_route: String,
+    /// Start time of the route's newest span, which closes the window;
+    /// `None` when the route has no spans.
+    pub window_end_ns: Option<i64>,
+    pub total: i64,
+    pub errors: i64,
+    /// `errors / total`, or 0 with no spans.
+    pub error_fraction: f64,
+    /// `1 - slo`: the error fraction the SLO tolerates.
+    pub budget_fraction: f64,
+    /// `error_fraction / budget_fraction`; 1.0 spends the budget exactly
+    /// over the SLO period, 14.4 exhausts a 30-day budget in two days.
+    pub burn_multiplier: f64,
+}
 
-    let canonical = canonical_json(payload)?;
-    let mut hasher = Sha256::new();
-    hasher.update(canonical.as_bytes());
-    let digest = hasher.finalize();
-    Ok(hex::encode(digest))
+/// Share of a route's spans carrying one attribute key, in each window.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KeyFrequency {
+    pub key: String,
+    pub frequency_a: f64,
+    pub frequency_b: f64,
 }
 
-/// Canonical JSON serialization to provide deterministic hashes.
-fn canonical_json(value: &Value) -> Result<String, JavaspectreError> {
-    fn sort_value(v: &Value) -> Value {
-        match v {
-            Value::Object(map) => {
-                let mut entries: Vec<_> = map.iter().collect();
-                entries.sort_by(|a, b| a.0.cmp(b.0));
-                let mut ordered = serde_json::map::Map::new();
-                for (k, v) in entries {
-                    ordered.insert(k.clone(), sort_value(v));
+/// Attribute keys a route's spans gained or lost between two windows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttrDrift {
+    pub http_route: String,
+    pub spans_a: i64,
+    pub spans_b: i64,
+    /// Keys seen in window b but on no span in window a, sorted.
+    pub added: Vec<String>,
+    /// Keys seen in window a but on no span in window b, sorted.
+    pub removed: Vec<String>,
+    /// Keys present in both windows whose share of spans changed, by key.
+    pub changed: Vec<KeyFrequency>,
+}
+
+impl JavaspectreStore {
+    /// Compare the attribute keys on `route`'s spans starting in `window_a`
+    /// against those in `window_b` (each `start..end`). Keys are the dotted
+    /// leaf paths `flatten_span_attributes` uses, read from the spans
+    /// directly so no flattening pass is needed.
+    pub fn attribute_drift(
+        &self,
+        route: &str,
+        window_a: (i64, i64),
+        window_b: (i64, i64),
+    ) -> Result<AttrDrift, JavaspectreError> {
+        let (spans_a, keys_a) = self.attribute_key_counts(route, window_a)?;
+        let (spans_b, keys_b) = self.attribute_key_counts(route, window_b)?;
+        let share = |count: i64, spans: i64| count as f64 / spans as f64;
+
+        let added = keys_b
+            .keys()
+            .filter(|k| !keys_a.contains_key(*k))
+            .cloned()
+            .collect();
+        let mut removed = Vec::new();
+        let mut changed = Vec::new();
+        for (key, &count_a) in &keys_a {
+            match keys_b.get(key) {
+                None => removed.push(key.clone()),
+                Some(&count_b) => {
+                    let frequency_a = share(count_a, spans_a);
+                    let frequency_b = share(count_b, spans_b);
+                    if frequency_a != frequency_b {
+                        changed.push(KeyFrequency {
+                            key: key.clone(),
+                            frequency_a,
+                            frequency_b,
+                        });
+                    }
                 }
-                Value::Object(ordered)
             }
-            Value::Array(arr) => {
-                Value::Array(arr.iter().map(sort_value).collect())
+        }
+        Ok(AttrDrift {
+            http_route: route.to_string(),
+            spans_a,
+            spans_b,
+            added,
+            removed,
+            changed,
+        })
+    }
+
+    /// Number of `route` spans starting in `start..end`, and how many of them
+    /// carry each attribute key.
+    fn attribute_key_counts(
+        &self,
+        route: &str,
+        (start, end): (i64, i64),
+    ) -> Result<(i64, BTreeMap<String, i64>), JavaspectreError> {
+        if end <= start {
+            return Err(JavaspectreError::Schema(format!(
+                "window end {} must be after start {}",
+                end, start
+            )));
+        }
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT attributes FROM spans
+            WHERE http_route = ?1 AND start_time_ns >= ?2 AND start_time_ns < ?3
+            "#,
+        )?;
+        let mut rows = stmt.query(params![route, start, end])?;
+        let mut spans = 0;
+        let mut counts = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let attributes: Value = serde_json::from_str(&row.get::<_, String>(0)?)?;
+            let mut leaves = Vec::new();
+            flatten_json("", &attributes, &mut leaves);
+            let keys: BTreeSet<String> = leaves.into_iter().map(|(key, _, _)| key).collect();
+            for key in keys {
+                *counts.entry(key).or_insert(0) += 1;
             }
-            _ => v.clone(),
+            spans += 1;
         }
+        Ok((spans, counts))
     }
 
-    let sorted = sort_value(value);
-    Ok(serde_json::to_string(&sorted)?)
-}
+    /// Burn rate of `http_route`'s error budget against `slo` (e.g. 0.999)
+    /// over the `window_ns` ending at the route's newest span start. Errors
+    /// are classified as in `red_metrics`.
+    pub fn error_budget_burn(
+        &self,
+        route: &str,
+        window_ns: i64,
+        slo: f64,
+    ) -> Result<BurnRate, JavaspectreError> {
+        if window_ns <= 0 {
+            return Err(JavaspectreError::Schema(format!(
+                "burn window {} must be positive",
+                window_ns
+            )));
+        }
+        if !(slo > 0.0 && slo < 1.0) {
+            return Err(JavaspectreError::Schema(format!(
+                "slo {} must be between 0 and 1 exclusive",
+                slo
+            )));
+        }
+        let conn = &*self.conn;
+        let window_end_ns: Option<i64> = conn.query_row(
+            "SELECT MAX(start_time_ns) FROM spans WHERE http_route = ?1",
+            params![route],
+            |row| row.get(0),
+        )?;
+        let (total, errors) = match window_end_ns {
+            Some(end) => conn.query_row(
+                &format!(
+                    r#"
+                    SELECT COUNT(*), COALESCE(SUM({}), 0)
+                    FROM spans
+                    WHERE http_route = ?1
+                      AND start_time_ns > ?2 AND start_time_ns <= ?3
+                    "#,
+                    SPAN_IS_ERROR_SQL
+                ),
+                params![route, end.saturating_sub(window_ns), end],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?)),
+            )?,
+            None => (0, 0),
+        };
 
-/// Example: ingest a raw OpenTelemetry span JSON blob into the spans table.
-/// This function extracts a few common attributes but keeps the payload semi-structured.
-pub fn ingest_otel_span(
-    store: &JavaspectreStore,
-    raw_span_json: &str,
-) -> Result<(), JavaspectreError> {
-    let raw: Value = serde_json::from_str(raw_span_json)?;
-    let span_id = raw
-        .get("span_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| JavaspectreError::Schema("missing span_id".into()))?
-        .to_string();
-    let trace_id = raw
-        .get("trace_id")
-        .and_then(|v| v.as_str())
-        .ok_or_else(|| JavaspectreError::Schema("missing trace_id".into()))?
-        .to_string();
+        let error_fraction = if total == 0 {
+            0.0
+        } else {
+            errors as f64 / total as f64
+        };
+        let budget_fraction = 1.0 - slo;
+        Ok(BurnRate {
+            http_route: route.to_string(),
+            window_end_ns,
+            total,
+            errors,
+            error_fraction,
+            budget_fraction,
+            burn_multiplier: error_fraction / budget_fraction,
+        })
+    }
 
-    let parent_span_id = raw.get("parent_span_id").and_then(|v| v.as_str()).map(|s| s.to_string());
-    let start_time_ns = raw
-        .get("start_time_unix_nano")
-        .or_else(|| raw.get("start_time_ns"))
-        .and_then(|v| v.as_str().and_then(|s| s.parse::<i64>().ok()).or_else(|| v.as_i64()))
-        .ok_or_else(|| JavaspectreError::Schema("missing start_time_ns".into()))?;
-    let end_time_ns = raw
-        .get("end_time_unix_nano")
-        .or_else(|| raw.get("end_time_ns"))
-        .and_then(|v| v.as_str().and_then(|s| s.parse::<i64>().ok()).or_else(|| v.as_i64()))
-        .ok_or_else(|| JavaspectreError::Schema("missing end_time_ns".into()))?;
+    /// RED metrics per `http_route` for spans starting in
+    /// `window_start_ns..window_end_ns`, ordered by route. A span counts as
+    /// an error when its numeric HTTP status is 5xx or its OTel status is
+    /// `ERROR`; 4xx responses are client errors and are not counted. Spans
+    /// without a route are skipped.
+    pub fn red_metrics(
+        &self,
+        window_start_ns: i64,
+        window_end_ns: i64,
+    ) -> Result<Vec<RedMetric>, JavaspectreError> {
+        if window_end_ns <= window_start_ns {
+            return Err(JavaspectreError::Schema(format!(
+                "window end {} must be after start {}",
+                window_end_ns, window_start_ns
+            )));
+        }
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(&format!(
+            r#"
+            SELECT
+              http_route,
+              end_time_ns - start_time_ns AS duration_ns,
+              {}
+            FROM spans
+            WHERE http_route IS NOT NULL
+              AND start_time_ns >= ?1 AND start_time_ns < ?2
+            ORDER BY http_route ASC, duration_ns ASC
+            "#,
+            SPAN_IS_ERROR_SQL
+        ))?;
+        let rows = stmt.query_map(params![window_start_ns, window_end_ns], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, bool>(2)?,
+            ))
+        })?;
 
-    let span_name = raw
-        .get("name")
-        .or_else(|| raw.get("span_name"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("unknown_span")
-        .to_string();
+        // Rows arrive grouped by route with durations ascending.
+        let mut grouped: Vec<(String, Vec<i64>, i64)> = Vec::new();
+        for row in rows {
+            let (route, duration_ns, is_error) = row?;
+            match grouped.last_mut() {
+                Some((last, durations, errors)) if *last == route => {
+                    durations.push(duration_ns);
+                    *errors += is_error as i64;
+                }
+                _ => grouped.push((route, vec![duration_ns], is_error as i64)),
+            }
+        }
 
-    let span_kind = raw
-        .get("kind")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        let window_secs = (window_end_ns - window_start_ns) as f64 / 1e9;
+        Ok(grouped
+            .into_iter()
+            .map(|(http_route, durations, error_count)| {
+                let percentile = |p: f64| {
+                    let rank = (p / 100.0 * durations.len() as f64).ceil() as usize;
+                    durations[rank.clamp(1, durations.len()) - 1]
+                };
+                let request_count = durations.len() as i64;
+                RedMetric {
+                    request_rate: request_count as f64 / window_secs,
+                    error_rate: error_count as f64 / request_count as f64,
+                    p50_ns: percentile(50.0),
+                    p90_ns: percentile(90.0),
+                    p99_ns: percentile(99.0),
+                    http_route,
+                    request_count,
+                    error_count,
+                }
+            })
+            .collect())
+    }
 
-    // Extract attributes map if present.
-    let attributes = raw
-        .get("attributes")
-        .cloned()
-        .unwrap_or_else(|| Value::Object(serde_json::map::Map::new()));
+    /// Render span counts, span-duration histograms (by `service_name` and
+    /// `http_route`), and per-correlation DOM stability gauges in Prometheus
+    /// text exposition format, using `DEFAULT_DURATION_BUCKETS_SECS`.
+    pub fn prometheus_metrics(&self) -> Result<String, JavaspectreError> {
+        self.prometheus_metrics_with_buckets(DEFAULT_DURATION_BUCKETS_SECS)
+    }
 
-    // Resource can be nested or separate.
-    let resource = raw
-        .get("resource")
-        .cloned()
-        .unwrap_or_else(|| Value::Object(serde_json::map::Map::new()));
+    /// Same as `prometheus_metrics`, with caller-supplied histogram bucket
+    /// upper bounds in seconds. Bounds must be finite and strictly increasing;
+    /// the `+Inf` bucket is always appended.
+    pub fn prometheus_metrics_with_buckets(
+        &self,
+        buckets_secs: &[f64],
+    ) -> Result<String, JavaspectreError> {
+        use std::collections::BTreeMap;
+        use std::fmt::Write;
 
-    let service_name = attributes
-        .get("service.name")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        if buckets_secs.iter().any(|b| !b.is_finite())
+            || buckets_secs.windows(2).any(|w| w[0] >= w[1])
+        {
+            return Err(JavaspectreError::Schema(
+                "histogram buckets must be finite and strictly increasing".into(),
+            ));
+        }
 
-    let http_method = attributes
-        .get("http.method")
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        struct Histogram {
+            buckets: Vec<u64>,
+            sum: f64,
+            count: u64,
+        }
 
-    let http_route = attributes
-        .get("http.route")
-        .or_else(|| attributes.get("http.target"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        let conn = &*self.conn;
+        let mut histograms: BTreeMap<(String, String), Histogram> = BTreeMap::new();
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT service_name, http_route, end_time_ns - start_time_ns
+            FROM spans
+            "#,
+        )?;
+        let mut rows = stmt.query(NO_PARAMS)?;
+        while let Some(row) = rows.next()? {
+            let service: Option<String> = row.get(0)?;
+            let route: Option<String> = row.get(1)?;
+            let duration_secs = row.get::<_, i64>(2)? as f64 / 1e9;
+            let h = histograms
+                .entry((service.unwrap_or_default(), route.unwrap_or_default()))
+                .or_insert_with(|| Histogram {
+                    buckets: vec![0; buckets_secs.len()],
+                    sum: 0.0,
+                    count: 0,
+                });
+            for (slot, bound) in h.buckets.iter_mut().zip(buckets_secs) {
+                if duration_secs <= *bound {
+                    *slot += 1;
+                }
+            }
+            h.sum += duration_secs;
+            h.count += 1;
+        }
 
-    let status_code_attr = attributes
-        .get("http.status_code")
-        .and_then(|v| v.as_i64())
-        .map(|c| c.to_string());
+        let mut out = String::new();
+        out.push_str("# HELP javaspectre_spans_total Stored spans by service and route.\n");
+        out.push_str("# TYPE javaspectre_spans_total counter\n");
+        for ((service, route), h) in &histograms {
+            let _ = writeln!(
+                out,
+                "javaspectre_spans_total{{service_name=\"{}\",http_route=\"{}\"}} {}",
+                prom_escape(service),
+                prom_escape(route),
+                h.count
+            );
+        }
 
-    let status_code = raw
-        .get("status")
-        .and_then(|st| st.get("code"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string())
-        .or(status_code_attr);
+        out.push_str("# HELP javaspectre_span_duration_seconds Span duration by service and route.\n");
+        out.push_str("# TYPE javaspectre_span_duration_seconds histogram\n");
+        for ((service, route), h) in &histograms {
+            let labels = format!(
+                "service_name=\"{}\",http_route=\"{}\"",
+                prom_escape(service),
+                prom_escape(route)
+            );
+            for (bound, cumulative) in buckets_secs.iter().zip(&h.buckets) {
+                let _ = writeln!(
+                    out,
+                    "javaspectre_span_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "javaspectre_span_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, h.count
+            );
+            let _ = writeln!(out, "javaspectre_span_duration_seconds_sum{{{}}} {}", labels, h.sum);
+            let _ = writeln!(out, "javaspectre_span_duration_seconds_count{{{}}} {}", labels, h.count);
+        }
 
-    // Correlation ID can be in baggage, attributes, or custom fields.
-    let correlation_id = attributes
-        .get("correlation_id")
-        .or_else(|| attributes.get("correlation.id"))
-        .or_else(|| attributes.get("session.id"))
-        .and_then(|v| v.as_str())
-        .map(|s| s.to_string());
+        out.push_str(
+            "# HELP javaspectre_dom_stability_score Mean DOM stability score per correlation.\n",
+        );
+        out.push_str("# TYPE javaspectre_dom_stability_score gauge\n");
+        let mut dom_stmt = conn.prepare(
+            r#"
+            SELECT correlation_id, AVG(dom_stability_score)
+            FROM dom_sheets
+            WHERE correlation_id IS NOT NULL AND dom_stability_score IS NOT NULL
+            GROUP BY correlation_id
+            ORDER BY correlation_id ASC
+            "#,
+        )?;
+        let mut dom_rows = dom_stmt.query(NO_PARAMS)?;
+        while let Some(row) = dom_rows.next()? {
+            let correlation_id: String = row.get(0)?;
+            let score: f64 = row.get(1)?;
+            let _ = writeln!(
+                out,
+                "javaspectre_dom_stability_score{{correlation_id=\"{}\"}} {}",
+                prom_escape(&correlation_id),
+                score
+            );
+        }
 
-    let span_record = SpanRecord {
-        span_id,
-        trace_id,
-        parent_span_id,
-        start_time_ns,
-        end_time_ns,
-        span_name,
-        span_kind,
-        status_code,
-        service_name,
-        http_method,
-        http_route,
-        correlation_id,
-        attributes,
-        resource,
-        raw_span: raw,
-    };
+        Ok(out)
+    }
+}
 
-    store.upsert_span(&span_record)?;
-    Ok(())
+/// Optional predicates for span exports; `None` fields don't filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpanFilter {
+    pub trace_id: Option<String>,
+    pub service_name: Option<String>,
+    pub http_route: Option<String>,
+    pub correlation_id: Option<String>,
+    /// Inclusive lower bound on `start_time_ns`.
+    pub start_ns_from: Option<i64>,
+    /// Exclusive upper bound on `start_time_ns`.
+    pub start_ns_to: Option<i64>,
 }
 
-/// Example: ingest a generic DOM snapshot JSON string.
-pub fn ingest_dom_snapshot(
-    store: &JavaspectreStore,
-    snapshot_id: &str,
-    trace_id: Option<&str>,
-    correlation_id: Option<&str>,
-    captured_at_ns: i64,
-    dom_json: &str,
-) -> Result<(), JavaspectreError> {
-    let raw_dom: Value = serde_json::from_str(dom_json)?;
-    let snap = DomSnapshotRecord {
-        snapshot_id: snapshot_id.to_string(),
-        trace_id: trace_id.map(|s| s.to_string()),
-        correlation_id: correlation_id.map(|s| s.to_string()),
-        captured_at_ns,
-        raw_dom,
-    };
-    store.insert_dom_snapshot(&snap)?;
-    Ok(())
+impl SpanFilter {
+    fn where_clause(&self) -> (String, Vec<Box<dyn rusqlite::ToSql>>) {
+        let mut clauses: Vec<&str> = Vec::new();
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        let text_filters = [
+            ("trace_id = ?", &self.trace_id),
+            ("service_name = ?", &self.service_name),
+            ("http_route = ?", &self.http_route),
+            ("correlation_id = ?", &self.correlation_id),
+        ];
+        for (clause, value) in text_filters {
+            if let Some(v) = value {
+                clauses.push(clause);
+                values.push(Box::new(v.clone()));
+            }
+        }
+        if let Some(from) = self.start_ns_from {
+            clauses.push("start_time_ns >= ?");
+            values.push(Box::new(from));
+        }
+        if let Some(to) = self.start_ns_to {
+            clauses.push("start_time_ns < ?");
+            values.push(Box::new(to));
+        }
+        if clauses.is_empty() {
+            (String::new(), values)
+        } else {
+            (format!("WHERE {}", clauses.join(" AND ")), values)
+        }
+    }
 }
 
-/// Example: derive a simple DOM sheet from a snapshot by picking out roles and node grid.
-/// Real systems can plug in a more advanced stabilizer here.
-pub fn derive_dom_sheet_from_snapshot(
-    store: &JavaspectreStore,
-    sheet_id: &str,
-    snapshot_id: &str,
-    dom_snapshot: &DomSnapshotRecord,
-) -> Result<(), JavaspectreError> {
-    // Example spec-aligned structure: root-level object with roles and basic tag summary.
-    let dom_tree = json!({
-        "roles": {
-            "button_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "button"),
-            "link_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "a"),
-            "input_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "input"),
-        },
-        "meta": {
-            "origin_trace_id": dom_snapshot.trace_id,
-            "origin_correlation_id": dom_snapshot.correlation_id,
+/// Column order of `export_spans_csv`.
+pub const SPAN_CSV_COLUMNS: &[&str] = &[
+    "span_id",
+    "trace_id",
+    "parent_span_id",
+    "start_time_ns",
+    "end_time_ns",
+    "duration_ns",
+    "span_name",
+    "span_kind",
+    "status_code",
+    "service_name",
+    "http_method",
+    "http_route",
+    "correlation_id",
+    "attributes",
+];
+
+impl JavaspectreStore {
+    /// Stream spans matching `filter` to `writer` as RFC 4180 CSV with a header
+    /// row, one row per span ordered by `start_time_ns, span_id`. Rows are
+    /// written as they are read, so memory stays flat for large exports.
+    /// `attributes` is emitted as its stored JSON text. Returns the row count.
+    pub fn export_spans_csv(
+        &self,
+        mut writer: impl std::io::Write,
+        filter: &SpanFilter,
+    ) -> Result<usize, JavaspectreError> {
+        let (where_clause, values) = filter.where_clause();
+        let sql = format!(
+            r#"
+            SELECT
+              span_id, trace_id, parent_span_id, start_time_ns, end_time_ns,
+              end_time_ns - start_time_ns, span_name, span_kind, status_code,
+              service_name, http_method, http_route, correlation_id, attributes
+            FROM spans
+            {}
+            ORDER BY start_time_ns ASC, span_id ASC
+            "#,
+            where_clause
+        );
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(&sql)?;
+        let mut rows = stmt.query(values.iter().map(|v| v.as_ref()))?;
+
+        writeln!(writer, "{}", SPAN_CSV_COLUMNS.join(","))?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let mut fields = Vec::with_capacity(SPAN_CSV_COLUMNS.len());
+            for idx in 0..SPAN_CSV_COLUMNS.len() {
+                let field = match row.get_raw(idx) {
+                    rusqlite::types::ValueRef::Null => String::new(),
+                    rusqlite::types::ValueRef::Integer(i) => i.to_string(),
+                    rusqlite::types::ValueRef::Real(f) => f.to_string(),
+                    _ => row.get::<_, String>(idx)?,
+                };
+                fields.push(csv_escape(&field));
+            }
+            writeln!(writer, "{}", fields.join(","))?;
+            count += 1;
         }
-    });
+        writer.flush()?;
+        Ok(count)
+    }
+}
 
-    let mut noise_stats_map = serde_json::map::Map::new();
-    noise_stats_map.insert(
-        "dynamic_id_count".to_string(),
-        json!(estimate_dynamic_ids(&dom_snapshot.raw_dom)),
-    );
-    let noise_stats = Value::Object(noise_stats_map);
+/// Quote a CSV field when it contains a delimiter, quote, or line break.
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
 
-    let dom_stability_score = Some(JavaspectreStore::compute_dom_stability(&dom_tree));
+/// Escape a Prometheus label value (backslash, double quote, newline).
+fn prom_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
 
-    let sheet = DomSheetRecord {
-        sheet_id: sheet_id.to_string(),
-        snapshot_id: snapshot_id.to_string(),
-        trace_id: dom_snapshot.trace_id.clone(),
-        correlation_id: dom_snapshot.correlation_id.clone(),
-        dom_stability_score,
-        dom_tree,
-        noise_stats: Some(noise_stats),
-    };
+/// Broken parent links in one trace; see `structural_anomalies`. Span ids
+/// are listed in span start order.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StructuralReport {
+    pub trace_id: String,
+    pub span_count: usize,
+    /// Spans with no `parent_span_id`.
+    pub roots: Vec<String>,
+    /// Spans whose `parent_span_id` names a span not stored for this trace.
+    pub dangling: Vec<String>,
+    /// The parent ids `dangling` spans point at, sorted and deduplicated:
+    /// spans that were referenced but never exported.
+    pub missing_parents: Vec<String>,
+}
 
-    store.insert_dom_sheet(&sheet)?;
-    Ok(())
+impl StructuralReport {
+    /// More than one span claims to be the trace's root.
+    pub fn has_multiple_roots(&self) -> bool {
+        self.roots.len() > 1
+    }
+
+    /// Exactly one root and every parent link resolves. An unknown trace
+    /// (no spans) is not clean.
+    pub fn is_clean(&self) -> bool {
+        self.roots.len() == 1 && self.dangling.is_empty()
+    }
 }
 
-fn count_nodes_with_tag(dom: &Value, tag: &str) -> i64 {
-    fn recurse(v: &Value, tag: &str, count: &mut i64) {
-        match v {
-            Value::Object(map) => {
-                if let Some(Value::String(t)) = map.get("tag") {
-                    if t.eq_ignore_ascii_case(tag) {
-                        *count += 1;
+impl JavaspectreStore {
+    /// Summarize how `trace_id`'s parent links are broken: its roots, spans
+    /// whose parent is missing from the trace, and the missing parent ids.
+    /// A span naming itself as parent counts as dangling. An unknown trace
+    /// yields an empty report.
+    pub fn structural_anomalies(
+        &self,
+        trace_id: &str,
+    ) -> Result<StructuralReport, JavaspectreError> {
+        use std::collections::{BTreeSet, HashSet};
+
+        let spans = self.find_spans_by_column("trace_id", trace_id)?;
+        let ids: HashSet<&str> = spans.iter().map(|s| s.span_id.as_str()).collect();
+        let mut report = StructuralReport {
+            trace_id: trace_id.to_string(),
+            span_count: spans.len(),
+            ..StructuralReport::default()
+        };
+        let mut missing = BTreeSet::new();
+        for span in &spans {
+            match span.parent_span_id.as_deref() {
+                None => report.roots.push(span.span_id.clone()),
+                Some(parent) if parent != span.span_id && ids.contains(parent) => {}
+                Some(parent) => {
+                    report.dangling.push(span.span_id.clone());
+                    if parent != span.span_id {
+                        missing.insert(parent.to_string());
                     }
                 }
-                for (_, child) in map {
-                    recurse(child, tag, count);
+            }
+        }
+        report.missing_parents = missing.into_iter().collect();
+        Ok(report)
+    }
+}
+
+/// Outcome of a content-hash deduplication pass.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DedupStats {
+    /// Rows examined.
+    pub scanned: usize,
+    /// Duplicate rows removed (each folded into the earliest identical row).
+    pub merged: usize,
+    /// Dependent rows whose foreign key was moved to the surviving row.
+    pub repointed: usize,
+}
+
+impl JavaspectreStore {
+    /// Collapse DOM snapshots whose `raw_dom` is identical under canonical JSON.
+    ///
+    /// Within each group of identical snapshots the earliest (`captured_at_ns`,
+    /// then `snapshot_id`) survives; `dom_sheets` rows referencing a duplicate
+    /// are repointed to the survivor before the duplicate is deleted, so the
+    /// `ON DELETE CASCADE` never drops a sheet. Runs in a single transaction.
+    pub fn dedup_dom_snapshots(&self) -> Result<DedupStats, JavaspectreError> {
+        use std::collections::HashMap;
+
+        let conn = &*self.conn;
+        let mut stats = DedupStats::default();
+        let mut keepers: HashMap<String, String> = HashMap::new();
+        let mut duplicates: Vec<(String, String)> = Vec::new();
+        {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT snapshot_id, raw_dom
+                FROM dom_snapshots
+                ORDER BY captured_at_ns ASC, snapshot_id ASC
+                "#,
+            )?;
+            let mut rows = stmt.query(NO_PARAMS)?;
+            while let Some(row) = rows.next()? {
+                let snapshot_id: String = row.get(0)?;
+                let hash = stable_snapshot_hash(&json_column(row, 1)?)?;
+                stats.scanned += 1;
+                match keepers.get(&hash) {
+                    Some(keeper) => duplicates.push((snapshot_id, keeper.clone())),
+                    None => {
+                        keepers.insert(hash, snapshot_id);
+                    }
                 }
             }
-            Value::Array(arr) => {
-                for child in arr {
-                    recurse(child, tag, count);
+        }
+
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut repoint =
+                tx.prepare("UPDATE dom_sheets SET snapshot_id = ?2 WHERE snapshot_id = ?1")?;
+            let mut delete = tx.prepare("DELETE FROM dom_snapshots WHERE snapshot_id = ?1")?;
+            for (duplicate, keeper) in &duplicates {
+                stats.repointed += repoint.execute(params![duplicate, keeper])?;
+                stats.merged += delete.execute(params![duplicate])?;
+            }
+        }
+        tx.commit()?;
+        Ok(stats)
+    }
+
+    /// Collapse HAR entries that repeat the same request within a correlation.
+    ///
+    /// Entries are fingerprinted by `build_endpoint_key(method, url)` (with the
+    /// url passed through `normalize_har_url`) plus the canonical hash of the
+    /// request's `postData`. Within each correlation the earliest entry
+    /// (`started_at_ns`, then `entry_id`) of every fingerprint survives and the
+    /// rest are deleted in a single transaction. Entries without a correlation
+    /// id are scanned but never merged. Nothing references `har_entries`, so
+    /// `repointed` is always 0.
+    pub fn dedup_har_entries(&self) -> Result<DedupStats, JavaspectreError> {
+        use std::collections::HashSet;
+
+        let conn = &*self.conn;
+        let mut stats = DedupStats::default();
+        let mut seen: HashSet<(String, String, String)> = HashSet::new();
+        let mut duplicates: Vec<String> = Vec::new();
+        {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT entry_id, correlation_id, method, url, request_json
+                FROM har_entries
+                ORDER BY started_at_ns IS NULL, started_at_ns ASC, entry_id ASC
+                "#,
+            )?;
+            let mut rows = stmt.query(NO_PARAMS)?;
+            while let Some(row) = rows.next()? {
+                stats.scanned += 1;
+                let entry_id: String = row.get(0)?;
+                let correlation_id: Option<String> = row.get(1)?;
+                let Some(correlation_id) = correlation_id else {
+                    continue;
+                };
+                let method: Option<String> = row.get(2)?;
+                let url: Option<String> = row.get(3)?;
+                let request = opt_json_column(row, 4)?;
+                let body = request
+                    .as_ref()
+                    .and_then(|r| r.get("postData"))
+                    .cloned()
+                    .unwrap_or(Value::Null);
+                let endpoint = build_endpoint_key(
+                    method.as_deref().unwrap_or(""),
+                    &normalize_har_url(url.as_deref().unwrap_or("")),
+                );
+                let fingerprint = (correlation_id, endpoint, stable_snapshot_hash(&body)?);
+                if !seen.insert(fingerprint) {
+                    duplicates.push(entry_id);
                 }
             }
-            _ => {}
         }
+
+        let tx = conn.unchecked_transaction()?;
+        {
+            let mut delete = tx.prepare("DELETE FROM har_entries WHERE entry_id = ?1")?;
+            for duplicate in &duplicates {
+                stats.merged += delete.execute(params![duplicate])?;
+            }
+        }
+        tx.commit()?;
+        Ok(stats)
     }
-    let mut count = 0;
-    recurse(dom, tag, &mut count);
-    count
 }
 
-fn estimate_dynamic_ids(dom: &Value) -> i64 {
-    fn recurse(v: &Value, count: &mut i64) {
-        match v {
-            Value::Object(map) => {
-                if let Some(Value::String(id)) = map.get("id") {
-                    if id.contains("uuid")
-                        || id.contains("session")
-                        || id.contains("abtest")
-                        || id.chars().any(|c| c.is_ascii_digit())
-                    {
-                        *count += 1;
-                    }
+/// Represents a Javaspectre "virtual object" cluster across traces, DOM, and HAR.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VirtualObjectCluster {
+    pub correlation_id: String,
+    pub spans: Vec<SpanRecord>,
+    pub dom_sheets: Vec<DomSheetRecord>,
+    pub har_entries: Vec<HarEntryRecord>,
+}
+
+impl JavaspectreStore {
+    /// Mirror each span's `attributes` into `span_attributes` as one row per
+    /// leaf: nested keys are joined with `.` and array elements use their
+    /// index (`tags.0`). Strings and booleans land in `value_text`, numbers in
+    /// `value_num`. When leaves collide on a key (`{"db.rows": 1}` next to
+    /// `{"db": {"rows": 2}}`), the one spelled with fewer nesting levels wins.
+    /// Only spans whose attributes changed since the last run are rewritten,
+    /// and rows for deleted spans are dropped. Returns the number of spans
+    /// (re)flattened.
+    pub fn flatten_span_attributes(&self) -> Result<usize, JavaspectreError> {
+        self.flatten_span_attributes_cancellable(|| true)
+    }
+
+    /// `flatten_span_attributes` in `maintenance_chunk_size` chunks of spans
+    /// (by `span_id`), one transaction each. `should_continue` is asked
+    /// between chunks; when it returns false the committed chunks are kept,
+    /// rows for deleted spans are left for the next run, and `Cancelled`
+    /// reports how many spans were flattened.
+    pub fn flatten_span_attributes_cancellable(
+        &self,
+        mut should_continue: impl FnMut() -> bool,
+    ) -> Result<usize, JavaspectreError> {
+        let mut after = String::new();
+        let mut flattened = 0;
+        loop {
+            let (chunk_flattened, scanned, last) =
+                self.retry_busy(|| self.flatten_span_attributes_chunk(&after))?;
+            flattened += chunk_flattened;
+            match last {
+                Some(last) if scanned == self.maintenance_chunk_size => after = last,
+                _ => break,
+            }
+            if !should_continue() {
+                return Err(JavaspectreError::Cancelled {
+                    completed: flattened,
+                });
+            }
+        }
+        self.retry_busy(|| {
+            Ok(self.conn.execute_batch(
+                r#"
+                DELETE FROM span_attributes
+                  WHERE span_id NOT IN (SELECT span_id FROM spans);
+                DELETE FROM span_attributes_state
+                  WHERE span_id NOT IN (SELECT span_id FROM spans);
+                "#,
+            )?)
+        })?;
+        Ok(flattened)
+    }
+
+    /// Flatten the next chunk of spans after `after`, returning how many were
+    /// rewritten, how many were scanned, and the last `span_id` seen.
+    fn flatten_span_attributes_chunk(
+        &self,
+        after: &str,
+    ) -> Result<(usize, usize, Option<String>), JavaspectreError> {
+        use sha2::{Digest, Sha256};
+
+        let conn = &*self.conn;
+        let tx = conn.unchecked_transaction()?;
+        let mut flattened = 0;
+        let mut scanned = 0;
+        let mut last = None;
+        {
+            let mut select = tx.prepare(
+                r#"
+                SELECT s.span_id, s.attributes, st.attributes_hash
+                FROM spans s
+                LEFT JOIN span_attributes_state st ON st.span_id = s.span_id
+                WHERE s.span_id > ?1
+                ORDER BY s.span_id ASC
+                LIMIT ?2
+                "#,
+            )?;
+            let mut clear = tx.prepare("DELETE FROM span_attributes WHERE span_id = ?1")?;
+            // Objects iterate in sorted key order, so of two leaves with the
+            // same key the one spelled with fewer nesting levels comes last
+            // (`db` < `db.rows`) and replaces the other.
+            let mut insert = tx.prepare(
+                r#"
+                INSERT OR REPLACE INTO span_attributes (span_id, key, value_text, value_num)
+                VALUES (?1, ?2, ?3, ?4)
+                "#,
+            )?;
+            let mut mark = tx.prepare(
+                r#"
+                INSERT INTO span_attributes_state (span_id, attributes_hash)
+                VALUES (?1, ?2)
+                ON CONFLICT(span_id) DO UPDATE SET attributes_hash = excluded.attributes_hash
+                "#,
+            )?;
+
+            let mut rows = select.query(params![after, self.maintenance_chunk_size as i64])?;
+            while let Some(row) = rows.next()? {
+                let span_id: String = row.get(0)?;
+                let attributes_text: String = row.get(1)?;
+                let previous: Option<String> = row.get(2)?;
+                scanned += 1;
+                last = Some(span_id.clone());
+                let hash = hex::encode(Sha256::digest(attributes_text.as_bytes()));
+                if previous.as_deref() == Some(hash.as_str()) {
+                    continue;
                 }
-                for (_, child) in map {
-                    recurse(child, count);
+
+                let attributes: Value = serde_json::from_str(&attributes_text)?;
+                let mut leaves = Vec::new();
+                flatten_json("", &attributes, &mut leaves);
+                clear.execute(params![span_id])?;
+                for (key, value_text, value_num) in leaves {
+                    insert.execute(params![span_id, key, value_text, value_num])?;
                 }
+                mark.execute(params![span_id, hash])?;
+                flattened += 1;
             }
-            Value::Array(arr) => {
-                for child in arr {
-                    recurse(child, count);
-                }
+        }
+        tx.commit()?;
+        Ok((flattened, scanned, last))
+    }
+}
+
+/// Collect `(key, value_text, value_num)` leaves of `value` under `prefix`.
+fn flatten_json(prefix: &str, value: &Value, out: &mut Vec<(String, Option<String>, Option<f64>)>) {
+    let child_key = |k: &str| {
+        if prefix.is_empty() {
+            k.to_string()
+        } else {
+            format!("{}.{}", prefix, k)
+        }
+    };
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                flatten_json(&child_key(k), v, out);
+            }
+        }
+        Value::Array(items) => {
+            for (i, v) in items.iter().enumerate() {
+                flatten_json(&child_key(&i.to_string()), v, out);
             }
-            _ => {}
         }
+        Value::String(text) => out.push((prefix.to_string(), Some(text.clone()), None)),
+        Value::Number(n) => out.push((prefix.to_string(), None, n.as_f64())),
+        Value::Bool(b) => out.push((prefix.to_string(), Some(b.to_string()), None)),
+        Value::Null => out.push((prefix.to_string(), None, None)),
     }
-    let mut count = 0;
-    recurse(dom, &mut count);
-    count
 }
 
-// The file intentionally contains only Rust code and is ready to be integrated
-// into the broader Cybercore-Brain / Cyberswarm ecosystem as a storage and
-// correlation subsystem for Javaspectre.
+/// Per-correlation row counts used to browse recorded sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationSummary {
+    pub correlation_id: String,
+    pub span_count: i64,
+    pub dom_sheet_count: i64,
+    pub har_count: i64,
+    pub first_ns: Option<i64>,
+    pub last_ns: Option<i64>,
+}
+
+/// Aggregates for one correlation, as stored in `cluster_summaries`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClusterSummary {
+    pub correlation_id: String,
+    pub span_count: i64,
+    /// Spans matching the same error rule as `red_metrics`.
+    pub error_count: i64,
+    pub total_duration_ns: i64,
+    /// Earliest span start and latest span end; `None` without spans.
+    pub earliest_ns: Option<i64>,
+    pub latest_ns: Option<i64>,
+    pub dom_sheet_count: i64,
+}
+
+/// Example integration point with a higher-level ScoreEngine.
+/// Scores can be computed by Cybercore-Brain and persisted into auxiliary tables.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterScore {
+    pub correlation_id: String,
+    pub stability_score: f64,
+    pub novelty_score: f64,
+    pub drift_score: f64,
+}
+
+const UPSERT_CLUSTER_SCORE_SQL: &str = r#"
+    INSERT INTO cluster_scores (
+      correlation_id, stability_score, novelty_score,
+      drift_score, updated_at_ns
+    ) VALUES (?1, ?2, ?3, ?4, ?5)
+    ON CONFLICT(correlation_id) DO UPDATE SET
+      stability_score = excluded.stability_score,
+      novelty_score = excluded.novelty_score,
+      drift_score = excluded.drift_score,
+      updated_at_ns = excluded.updated_at_ns
+"#;
+
+impl JavaspectreStore {
+    pub fn init_score_table(&self) -> Result<(), JavaspectreError> {
+        let conn = &*self.conn;
+        conn.execute_batch(
+            r#"
+            CREATE TABLE IF NOT EXISTS cluster_scores (
+              correlation_id TEXT PRIMARY KEY,
+              stability_score REAL NOT NULL,
+              novelty_score REAL NOT NULL,
+              drift_score REAL NOT NULL,
+              updated_at_ns INTEGER NOT NULL
+            );
+            "#,
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_cluster_score(
+        &self,
+        score: &ClusterScore,
+        updated_at_ns: i64,
+    ) -> Result<(), JavaspectreError> {
+        let conn = &*self.conn;
+        conn.execute(
+            UPSERT_CLUSTER_SCORE_SQL,
+            params![
+                score.correlation_id,
+                score.stability_score,
+                score.novelty_score,
+                score.drift_score,
+                updated_at_ns
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// `upsert_cluster_score` for each `(score, updated_at_ns)` pair inside
+    /// one transaction; either every score is written or none is. Returns
+    /// the number of scores written.
+    pub fn upsert_cluster_scores(
+        &self,
+        scores: &[(ClusterScore, i64)],
+    ) -> Result<usize, JavaspectreError> {
+        self.retry_busy(|| {
+            let tx = self.conn.unchecked_transaction()?;
+            {
+                let mut stmt = tx.prepare(UPSERT_CLUSTER_SCORE_SQL)?;
+                for (score, updated_at_ns) in scores {
+                    stmt.execute(params![
+                        score.correlation_id,
+                        score.stability_score,
+                        score.novelty_score,
+                        score.drift_score,
+                        updated_at_ns
+                    ])?;
+                }
+            }
+            tx.commit()?;
+            Ok(scores.len())
+        })
+    }
+
+    pub fn load_cluster_score(
+        &self,
+        correlation_id: &str,
+    ) -> Result<Option<ClusterScore>, JavaspectreError> {
+        let conn = &*self.conn;
+        let mut stmt = conn.prepare(
+            r#"
+            SELECT correlation_id, stability_score, novelty_score, drift_score
+            FROM cluster_scores
+            WHERE correlation_id = ?1
+            "#,
+        )?;
+        let mut rows = stmt.query(params![correlation_id])?;
+        if let Some(row) = rows.next()? {
+            Ok(Some(ClusterScore {
+                correlation_id: row.get(0)?,
+                stability_score: row.get(1)?,
+                novelty_score: row.get(2)?,
+                drift_score: row.get(3)?,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Copy a cluster's stored scores into the `attributes` of every span in
+    /// that cluster under `javaspectre.*_score`, using the same deep merge as
+    /// `enrich_span_attributes`. Returns the number of spans tagged.
+    pub fn tag_spans_with_scores(&self, correlation_id: &str) -> Result<usize, JavaspectreError> {
+        let score = self.load_cluster_score(correlation_id)?.ok_or_else(|| {
+            JavaspectreError::Schema(format!(
+                "no cluster score for correlation_id {}",
+                correlation_id
+            ))
+        })?;
+        let patch = json!({
+            "javaspectre": {
+                "stability_score": score.stability_score,
+                "novelty_score": score.novelty_score,
+                "drift_score": score.drift_score,
+            }
+        });
+
+        let span_ids = {
+            let conn = &*self.conn;
+            let mut stmt = conn.prepare("SELECT span_id FROM spans WHERE correlation_id = ?1")?;
+            let rows = stmt.query_map(params![correlation_id], |row| row.get::<_, String>(0))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        for span_id in &span_ids {
+            self.enrich_span_attributes(span_id, &patch)?;
+        }
+        Ok(span_ids.len())
+    }
+}
+
+/// Parse a TEXT column holding JSON, surfacing parse failures as a column conversion error.
+fn json_column(row: &Row<'_>, idx: usize) -> Result<Value, rusqlite::Error> {
+    let text: String = row.get(idx)?;
+    serde_json::from_str(&text).map_err(|e| {
+        rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+    })
+}
+
+/// Whether `err` is SQLite reporting the database busy or locked, the
+/// failures `retry_busy` tries again.
+fn is_busy(err: &JavaspectreError) -> bool {
+    matches!(
+        err,
+        JavaspectreError::Sqlite(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::DatabaseBusy
+                || e.code == rusqlite::ErrorCode::DatabaseLocked
+    )
+}
+
+/// Nullable variant of `json_column`.
+fn opt_json_column(row: &Row<'_>, idx: usize) -> Result<Option<Value>, rusqlite::Error> {
+    match row.get::<_, Option<String>>(idx)? {
+        Some(text) => serde_json::from_str(&text).map(Some).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(idx, rusqlite::types::Type::Text, Box::new(e))
+        }),
+        None => Ok(None),
+    }
+}
+
+/// Recursively merge `patch` into `target`; keys in `patch` win.
+fn merge_json(target: &mut Value, patch: &Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (k, v) in patch {
+                match target.get_mut(k) {
+                    Some(existing) => merge_json(existing, v),
+                    None => {
+                        target.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+/// JSON path selecting the top-level attribute `key`. The key is quoted so
+/// dotted OTel names (`db.system`) are not read as nested objects; SQLite
+/// paths have no escape for `"`, so such keys are rejected.
+fn attribute_json_path(key: &str) -> Result<String, JavaspectreError> {
+    if key.is_empty() || key.contains('"') {
+        return Err(JavaspectreError::Schema(format!(
+            "attribute key {:?} cannot be expressed as a JSON path",
+            key
+        )));
+    }
+    Ok(format!("$.\"{}\"", key))
+}
+
+/// `json_extract` over `key` with the path as an SQL string literal, for
+/// expression indexes and the queries that must match them.
+fn attribute_extract_sql(key: &str) -> Result<String, JavaspectreError> {
+    Ok(format!(
+        "json_extract(attributes, '{}')",
+        attribute_json_path(key)?.replace('\'', "''")
+    ))
+}
+
+/// Index name for `key`; the hash suffix keeps `db.system` and `db_system`
+/// from colliding after sanitizing.
+fn attribute_index_name(key: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let sanitized: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let digest = hex::encode(Sha256::digest(key.as_bytes()));
+    format!("idx_spans_attr_{}_{}", sanitized, &digest[..8])
+}
+
+/// Optional helper to build endpoint_key from method and normalized route.
+pub fn build_endpoint_key(method: &str, route: &str) -> String {
+    format!("{} {}", method.to_uppercase(), route)
+}
+
+/// A HAR request url in comparable form: the `#fragment` is dropped, the
+/// scheme and host are lower-cased and a trailing `/` on a non-root path is
+/// removed. The query string is kept, since it distinguishes requests.
+pub fn normalize_har_url(url: &str) -> String {
+    let url = url.trim();
+    let url = url.split('#').next().unwrap_or("");
+    let (prefix, rest) = match url.find("://") {
+        Some(i) => {
+            let after = &url[i + 3..];
+            let host_end = after.find(['/', '?']).unwrap_or(after.len());
+            let prefix = url[..i + 3 + host_end].to_ascii_lowercase();
+            (prefix, &after[host_end..])
+        }
+        None => (String::new(), url),
+    };
+    let (path, query) = match rest.find('?') {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, ""),
+    };
+    let path = match path.strip_suffix('/') {
+        Some(trimmed) if !trimmed.is_empty() => trimmed,
+        _ => path,
+    };
+    format!("{}{}{}", prefix, path, query)
+}
+
+/// A minimal hash helper for content-addressed snapshots.
+/// In a production system, this should use a proven SHA-256 implementation.
+pub fn stable_snapshot_hash(payload: &Value) -> Result<String, JavaspectreError> {
+    use sha2::{Digest, Sha256};
+
+    let canonical = canonical_json(payload)?;
+    let mut hasher = Sha256::new();
+    hasher.update(canonical.as_bytes());
+    let digest = hasher.finalize();
+    Ok(hex::encode(digest))
+}
+
+/// Canonical JSON serialization to provide deterministic hashes.
+///
+/// Object keys are sorted and numbers are normalized so values that compare
+/// equal numerically serialize identically: floats holding an integer that
+/// fits `i64`/`u64` (including `-0.0`, and exponent forms such as `1e3` or
+/// `1e19`) are written as plain integers; larger integral floats have no
+/// integer form and keep serde_json's shortest float rendering. Other floats are rounded to 15
+/// significant digits, the precision every f64 keeps through a decimal round
+/// trip even with serde_json's fast, not-quite-exact parser. Subnormals, which
+/// lack that precision, are flushed to `0`. Non-finite floats are rejected.
+fn canonical_json(value: &Value) -> Result<String, JavaspectreError> {
+    Ok(serde_json::to_string(&canonical_value(value)?)?)
+}
+
+/// `value` rebuilt with sorted keys and normalized numbers; serializing it
+/// yields `canonical_json`.
+fn canonical_value(v: &Value) -> Result<Value, JavaspectreError> {
+    Ok(match v {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            let mut ordered = serde_json::map::Map::new();
+            for (k, v) in entries {
+                ordered.insert(k.clone(), canonical_value(v)?);
+            }
+            Value::Object(ordered)
+        }
+        Value::Array(arr) => {
+            Value::Array(arr.iter().map(canonical_value).collect::<Result<_, _>>()?)
+        }
+        Value::Number(n) => Value::Number(canonical_number(n)?),
+        _ => v.clone(),
+    })
+}
+
+/// `2^64`: integral floats in `i64::MIN..U64_LIMIT_F64` convert exactly to
+/// an integer `Number`.
+const U64_LIMIT_F64: f64 = 18_446_744_073_709_551_616.0;
+
+/// `x` as an integer `Number` when it is integral and in `i64`/`u64` range.
+fn integral_number(x: f64) -> Option<serde_json::Number> {
+    if x.fract() != 0.0 || x < i64::MIN as f64 || x >= U64_LIMIT_F64 {
+        return None;
+    }
+    Some(if x < 0.0 {
+        serde_json::Number::from(x as i64)
+    } else {
+        serde_json::Number::from(x as u64)
+    })
+}
+
+fn canonical_number(n: &serde_json::Number) -> Result<serde_json::Number, JavaspectreError> {
+    if n.is_i64() || n.is_u64() {
+        return Ok(n.clone());
+    }
+    let f = n
+        .as_f64()
+        .ok_or_else(|| JavaspectreError::Schema(format!("unrepresentable number {}", n)))?;
+    if !f.is_finite() {
+        return Err(JavaspectreError::Schema(format!("non-finite number {}", f)));
+    }
+    if f.is_subnormal() {
+        return Ok(serde_json::Number::from(0));
+    }
+    if let Some(int) = integral_number(f) {
+        return Ok(int);
+    }
+    let rounded: f64 = format!("{:.14e}", f).parse().expect("formatted f64 parses");
+    if let Some(int) = integral_number(rounded) {
+        return Ok(int);
+    }
+    serde_json::Number::from_f64(rounded)
+        .ok_or_else(|| JavaspectreError::Schema(format!("non-finite number {}", rounded)))
+}
+
+/// Identity of a span's content for idempotent ingest: trace and span ids,
+/// timing and name. Attributes are excluded so late enrichment doesn't make a
+/// re-export look new.
+pub fn span_content_hash(span: &SpanRecord) -> String {
+    use sha2::{Digest, Sha256};
+
+    let key = format!(
+        "{}\u{1f}{}\u{1f}{}\u{1f}{}\u{1f}{}",
+        span.trace_id, span.span_id, span.start_time_ns, span.end_time_ns, span.span_name
+    );
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+/// Result of `ingest_otel_span`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IngestOutcome {
+    Stored,
+    /// The span was not written: `dedup_ingest` found the same content
+    /// already stored, or `append_only` kept an existing row.
+    AlreadyPresent,
+}
+
+/// Latest span timestamp `ingest_otel_span` accepts: 2200-01-01T00:00:00Z in
+/// unix nanoseconds. Anything later is almost certainly a unit mix-up.
+const MAX_SANE_UNIX_NANO: i64 = 7_258_118_400_000_000_000;
+
+/// Read a unix-nano timestamp from the OTLP key or its short alias, as a JSON
+/// integer or decimal string, rejecting values outside `0..=MAX_SANE_UNIX_NANO`.
+fn span_timestamp(raw: &Value, otlp_key: &str, key: &str) -> Result<i64, JavaspectreError> {
+    let value = raw
+        .get(otlp_key)
+        .or_else(|| raw.get(key))
+        .ok_or_else(|| JavaspectreError::Schema(format!("missing {}", key)))?;
+    let ns = value
+        .as_str()
+        .and_then(|s| s.parse::<i64>().ok())
+        .or_else(|| value.as_i64())
+        .ok_or_else(|| {
+            JavaspectreError::Schema(format!(
+                "{} is not an i64 nanosecond timestamp: {}",
+                key, value
+            ))
+        })?;
+    if !(0..=MAX_SANE_UNIX_NANO).contains(&ns) {
+        return Err(JavaspectreError::Schema(format!(
+            "{} {} is outside the supported range 0..={}",
+            key, ns, MAX_SANE_UNIX_NANO
+        )));
+    }
+    Ok(ns)
+}
+
+/// Example: ingest a raw OpenTelemetry span JSON blob into the spans table.
+/// This function extracts a few common attributes but keeps the payload semi-structured.
+pub fn ingest_otel_span(
+    store: &JavaspectreStore,
+    raw_span_json: &str,
+) -> Result<IngestOutcome, JavaspectreError> {
+    let raw: Value = serde_json::from_str(raw_span_json)?;
+    let span_id = raw
+        .get("span_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JavaspectreError::Schema("missing span_id".into()))?
+        .to_string();
+    let trace_id = raw
+        .get("trace_id")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| JavaspectreError::Schema("missing trace_id".into()))?
+        .to_string();
+
+    let parent_span_id = raw.get("parent_span_id").and_then(|v| v.as_str()).map(|s| s.to_string());
+    let start_time_ns = span_timestamp(&raw, "start_time_unix_nano", "start_time_ns")?;
+    let end_time_ns = span_timestamp(&raw, "end_time_unix_nano", "end_time_ns")?;
+    if end_time_ns < start_time_ns {
+        return Err(JavaspectreError::Schema(format!(
+            "span {}: end_time_ns {} precedes start_time_ns {}",
+            span_id, end_time_ns, start_time_ns
+        )));
+    }
+
+    let span_name = raw
+        .get("name")
+        .or_else(|| raw.get("span_name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown_span")
+        .to_string();
+
+    let span_kind = raw
+        .get("kind")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    // Extract attributes map if present.
+    let mut attributes = raw
+        .get("attributes")
+        .cloned()
+        .unwrap_or_else(|| Value::Object(serde_json::map::Map::new()));
+
+    // Resource can be nested or separate.
+    let mut resource = raw
+        .get("resource")
+        .cloned()
+        .unwrap_or_else(|| Value::Object(serde_json::map::Map::new()));
+
+    if store.canonical_attributes {
+        attributes = canonical_value(&attributes)?;
+        resource = canonical_value(&resource)?;
+    }
+
+    let service_name = attributes
+        .get("service.name")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let http_method = attributes
+        .get("http.method")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let http_route = attributes
+        .get("http.route")
+        .or_else(|| attributes.get("http.target"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let http_status_code = attributes
+        .get("http.status_code")
+        .or_else(|| attributes.get("http.response.status_code"))
+        .and_then(|v| {
+            v.as_i64()
+                .or_else(|| v.as_str().and_then(|s| s.parse().ok()))
+        });
+    let status_code_attr = http_status_code.map(|c| c.to_string());
+
+    let rpc_method = attributes
+        .get("rpc.method")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let messaging_destination = attributes
+        .get("messaging.destination")
+        .or_else(|| attributes.get("messaging.destination.name"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let status_code = raw
+        .get("status")
+        .and_then(|st| st.get("code"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string())
+        .or(status_code_attr);
+
+    // Correlation ID can be in baggage, attributes, or custom fields.
+    let correlation_id = attributes
+        .get("correlation_id")
+        .or_else(|| attributes.get("correlation.id"))
+        .or_else(|| attributes.get("session.id"))
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+
+    let span_record = SpanRecord {
+        span_id,
+        trace_id,
+        parent_span_id,
+        start_time_ns,
+        end_time_ns,
+        span_name,
+        span_kind,
+        status_code,
+        http_status_code,
+        rpc_method,
+        messaging_destination,
+        service_name,
+        http_method,
+        http_route,
+        correlation_id,
+        attributes,
+        resource,
+        raw_span: raw,
+    };
+
+    let written = if store.dedup_ingest {
+        store.insert_span_if_absent(&span_record)?
+    } else {
+        store.upsert_span(&span_record)?
+    };
+    Ok(if written {
+        IngestOutcome::Stored
+    } else {
+        IngestOutcome::AlreadyPresent
+    })
+}
+
+/// Example: ingest a generic DOM snapshot JSON string.
+pub fn ingest_dom_snapshot(
+    store: &JavaspectreStore,
+    snapshot_id: &str,
+    trace_id: Option<&str>,
+    correlation_id: Option<&str>,
+    captured_at_ns: i64,
+    dom_json: &str,
+) -> Result<(), JavaspectreError> {
+    let raw_dom: Value = serde_json::from_str(dom_json)?;
+    let snap = DomSnapshotRecord {
+        snapshot_id: snapshot_id.to_string(),
+        trace_id: trace_id.map(|s| s.to_string()),
+        correlation_id: correlation_id.map(|s| s.to_string()),
+        captured_at_ns,
+        raw_dom,
+    };
+    store.insert_dom_snapshot(&snap)?;
+    Ok(())
+}
+
+/// Example: derive a simple DOM sheet from a snapshot by picking out roles and node grid.
+/// Real systems can plug in a more advanced stabilizer here.
+pub fn derive_dom_sheet_from_snapshot(
+    store: &JavaspectreStore,
+    sheet_id: &str,
+    snapshot_id: &str,
+    dom_snapshot: &DomSnapshotRecord,
+) -> Result<(), JavaspectreError> {
+    // Example spec-aligned structure: root-level object with roles and basic tag summary.
+    let dom_tree = json!({
+        "roles": {
+            "button_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "button"),
+            "link_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "a"),
+            "input_count": count_nodes_with_tag(&dom_snapshot.raw_dom, "input"),
+        },
+        "paths": interactive_selectors(&dom_snapshot.raw_dom, &store.dynamic_ids),
+        "grid": dom_grid(&dom_snapshot.raw_dom),
+        "meta": {
+            "origin_trace_id": dom_snapshot.trace_id,
+            "origin_correlation_id": dom_snapshot.correlation_id,
+        }
+    });
+
+    let mut noise_stats_map = serde_json::map::Map::new();
+    noise_stats_map.insert(
+        "dynamic_id_count".to_string(),
+        json!(estimate_dynamic_ids(
+            &dom_snapshot.raw_dom,
+            &store.dynamic_ids
+        )),
+    );
+    let noise_stats = Value::Object(noise_stats_map);
+
+    let dom_stability_score = Some(store.compute_dom_stability(&dom_tree));
+
+    let sheet = DomSheetRecord {
+        sheet_id: sheet_id.to_string(),
+        snapshot_id: snapshot_id.to_string(),
+        trace_id: dom_snapshot.trace_id.clone(),
+        correlation_id: dom_snapshot.correlation_id.clone(),
+        dom_stability_score,
+        dom_tree,
+        noise_stats: Some(noise_stats),
+    };
+
+    store.insert_dom_sheet(&sheet)?;
+    Ok(())
+}
+
+fn count_nodes_with_tag(dom: &Value, tag: &str) -> i64 {
+    fn recurse(v: &Value, tag: &str, count: &mut i64) {
+        match v {
+            Value::Object(map) => {
+                if let Some(Value::String(t)) = map.get("tag") {
+                    if t.eq_ignore_ascii_case(tag) {
+                        *count += 1;
+                    }
+                }
+                for (_, child) in map {
+                    recurse(child, tag, count);
+                }
+            }
+            Value::Array(arr) => {
+                for child in arr {
+                    recurse(child, tag, count);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut count = 0;
+    recurse(dom, tag, &mut count);
+    count
+}
+
+const INTERACTIVE_TAGS: [&str; 5] = ["a", "button", "input", "select", "textarea"];
+
+/// Tag-path selectors such as `body > form#login > button` for every
+/// interactive element in `dom`, sorted and deduplicated. Stable ids are
+/// kept on each segment; ids matching `dynamic` are dropped.
+fn interactive_selectors(dom: &Value, dynamic: &RegexSet) -> Vec<String> {
+    fn recurse(v: &Value, dynamic: &RegexSet, chain: &mut Vec<String>, out: &mut BTreeSet<String>) {
+        match v {
+            Value::Object(map) => {
+                let tag = map
+                    .get("tag")
+                    .and_then(Value::as_str)
+                    .map(str::to_ascii_lowercase);
+                if let Some(tag) = &tag {
+                    let mut segment = tag.clone();
+                    if let Some(Value::String(id)) = map.get("id") {
+                        if !id.is_empty() && !dynamic.is_match(id) {
+                            segment.push('#');
+                            segment.push_str(id);
+                        }
+                    }
+                    chain.push(segment);
+                    if INTERACTIVE_TAGS.contains(&tag.as_str()) {
+                        out.insert(chain.join(" > "));
+                    }
+                }
+                for (_, child) in map {
+                    recurse(child, dynamic, chain, out);
+                }
+                if tag.is_some() {
+                    chain.pop();
+                }
+            }
+            Value::Array(arr) => {
+                for child in arr {
+                    recurse(child, dynamic, chain, out);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut out = BTreeSet::new();
+    recurse(dom, dynamic, &mut Vec::new(), &mut out);
+    out.into_iter().collect()
+}
+
+/// Side of one `dom_tree.grid` cell, in CSS pixels.
+const DOM_GRID_CELL_PX: f64 = 100.0;
+/// Rows/columns beyond this are dropped, bounding the grid for huge pages.
+const DOM_GRID_MAX_CELLS: i64 = 64;
+
+/// Role an element contributes to the grid: its explicit `role`, else an
+/// implicit one for interactive tags (`a` is `link`, others use the tag).
+fn element_role(map: &serde_json::Map<String, Value>) -> Option<String> {
+    if let Some(role) = map.get("role").and_then(Value::as_str) {
+        if !role.is_empty() {
+            return Some(role.to_ascii_lowercase());
+        }
+    }
+    let tag = map.get("tag").and_then(Value::as_str)?.to_ascii_lowercase();
+    match tag.as_str() {
+        "a" => Some("link".to_string()),
+        t if INTERACTIVE_TAGS.contains(&t) => Some(tag),
+        _ => None,
+    }
+}
+
+/// Coarse spatial grid over elements carrying a `bbox` (`x`, `y`, `width`,
+/// `height` in CSS pixels): each `DOM_GRID_CELL_PX` cell maps to the role
+/// covering most of its area, ties going to the alphabetically first role.
+/// Cells are listed by row, then column; snapshots without geometry get no
+/// cells.
+fn dom_grid(dom: &Value) -> Value {
+    fn recurse(v: &Value, areas: &mut BTreeMap<(i64, i64), BTreeMap<String, f64>>) {
+        match v {
+            Value::Object(map) => {
+                if let (Some(role), Some(bbox)) = (element_role(map), map.get("bbox")) {
+                    let num = |k: &str| bbox.get(k).and_then(Value::as_f64);
+                    if let (Some(x), Some(y), Some(w), Some(h)) =
+                        (num("x"), num("y"), num("width"), num("height"))
+                    {
+                        let (x0, y0) = (x.max(0.0), y.max(0.0));
+                        let (x1, y1) = (x + w, y + h);
+                        let limit = DOM_GRID_MAX_CELLS as f64 * DOM_GRID_CELL_PX;
+                        let (x1, y1) = (x1.min(limit), y1.min(limit));
+                        if x1 > x0 && y1 > y0 {
+                            let cell = |p: f64| (p / DOM_GRID_CELL_PX).floor() as i64;
+                            for row in cell(y0)..=cell(y1).min(DOM_GRID_MAX_CELLS - 1) {
+                                for col in cell(x0)..=cell(x1).min(DOM_GRID_MAX_CELLS - 1) {
+                                    let (cx, cy) = (
+                                        col as f64 * DOM_GRID_CELL_PX,
+                                        row as f64 * DOM_GRID_CELL_PX,
+                                    );
+                                    let overlap_w = x1.min(cx + DOM_GRID_CELL_PX) - x0.max(cx);
+                                    let overlap_h = y1.min(cy + DOM_GRID_CELL_PX) - y0.max(cy);
+                                    if overlap_w > 0.0 && overlap_h > 0.0 {
+                                        *areas
+                                            .entry((row, col))
+                                            .or_default()
+                                            .entry(role.clone())
+                                            .or_default() += overlap_w * overlap_h;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                for (_, child) in map {
+                    recurse(child, areas);
+                }
+            }
+            Value::Array(arr) => {
+                for child in arr {
+                    recurse(child, areas);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut areas = BTreeMap::new();
+    recurse(dom, &mut areas);
+    let cells: Vec<Value> = areas
+        .into_iter()
+        .filter_map(|((row, col), roles)| {
+            // Roles iterate alphabetically and only a strictly larger area
+            // displaces the current pick, so ties go to the first role.
+            let (role, _) = roles
+                .into_iter()
+                .fold(None, |best: Option<(String, f64)>, (role, area)| match best {
+                    Some((_, best_area)) if best_area >= area => best,
+                    _ => Some((role, area)),
+                })?;
+            Some(json!({"row": row, "col": col, "role": role}))
+        })
+        .collect();
+    json!({"cell_px": DOM_GRID_CELL_PX, "cells": cells})
+}
+
+/// Number of elements in `dom` whose id matches `dynamic`.
+fn estimate_dynamic_ids(dom: &Value, dynamic: &RegexSet) -> i64 {
+    fn recurse(v: &Value, dynamic: &RegexSet, count: &mut i64) {
+        match v {
+            Value::Object(map) => {
+                if let Some(Value::String(id)) = map.get("id") {
+                    if dynamic.is_match(id) {
+                        *count += 1;
+                    }
+                }
+                for (_, child) in map {
+                    recurse(child, dynamic, count);
+                }
+            }
+            Value::Array(arr) => {
+                for child in arr {
+                    recurse(child, dynamic, count);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut count = 0;
+    recurse(dom, dynamic, &mut count);
+    count
+}
+
+// The file intentionally contains only Rust code and is ready to be integrated
+// into the broader Cybercore-Brain / Cyberswarm ecosystem as a storage and
+// correlation subsystem for Javaspectre.
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory_store() -> JavaspectreStore {
+        JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap()
+    }
+
+    fn span(span_id: &str, correlation_id: Option<&str>, start: i64, end: i64) -> SpanRecord {
+        SpanRecord {
+            span_id: span_id.to_string(),
+            trace_id: "trace-1".to_string(),
+            parent_span_id: None,
+            start_time_ns: start,
+            end_time_ns: end,
+            span_name: "GET /".to_string(),
+            span_kind: None,
+            status_code: None,
+            http_status_code: None,
+            rpc_method: None,
+            messaging_destination: None,
+            service_name: None,
+            http_method: None,
+            http_route: None,
+            correlation_id: correlation_id.map(|s| s.to_string()),
+            attributes: json!({}),
+            resource: json!({}),
+            raw_span: json!({}),
+        }
+    }
+
+    fn har(entry_id: &str, correlation_id: Option<&str>, started_at_ns: i64) -> HarEntryRecord {
+        HarEntryRecord {
+            entry_id: entry_id.to_string(),
+            correlation_id: correlation_id.map(|s| s.to_string()),
+            started_at_ns: Some(started_at_ns),
+            method: Some("GET".to_string()),
+            url: Some("https://example.test/".to_string()),
+            status: Some(200),
+            request_json: None,
+            response_json: None,
+            raw_entry: json!({}),
+        }
+    }
+
+    fn snapshot_with_sheet(store: &JavaspectreStore, id: &str, correlation_id: Option<&str>) {
+        let snap = DomSnapshotRecord {
+            snapshot_id: format!("snap-{}", id),
+            trace_id: None,
+            correlation_id: correlation_id.map(|s| s.to_string()),
+            captured_at_ns: 0,
+            raw_dom: json!({"tag": "body"}),
+        };
+        store.insert_dom_snapshot(&snap).unwrap();
+        derive_dom_sheet_from_snapshot(store, &format!("sheet-{}", id), &snap.snapshot_id, &snap)
+            .unwrap();
+    }
+
+    fn write_config(name: &str, body: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("javaspectre-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        std::fs::write(&path, body).unwrap();
+        path
+    }
+
+    #[test]
+    fn partial_config_files_fill_defaults() {
+        let json =
+            JavaspectreConfig::from_file(write_config("partial.json", r#"{"path": "a.db"}"#))
+                .unwrap();
+        assert_eq!(json.path, "a.db");
+        assert!(!json.read_only);
+        assert!(json.foreign_keys);
+        assert!(json.wal_mode);
+
+        let toml = JavaspectreConfig::from_file(write_config(
+            "partial.toml",
+            "read_only = true\nwal_mode = false\n",
+        ))
+        .unwrap();
+        assert_eq!(toml.path, "javaspectre.db");
+        assert!(toml.read_only);
+        assert!(toml.foreign_keys);
+    }
+
+    #[test]
+    fn contradictory_or_empty_config_is_rejected() {
+        let read_only_wal = write_config("ro-wal.json", r#"{"read_only": true}"#);
+        assert!(matches!(
+            JavaspectreConfig::from_file(read_only_wal),
+            Err(JavaspectreError::Config(_))
+        ));
+
+        let empty_path = write_config("empty.json", r#"{"path": "  "}"#);
+        assert!(matches!(
+            JavaspectreConfig::from_file(empty_path),
+            Err(JavaspectreError::Config(_))
+        ));
+    }
+
+    /// Minimal RFC 4180 reader for round-tripping exports in tests.
+    fn parse_csv(text: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, in_quotes) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', _) => in_quotes = !in_quotes,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                _ => field.push(c),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn export_spans_csv_round_trips() {
+        let store = memory_store();
+        for (i, corr) in ["corr-a", "corr-a", "corr-b"].iter().enumerate() {
+            let mut s = span(
+                &format!("s{}", i),
+                Some(corr),
+                i as i64 * 10,
+                i as i64 * 10 + 5,
+            );
+            s.attributes = json!({"http.route": "/a,b", "note": "say \"hi\""});
+            store.upsert_span(&s).unwrap();
+        }
+
+        let mut out = Vec::new();
+        let count = store
+            .export_spans_csv(&mut out, &SpanFilter::default())
+            .unwrap();
+        assert_eq!(count, 3);
+
+        let rows = parse_csv(&String::from_utf8(out).unwrap());
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], SPAN_CSV_COLUMNS);
+        assert_eq!(rows[1][0], "s0");
+        assert_eq!(rows[2][5], "5");
+        assert_eq!(rows[3][12], "corr-b");
+        let attrs: Value = serde_json::from_str(&rows[1][13]).unwrap();
+        assert_eq!(attrs["note"], "say \"hi\"");
+
+        let filter = SpanFilter {
+            correlation_id: Some("corr-a".into()),
+            ..SpanFilter::default()
+        };
+        assert_eq!(store.export_spans_csv(std::io::sink(), &filter).unwrap(), 2);
+    }
+
+    #[test]
+    fn dedup_dom_snapshots_repoints_sheets_to_earliest() {
+        let store = memory_store();
+        let dom_a = json!({"tag": "body", "children": [{"tag": "button", "id": "buy"}]});
+        let dom_b = json!({"children": [{"id": "buy", "tag": "button"}], "tag": "body"});
+        for (id, at, dom) in [("snap-late", 20, &dom_b), ("snap-early", 10, &dom_a)] {
+            let snap = DomSnapshotRecord {
+                snapshot_id: id.to_string(),
+                trace_id: None,
+                correlation_id: Some("corr-a".into()),
+                captured_at_ns: at,
+                raw_dom: dom.clone(),
+            };
+            store.insert_dom_snapshot(&snap).unwrap();
+            derive_dom_sheet_from_snapshot(&store, &format!("sheet-{}", id), id, &snap).unwrap();
+        }
+
+        let stats = store.dedup_dom_snapshots().unwrap();
+        assert_eq!(
+            stats,
+            DedupStats {
+                scanned: 2,
+                merged: 1,
+                repointed: 1
+            }
+        );
+
+        let sheets = store
+            .load_virtual_object_cluster("corr-a")
+            .unwrap()
+            .dom_sheets;
+        assert_eq!(sheets.len(), 2);
+        assert!(sheets.iter().all(|s| s.snapshot_id == "snap-early"));
+        let remaining: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM dom_snapshots", NO_PARAMS, |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(remaining, 1);
+    }
+
+    #[test]
+    fn dedup_har_entries_keeps_earliest_identical_post() {
+        let store = memory_store();
+        let post = |id: &str, corr: &str, at: i64, url: &str, body: &str| {
+            let mut entry = har(id, Some(corr), at);
+            entry.method = Some("post".to_string());
+            entry.url = Some(url.to_string());
+            entry.request_json = Some(json!({"postData": {"text": body}}));
+            entry
+        };
+        let cart = "https://example.test/cart";
+        let cart_variant = "https://Example.test/cart/#top";
+        for entry in [
+            post("late", "corr-a", 20, cart_variant, "sku=1"),
+            post("early", "corr-a", 10, cart, "sku=1"),
+            post("other-body", "corr-a", 30, cart, "sku=2"),
+            post("other-corr", "corr-b", 40, cart, "sku=1"),
+        ] {
+            store.insert_har_entry(&entry).unwrap();
+        }
+
+        let stats = store.dedup_har_entries().unwrap();
+        assert_eq!(
+            stats,
+            DedupStats {
+                scanned: 4,
+                merged: 1,
+                repointed: 0
+            }
+        );
+        let mut stmt = store
+            .conn
+            .prepare("SELECT entry_id FROM har_entries ORDER BY entry_id")
+            .unwrap();
+        let ids: Vec<String> = stmt
+            .query_map(NO_PARAMS, |r| r.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(ids, ["early", "other-body", "other-corr"]);
+    }
+
+    #[test]
+    fn prometheus_metrics_is_well_formed() {
+        let store = memory_store();
+        let mut a = span("s1", Some("corr-a"), 0, 20_000_000);
+        a.service_name = Some("checkout".to_string());
+        a.http_route = Some("/cart".to_string());
+        let mut b = a.clone();
+        b.span_id = "s2".to_string();
+        b.end_time_ns = 2_000_000_000;
+        store.upsert_span(&a).unwrap();
+        store.upsert_span(&b).unwrap();
+        snapshot_with_sheet(&store, "1", Some("corr-a"));
+
+        let text = store.prometheus_metrics_with_buckets(&[0.05, 1.0]).unwrap();
+        let types: Vec<&str> = text.lines().filter(|l| l.starts_with("# TYPE")).collect();
+        assert_eq!(types.len(), 3);
+        for line in types {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            assert_eq!(parts.len(), 4, "malformed TYPE line: {}", line);
+            assert!(["counter", "histogram", "gauge"].contains(&parts[3]));
+        }
+
+        let labels = "service_name=\"checkout\",http_route=\"/cart\"";
+        assert!(text.contains(&format!("javaspectre_spans_total{{{}}} 2", labels)));
+        assert!(text.contains(&format!(
+            "javaspectre_span_duration_seconds_bucket{{{},le=\"0.05\"}} 1",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "javaspectre_span_duration_seconds_bucket{{{},le=\"+Inf\"}} 2",
+            labels
+        )));
+        assert!(text.contains("javaspectre_dom_stability_score{correlation_id=\"corr-a\"}"));
+
+        assert!(store.prometheus_metrics_with_buckets(&[1.0, 0.5]).is_err());
+    }
+
+    #[test]
+    fn list_correlations_counts_each_table() {
+        let store = memory_store();
+        store.upsert_span(&span("s1", Some("corr-a"), 100, 200)).unwrap();
+        store.upsert_span(&span("s2", Some("corr-a"), 150, 400)).unwrap();
+        store.upsert_span(&span("s3", Some("corr-b"), 10, 20)).unwrap();
+        store.upsert_span(&span("s4", None, 1_000, 2_000)).unwrap();
+        store.insert_har_entry(&har("h1", Some("corr-a"), 500)).unwrap();
+        snapshot_with_sheet(&store, "1", Some("corr-a"));
+        snapshot_with_sheet(&store, "2", Some("corr-a"));
+
+        let summaries = store.list_correlations(10, 0).unwrap();
+        assert_eq!(summaries.len(), 2);
+
+        let a = &summaries[0];
+        assert_eq!(a.correlation_id, "corr-a");
+        assert_eq!(a.span_count, 2);
+        assert_eq!(a.dom_sheet_count, 2);
+        assert_eq!(a.har_count, 1);
+        assert_eq!(a.first_ns, Some(100));
+        assert_eq!(a.last_ns, Some(500));
+
+        assert_eq!(summaries[1].correlation_id, "corr-b");
+        assert_eq!(store.list_correlations(1, 1).unwrap()[0].correlation_id, "corr-b");
+    }
+
+    #[test]
+    fn find_spans_by_http_status_range() {
+        let store = memory_store();
+        for (i, code) in [json!(200), json!(404), json!("503"), json!(500)]
+            .iter()
+            .enumerate()
+        {
+            let raw = json!({
+                "span_id": format!("s{}", i),
+                "trace_id": "trace-1",
+                "start_time_ns": i as i64 * 10,
+                "end_time_ns": i as i64 * 10 + 5,
+                "attributes": {"http.status_code": code},
+                "status": {"code": "STATUS_CODE_UNSET"}
+            });
+            ingest_otel_span(&store, &raw.to_string()).unwrap();
+        }
+
+        let errors = store.find_spans_by_http_status(500, 599).unwrap();
+        let ids: Vec<(&str, Option<i64>)> = errors
+            .iter()
+            .map(|s| (s.span_id.as_str(), s.http_status_code))
+            .collect();
+        assert_eq!(ids, vec![("s2", Some(503)), ("s3", Some(500))]);
+        assert_eq!(errors[0].status_code.as_deref(), Some("STATUS_CODE_UNSET"));
+
+        assert_eq!(store.find_spans_by_http_status(400, 499).unwrap().len(), 1);
+    }
+
+    fn arb_json() -> impl proptest::strategy::Strategy<Value = Value> {
+        use proptest::prelude::*;
+        let leaf = prop_oneof![
+            Just(Value::Null),
+            any::<bool>().prop_map(Value::Bool),
+            any::<i64>().prop_map(Value::from),
+            any::<f64>().prop_map(Value::from),
+            (-1_000_000i64..1_000_000).prop_map(|i| Value::from(i as f64)),
+            "[a-z]{0,6}".prop_map(Value::from),
+        ];
+        leaf.prop_recursive(4, 48, 6, |inner| {
+            prop_oneof![
+                proptest::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+                proptest::collection::vec(("[a-z]{1,4}", inner), 0..6)
+                    .prop_map(|entries| Value::Object(entries.into_iter().collect())),
+            ]
+        })
+    }
+
+    /// Rebuild `v` with every integral number stored as an f64, the way it
+    /// would arrive from a producer that emits `1.0` instead of `1`.
+    fn floatify(v: &Value) -> Value {
+        match v {
+            Value::Number(n) if n.is_i64() && n.as_i64().unwrap().abs() < (1 << 53) => {
+                Value::from(n.as_i64().unwrap() as f64)
+            }
+            Value::Array(arr) => Value::Array(arr.iter().map(floatify).collect()),
+            Value::Object(map) => Value::Object(
+                map.iter()
+                    .rev()
+                    .map(|(k, v)| (k.clone(), floatify(v)))
+                    .collect(),
+            ),
+            _ => v.clone(),
+        }
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn canonical_json_is_deterministic(v in arb_json()) {
+            let canonical = canonical_json(&v).unwrap();
+            let reparsed: Value = serde_json::from_str(&canonical).unwrap();
+            proptest::prop_assert_eq!(&canonical_json(&reparsed).unwrap(), &canonical);
+            proptest::prop_assert_eq!(&canonical_json(&floatify(&v)).unwrap(), &canonical);
+            proptest::prop_assert_eq!(&canonical_json(&v.clone()).unwrap(), &canonical);
+        }
+    }
+
+    #[test]
+    fn canonical_json_normalizes_integral_floats() {
+        assert_eq!(
+            canonical_json(&json!({"b": 2.0, "a": [-0.0, 1.5, 3]})).unwrap(),
+            r#"{"a":[0,1.5,3],"b":2}"#
+        );
+        // Regression: serde_json parses this one ulp off, which used to make
+        // canonicalization not idempotent.
+        let v = json!(919449315151.5637);
+        let reparsed: Value = serde_json::from_str(&canonical_json(&v).unwrap()).unwrap();
+        assert_eq!(
+            canonical_json(&reparsed).unwrap(),
+            canonical_json(&v).unwrap()
+        );
+    }
+
+    #[test]
+    fn canonical_json_writes_exponent_integers_in_decimal() {
+        let plain: Value = serde_json::from_str(r#"{"a": 1000}"#).unwrap();
+        let exponent: Value = serde_json::from_str(r#"{"a": 1e3}"#).unwrap();
+        assert!(exponent["a"].is_f64());
+        assert_eq!(canonical_json(&exponent).unwrap(), r#"{"a":1000}"#);
+        assert_eq!(
+            stable_snapshot_hash(&plain).unwrap(),
+            stable_snapshot_hash(&exponent).unwrap()
+        );
+
+        // Beyond 2^53 but within u64/i64: still a plain integer.
+        let large: Value = serde_json::from_str(r#"[1e19, 10000000000000000000, -4e18]"#).unwrap();
+        assert_eq!(
+            canonical_json(&large).unwrap(),
+            "[10000000000000000000,10000000000000000000,-4000000000000000000]"
+        );
+        let reparsed: Value = serde_json::from_str(&canonical_json(&large).unwrap()).unwrap();
+        assert_eq!(
+            canonical_json(&reparsed).unwrap(),
+            canonical_json(&large).unwrap()
+        );
+    }
+
+    #[test]
+    fn enrich_span_attributes_deep_merges() {
+        let store = memory_store();
+        let mut s = span("s1", Some("corr-a"), 0, 10);
+        s.attributes = json!({
+            "http.route": "/checkout",
+            "javaspectre": {"source": "otel", "tags": ["a"]}
+        });
+        store.upsert_span(&s).unwrap();
+
+        store
+            .enrich_span_attributes(
+                "s1",
+                &json!({"javaspectre": {"score": 0.5, "tags": ["b"]}, "team": "payments"}),
+            )
+            .unwrap();
+
+        let enriched = store.find_slow_spans_with_dom(0, 10).unwrap().remove(0).0;
+        assert_eq!(
+            enriched.attributes,
+            json!({
+                "http.route": "/checkout",
+                "team": "payments",
+                "javaspectre": {"source": "otel", "score": 0.5, "tags": ["b"]}
+            })
+        );
+        assert_eq!(enriched.correlation_id.as_deref(), Some("corr-a"));
+        assert!(store.enrich_span_attributes("missing", &json!({})).is_err());
+    }
+
+    #[test]
+    fn tag_spans_with_scores_writes_cluster_scores() {
+        let store = memory_store();
+        store.init_score_table().unwrap();
+        let mut member = span("s1", Some("corr-a"), 0, 10);
+        member.attributes = json!({"javaspectre": {"source": "otel"}});
+        store.upsert_span(&member).unwrap();
+        store
+            .upsert_span(&span("s2", Some("corr-b"), 0, 10))
+            .unwrap();
+        store
+            .upsert_cluster_score(
+                &ClusterScore {
+                    correlation_id: "corr-a".to_string(),
+                    stability_score: 0.75,
+                    novelty_score: 0.25,
+                    drift_score: 0.5,
+                },
+                1,
+            )
+            .unwrap();
+
+        assert_eq!(store.tag_spans_with_scores("corr-a").unwrap(), 1);
+
+        let spans = store.find_slow_spans_with_dom(0, 10).unwrap();
+        let tagged = spans.iter().find(|(s, _)| s.span_id == "s1").unwrap();
+        assert_eq!(
+            tagged.0.attributes,
+            json!({"javaspectre": {
+                "source": "otel",
+                "stability_score": 0.75,
+                "novelty_score": 0.25,
+                "drift_score": 0.5
+            }})
+        );
+        let other = spans.iter().find(|(s, _)| s.span_id == "s2").unwrap();
+        assert_eq!(other.0.attributes, json!({}));
+        assert!(store.tag_spans_with_scores("corr-missing").is_err());
+    }
+
+    #[test]
+    fn flag_unstable_sheets_uses_nearest_rank_with_ties() {
+        let store = memory_store();
+        assert!(store.flag_unstable_sheets(25.0).unwrap().is_empty());
+
+        let scores = [
+            ("a", 0.9),
+            ("b", 0.1),
+            ("c", 0.5),
+            ("d", 0.3),
+            ("e", 0.3),
+            ("f", 0.7),
+            ("g", 0.2),
+            ("h", 0.8),
+        ];
+        for (id, score) in scores {
+            snapshot_with_sheet(&store, id, None);
+            store
+                .conn
+                .execute(
+                    "UPDATE dom_sheets SET dom_stability_score = ?2 WHERE sheet_id = ?1",
+                    params![format!("sheet-{}", id), score],
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            store.flag_unstable_sheets(25.0).unwrap(),
+            ["sheet-b", "sheet-g"]
+        );
+        assert_eq!(
+            store.flag_unstable_sheets(30.0).unwrap(),
+            ["sheet-b", "sheet-g", "sheet-d", "sheet-e"]
+        );
+        assert!(store.flag_unstable_sheets(0.0).unwrap().is_empty());
+        assert_eq!(
+            store.flag_unstable_sheets(100.0).unwrap().len(),
+            scores.len()
+        );
+        assert!(store.flag_unstable_sheets(101.0).is_err());
+    }
+
+    #[test]
+    fn cached_dom_trees_are_not_reparsed() {
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            dom_tree_cache_size: 4,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        snapshot_with_sheet(&store, "a", Some("corr-a"));
+
+        store.recompute_dom_stability_scores().unwrap();
+        store.recompute_dom_stability_scores().unwrap();
+        store.load_virtual_object_cluster("corr-a").unwrap();
+        assert_eq!(store.dom_tree_parse_count(), 1);
+
+        snapshot_with_sheet(&store, "a", Some("corr-a"));
+        store.recompute_dom_stability_scores().unwrap();
+        assert_eq!(store.dom_tree_parse_count(), 2);
+
+        let uncached = memory_store();
+        snapshot_with_sheet(&uncached, "a", Some("corr-a"));
+        uncached.recompute_dom_stability_scores().unwrap();
+        uncached.recompute_dom_stability_scores().unwrap();
+        assert_eq!(uncached.dom_tree_parse_count(), 2);
+    }
+
+    #[test]
+    fn cancelled_maintenance_stops_after_the_current_chunk() {
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            maintenance_chunk_size: 2,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        for id in ["a", "b", "c", "d", "e"] {
+            snapshot_with_sheet(&store, id, Some("corr-a"));
+            store.upsert_span(&span(id, Some("corr-a"), 0, 10)).unwrap();
+        }
+        store
+            .conn
+            .execute("UPDATE dom_sheets SET dom_stability_score = NULL", NO_PARAMS)
+            .unwrap();
+        let scored = || -> Vec<String> {
+            let mut stmt = store
+                .conn
+                .prepare(
+                    "SELECT sheet_id FROM dom_sheets
+                     WHERE dom_stability_score IS NOT NULL ORDER BY sheet_id",
+                )
+                .unwrap();
+            let rows = stmt.query_map(NO_PARAMS, |row| row.get(0)).unwrap();
+            rows.collect::<rusqlite::Result<_>>().unwrap()
+        };
+
+        let mut asked = 0;
+        match store.recompute_dom_stability_scores_cancellable(|| {
+            asked += 1;
+            false
+        }) {
+            Err(JavaspectreError::Cancelled { completed }) => assert_eq!(completed, 2),
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        assert_eq!(asked, 1);
+        assert_eq!(scored(), ["sheet-a", "sheet-b"]);
+
+        assert_eq!(store.recompute_dom_stability_scores_cancellable(|| true).unwrap(), 5);
+        assert_eq!(scored().len(), 5);
+
+        match store.flatten_span_attributes_cancellable(|| false) {
+            Err(JavaspectreError::Cancelled { completed }) => assert_eq!(completed, 2),
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        // The committed chunk is skipped on resume.
+        assert_eq!(store.flatten_span_attributes().unwrap(), 3);
+    }
+
+    #[test]
+    fn explicit_chunk_size_commits_every_chunk() {
+        let store = memory_store();
+        for i in 0..2500 {
+            snapshot_with_sheet(&store, &format!("{:04}", i), None);
+        }
+        store
+            .conn
+            .execute(
+                "UPDATE dom_sheets SET dom_stability_score = NULL",
+                NO_PARAMS,
+            )
+            .unwrap();
+        let unscored = || -> i64 {
+            store
+                .conn
+                .query_row(
+                    "SELECT COUNT(*) FROM dom_sheets WHERE dom_stability_score IS NULL",
+                    NO_PARAMS,
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+
+        // Cancelling after the first boundary leaves exactly one chunk scored.
+        match store.recompute_dom_stability_scores_chunked(1000, || false) {
+            Err(JavaspectreError::Cancelled { completed }) => assert_eq!(completed, 1000),
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        assert_eq!(unscored(), 1500);
+
+        // Two full chunks ask to continue; the short third one ends the run.
+        let mut boundaries = 0;
+        let scored = store
+            .recompute_dom_stability_scores_chunked(1000, || {
+                boundaries += 1;
+                true
+            })
+            .unwrap();
+        assert_eq!((scored, boundaries), (2500, 2));
+        assert_eq!(unscored(), 0);
+
+        assert!(matches!(
+            store.recompute_dom_stability_scores_chunked(0, || true),
+            Err(JavaspectreError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn equal_durations_and_scores_break_ties_by_id() {
+        let store = memory_store();
+        for id in ["s3", "s1", "s2"] {
+            store.upsert_span(&span(id, Some("corr-a"), 0, 10)).unwrap();
+        }
+        snapshot_with_sheet(&store, "b", Some("corr-a"));
+        snapshot_with_sheet(&store, "a", Some("corr-a"));
+
+        let page = store.find_slow_spans_with_dom(0, 2).unwrap();
+        let ids: Vec<&str> = page.iter().map(|(s, _)| s.span_id.as_str()).collect();
+        assert_eq!(ids, ["s1", "s2"]);
+        let sheets: Vec<&str> = page[0].1.iter().map(|s| s.sheet_id.as_str()).collect();
+        assert_eq!(sheets, ["sheet-a", "sheet-b"]);
+    }
+
+    #[test]
+    fn append_only_mode_keeps_original_span() {
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            append_only: true,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+
+        let original = span("s1", Some("corr-a"), 0, 10);
+        assert!(store.upsert_span(&original).unwrap());
+        let overwrite = span("s1", Some("corr-b"), 0, 99);
+        assert!(!store.upsert_span(&overwrite).unwrap());
+
+        let stored = store.find_slow_spans_with_dom(0, 10).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].0.end_time_ns, 10);
+        assert_eq!(stored[0].0.correlation_id.as_deref(), Some("corr-a"));
+
+        // Default mode still overwrites.
+        let store = memory_store();
+        store.upsert_span(&original).unwrap();
+        assert!(store.upsert_span(&overwrite).unwrap());
+        assert_eq!(
+            store.find_slow_spans_with_dom(0, 10).unwrap()[0]
+                .0
+                .end_time_ns,
+            99
+        );
+    }
+
+    #[test]
+    fn contended_writes_retry_then_report_busy() {
+        let path = std::env::temp_dir().join(format!("javaspectre-busy-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let open = |busy_timeout_ms| {
+            JavaspectreStore::open(JavaspectreConfig {
+                path: path.to_string_lossy().into_owned(),
+                wal_mode: false,
+                busy_timeout_ms,
+                busy_retries: 2,
+                ..JavaspectreConfig::default()
+            })
+            .unwrap()
+        };
+        let holder = open(5_000);
+        let writer = open(1);
+
+        holder.conn.execute_batch("BEGIN EXCLUSIVE").unwrap();
+        match writer.upsert_span(&span("s1", None, 0, 1)) {
+            Err(JavaspectreError::Busy { attempts }) => assert_eq!(attempts, 3),
+            other => panic!("expected Busy, got {:?}", other),
+        }
+
+        holder.conn.execute_batch("COMMIT").unwrap();
+        assert!(writer.upsert_span(&span("s1", None, 0, 1)).unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn opening_a_db_missing_a_column_reports_schema_drift() {
+        let path =
+            std::env::temp_dir().join(format!("javaspectre-drift-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        // dom_sheets as created before noise_stats existed.
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch(
+                r#"
+                CREATE TABLE dom_sheets (
+                  sheet_id       TEXT PRIMARY KEY,
+                  snapshot_id    TEXT NOT NULL,
+                  trace_id       TEXT,
+                  correlation_id TEXT,
+                  dom_stability_score REAL,
+                  dom_tree       TEXT NOT NULL
+                );
+                "#,
+            )
+            .unwrap();
+
+        let config = JavaspectreConfig {
+            path: path.to_string_lossy().into_owned(),
+            wal_mode: false,
+            ..JavaspectreConfig::default()
+        };
+        match JavaspectreStore::open(config.clone()) {
+            Err(JavaspectreError::Schema(msg)) => {
+                assert!(msg.contains("dom_sheets.noise_stats"), "{}", msg);
+                assert!(!msg.contains("spans."), "{}", msg);
+            }
+            other => panic!("expected Schema error, got {:?}", other.map(|_| ())),
+        }
+
+        Connection::open(&path)
+            .unwrap()
+            .execute_batch("ALTER TABLE dom_sheets ADD COLUMN noise_stats TEXT;")
+            .unwrap();
+        let store = JavaspectreStore::open(config).unwrap();
+        store.init_score_table().unwrap();
+        store.check_schema().unwrap();
+        drop(store);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn closing_the_last_handle_truncates_the_wal() {
+        let path =
+            std::env::temp_dir().join(format!("javaspectre-close-{}.db", std::process::id()));
+        let wal = std::path::PathBuf::from(format!("{}-wal", path.display()));
+        let _ = std::fs::remove_file(&path);
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: path.to_string_lossy().into_owned(),
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        for i in 0..20 {
+            store
+                .upsert_span(&span(&format!("s{}", i), None, 0, 1))
+                .unwrap();
+        }
+        assert!(std::fs::metadata(&wal).unwrap().len() > 0);
+
+        let clone = store.clone();
+        match clone.close() {
+            Err(JavaspectreError::StillShared { handles }) => assert_eq!(handles, 1),
+            other => panic!("expected StillShared, got {:?}", other),
+        }
+        store.close().unwrap();
+        assert_eq!(std::fs::metadata(&wal).map(|m| m.len()).unwrap_or(0), 0);
+
+        let reopened = JavaspectreStore::open(JavaspectreConfig {
+            path: path.to_string_lossy().into_owned(),
+            read_only: true,
+            wal_mode: false,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        assert_eq!(reopened.find_slow_spans_with_dom(0, 100).unwrap().len(), 20);
+        drop(reopened);
+        let _ = std::fs::remove_file(&path);
+        let _ = std::fs::remove_file(&wal);
+    }
+
+    #[test]
+    fn flatten_span_attributes_supports_key_value_queries() {
+        let store = memory_store();
+        let mut fast = span("fast", None, 0, 10);
+        fast.attributes = json!({"http.route": "/a", "db": {"rows": 3, "cached": true}});
+        let mut slow = span("slow", None, 0, 10);
+        slow.attributes = json!({"http.route": "/b", "db": {"rows": 250}, "tags": ["x"]});
+        store.upsert_span(&fast).unwrap();
+        store.upsert_span(&slow).unwrap();
+
+        assert_eq!(store.flatten_span_attributes().unwrap(), 2);
+        assert_eq!(store.flatten_span_attributes().unwrap(), 0);
+
+        let query = |key: &str, min: f64| -> Vec<String> {
+            let mut stmt = store
+                .conn
+                .prepare(
+                    "SELECT span_id FROM span_attributes WHERE key = ?1 AND value_num > ?2 ORDER BY span_id",
+                )
+                .unwrap();
+            let ids = stmt
+                .query_map(params![key, min], |row| row.get(0))
+                .unwrap()
+                .map(|r| r.unwrap())
+                .collect();
+            ids
+        };
+        assert_eq!(query("db.rows", 100.0), vec!["slow".to_string()]);
+
+        let text: String = store
+            .conn
+            .query_row(
+                "SELECT value_text FROM span_attributes WHERE span_id = 'slow' AND key = 'tags.0'",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(text, "x");
+
+        // Only the changed span is re-flattened, and stale keys disappear.
+        fast.attributes = json!({"http.route": "/a", "db": {"rows": 500}});
+        store.upsert_span(&fast).unwrap();
+        assert_eq!(store.flatten_span_attributes().unwrap(), 1);
+        assert_eq!(
+            query("db.rows", 100.0),
+            vec!["fast".to_string(), "slow".to_string()]
+        );
+        let cached: i64 = store
+            .conn
+            .query_row(
+                "SELECT COUNT(*) FROM span_attributes WHERE key = 'db.cached'",
+                NO_PARAMS,
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(cached, 0);
+    }
+
+    #[test]
+    fn flatten_prefers_literal_dotted_keys_over_nested_ones() {
+        let store = memory_store();
+        let mut mixed = span("mixed", None, 0, 10);
+        mixed.attributes = json!({"db.rows": 5, "db": {"rows": 7, "system": "pg"}});
+        store.upsert_span(&mixed).unwrap();
+        store.upsert_span(&span("plain", None, 0, 10)).unwrap();
+
+        assert_eq!(store.flatten_span_attributes().unwrap(), 2);
+        let mut stmt = store
+            .conn
+            .prepare("SELECT key, value_text, value_num FROM span_attributes WHERE span_id = 'mixed' ORDER BY key")
+            .unwrap();
+        let rows: Vec<(String, Option<String>, Option<f64>)> = stmt
+            .query_map(NO_PARAMS, |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("db.rows".to_string(), None, Some(5.0)),
+                ("db.system".to_string(), Some("pg".to_string()), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn permuted_attribute_keys_store_identical_text() {
+        let stored = |store: &JavaspectreStore, span_id: &str| -> (String, String) {
+            store
+                .conn
+                .query_row(
+                    "SELECT attributes, resource FROM spans WHERE span_id = ?1",
+                    params![span_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap()
+        };
+        let exact = r#"{"span_id":"x","trace_id":"t","start_time_ns":1,"end_time_ns":2,
+            "attributes":{"retry":2.0,"ratio":0.1234567890123456789,"tiny":5e-324}}"#;
+
+        // By default keys are sorted but every number is stored as sent.
+        let store = memory_store();
+        ingest_otel_span(&store, exact).unwrap();
+        let (attrs, _) = stored(&store, "x");
+        assert_eq!(
+            attrs,
+            r#"{"ratio":0.12345678901234568,"retry":2.0,"tiny":5e-324}"#
+        );
+
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            canonical_attributes: true,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        let first = r#"{"span_id":"a","trace_id":"t","start_time_ns":1,"end_time_ns":2,
+            "attributes":{"http.method":"GET","retry":2.0,"nested":{"z":1,"a":[1.50]}},
+            "resource":{"service.name":"cart","host":"h1"}}"#;
+        let second = r#"{"span_id":"b","trace_id":"t","start_time_ns":1,"end_time_ns":2,
+            "attributes":{"nested":{"a":[1.5],"z":1},"retry":2,"http.method":"GET"},
+            "resource":{"host":"h1","service.name":"cart"}}"#;
+        ingest_otel_span(&store, first).unwrap();
+        ingest_otel_span(&store, second).unwrap();
+
+        let (attrs_a, resource_a) = stored(&store, "a");
+        assert_eq!((attrs_a.clone(), resource_a), stored(&store, "b"));
+        assert_eq!(attrs_a, r#"{"http.method":"GET","nested":{"a":[1.5],"z":1},"retry":2}"#);
+    }
+
+    #[test]
+    fn critical_path_follows_the_latest_finishing_branch() {
+        let store = memory_store();
+        let child = |id: &str, parent: &str, start: i64, end: i64| SpanRecord {
+            parent_span_id: Some(parent.to_string()),
+            ..span(id, None, start, end)
+        };
+        // root ─┬─ fast (10..40) ── fast-db (15..35)
+        //       └─ slow (12..60) ─┬─ slow-a (13..30)
+        //                         └─ slow-b (20..58) ── slow-b-io (21..50)
+        // `fast` ends first but `slow` is the parallel branch that dominates.
+        for s in [
+            span("root", None, 0, 70),
+            child("fast", "root", 10, 40),
+            child("fast-db", "fast", 15, 35),
+            child("slow", "root", 12, 60),
+            child("slow-a", "slow", 13, 30),
+            child("slow-b", "slow", 20, 58),
+            child("slow-b-io", "slow-b", 21, 50),
+            SpanRecord {
+                trace_id: "other".to_string(),
+                ..child("elsewhere", "root", 0, 1_000)
+            },
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+
+        let path: Vec<String> = store
+            .critical_path("trace-1")
+            .unwrap()
+            .into_iter()
+            .map(|s| s.span_id)
+            .collect();
+        assert_eq!(path, ["root", "slow", "slow-b", "slow-b-io"]);
+        assert!(store.critical_path("missing").unwrap().is_empty());
+    }
+
+    #[test]
+    fn structural_anomalies_report_a_missing_intermediate_span() {
+        let store = memory_store();
+        let child = |id: &str, parent: &str, start: i64| SpanRecord {
+            parent_span_id: Some(parent.to_string()),
+            ..span(id, None, start, start + 5)
+        };
+        // root ── gateway ── (service, never exported) ─┬─ db
+        //                                               └─ cache
+        // plus a second, unrelated root.
+        for s in [
+            span("root", None, 0, 100),
+            child("gateway", "root", 1),
+            child("db", "service", 3),
+            child("cache", "service", 4),
+            span("stray-root", None, 50, 60),
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+
+        let report = store.structural_anomalies("trace-1").unwrap();
+        assert_eq!(report.span_count, 5);
+        assert_eq!(report.roots, ["root", "stray-root"]);
+        assert!(report.has_multiple_roots());
+        assert_eq!(report.dangling, ["db", "cache"]);
+        assert_eq!(report.missing_parents, ["service"]);
+        assert!(!report.is_clean());
+
+        store.upsert_span(&child("service", "gateway", 2)).unwrap();
+        store
+            .conn
+            .execute("DELETE FROM spans WHERE span_id = 'stray-root'", NO_PARAMS)
+            .unwrap();
+        let report = store.structural_anomalies("trace-1").unwrap();
+        assert!(report.is_clean(), "{:?}", report);
+        let unknown = store.structural_anomalies("missing").unwrap();
+        assert_eq!((unknown.span_count, unknown.is_clean()), (0, false));
+    }
+
+    #[test]
+    fn spans_with_attribute_matches_exact_values() {
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            indexed_attributes: vec!["db.system".to_string()],
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        let with_attrs = |id: &str, start: i64, attributes: Value| SpanRecord {
+            attributes,
+            ..span(id, None, start, start + 1)
+        };
+        for s in [
+            with_attrs("pg-2", 20, json!({"db.system": "postgresql"})),
+            with_attrs("pg-1", 10, json!({"db.system": "postgresql", "db.name": "cart"})),
+            with_attrs("pg-like", 30, json!({"db.system": "postgresql-14"})),
+            with_attrs("mysql", 40, json!({"db.system": "mysql"})),
+            with_attrs("nested", 50, json!({"db": {"system": "postgresql"}})),
+            with_attrs("quote", 60, json!({"it's": "x"})),
+            with_attrs("none", 70, json!({})),
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+
+        let ids = |key: &str, value: &str, limit: i64| -> Vec<String> {
+            store
+                .spans_with_attribute(key, value, limit)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.span_id)
+                .collect()
+        };
+        assert_eq!(ids("db.system", "postgresql", 10), ["pg-1", "pg-2"]);
+        assert_eq!(ids("db.system", "postgresql", 1), ["pg-1"]);
+        assert_eq!(ids("db.name", "cart", 10), ["pg-1"]);
+        assert_eq!(ids("it's", "x", 10), ["quote"]);
+        assert!(ids("db.system", "postgresql' OR '1'='1", 10).is_empty());
+        assert!(store.spans_with_attribute("bad\"key", "x", 10).is_err());
+
+        let plan: String = store
+            .conn
+            .query_row(
+                &format!(
+                    "EXPLAIN QUERY PLAN SELECT span_id FROM spans WHERE {} = 'postgresql'",
+                    attribute_extract_sql("db.system").unwrap()
+                ),
+                NO_PARAMS,
+                |row| row.get(3),
+            )
+            .unwrap();
+        assert!(plan.contains("idx_spans_attr_db_system_"), "{}", plan);
+    }
+
+    #[test]
+    fn red_metrics_summarize_routes_in_window() {
+        let store = memory_store();
+        let request = |id: &str, route: &str, start: i64, duration: i64, status: Option<i64>| {
+            SpanRecord {
+                http_route: Some(route.to_string()),
+                http_status_code: status,
+                ..span(id, None, start, start + duration)
+            }
+        };
+        let second = 1_000_000_000;
+        for s in [
+            request("c1", "/cart", 0, 10, Some(200)),
+            request("c2", "/cart", second, 40, Some(500)),
+            request("c3", "/cart", 2 * second, 20, Some(404)),
+            SpanRecord {
+                status_code: Some("STATUS_CODE_ERROR".to_string()),
+                ..request("c4", "/cart", 3 * second, 30, None)
+            },
+            request("p1", "/pay", 5 * second, 100, Some(200)),
+            request("late", "/cart", 10 * second, 1, Some(500)),
+            span("no-route", None, 0, 5),
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+
+        let metrics = store.red_metrics(0, 10 * second).unwrap();
+        let routes: Vec<&str> = metrics.iter().map(|m| m.http_route.as_str()).collect();
+        assert_eq!(routes, ["/cart", "/pay"]);
+
+        let cart = &metrics[0];
+        assert_eq!(cart.request_count, 4);
+        assert_eq!(cart.error_count, 2);
+        assert!((cart.request_rate - 0.4).abs() < 1e-12);
+        assert!((cart.error_rate - 0.5).abs() < 1e-12);
+        assert_eq!((cart.p50_ns, cart.p90_ns, cart.p99_ns), (20, 40, 40));
+
+        assert_eq!(metrics[1].error_count, 0);
+        assert_eq!(metrics[1].p50_ns, 100);
+        assert!(store.red_metrics(5, 5).is_err());
+    }
+
+    #[test]
+    fn attribute_drift_reports_a_key_that_disappears() {
+        let store = memory_store();
+        let request = |id: &str, start: i64, attributes: Value| SpanRecord {
+            http_route: Some("/cart".to_string()),
+            attributes,
+            ..span(id, None, start, start + 1)
+        };
+        for s in [
+            request("a1", 0, json!({"user.id": "u1", "cart.size": 2})),
+            request("a2", 1, json!({"user.id": "u2", "cart.size": 1})),
+            request(
+                "b1",
+                10,
+                json!({"user.id": "u1", "feature": {"flag": true}}),
+            ),
+            request("b2", 11, json!({"feature": {"flag": false}})),
+            SpanRecord {
+                http_route: Some("/other".to_string()),
+                ..request("x", 10, json!({"cart.size": 3}))
+            },
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+
+        let drift = store.attribute_drift("/cart", (0, 10), (10, 20)).unwrap();
+        assert_eq!((drift.spans_a, drift.spans_b), (2, 2));
+        assert_eq!(drift.removed, ["cart.size"]);
+        assert_eq!(drift.added, ["feature.flag"]);
+        assert_eq!(
+            drift.changed,
+            [KeyFrequency {
+                key: "user.id".to_string(),
+                frequency_a: 1.0,
+                frequency_b: 0.5,
+            }]
+        );
+
+        let same = store.attribute_drift("/cart", (0, 10), (0, 10)).unwrap();
+        assert!(same.added.is_empty() && same.removed.is_empty() && same.changed.is_empty());
+        assert!(store.attribute_drift("/cart", (5, 5), (10, 20)).is_err());
+    }
+
+    #[test]
+    fn error_budget_burn_reports_multiplier_over_window() {
+        let store = memory_store();
+        let request = |id: String, start: i64, status: i64| SpanRecord {
+            http_route: Some("/checkout".to_string()),
+            http_status_code: Some(status),
+            ..span(&id, None, start, start + 10)
+        };
+        // Outside the window: errors that must not count.
+        for i in 0..5 {
+            store
+                .upsert_span(&request(format!("old-{}", i), i, 503))
+                .unwrap();
+        }
+        // 1000 requests in the window, 2 of them 5xx and one 4xx.
+        for i in 0..1000 {
+            let status = match i {
+                10 | 20 => 500,
+                30 => 404,
+                _ => 200,
+            };
+            store
+                .upsert_span(&request(format!("req-{}", i), 1_000 + i, status))
+                .unwrap();
+        }
+
+        let burn = store.error_budget_burn("/checkout", 1_000, 0.999).unwrap();
+        assert_eq!(burn.window_end_ns, Some(1_999));
+        assert_eq!((burn.total, burn.errors), (1000, 2));
+        assert!((burn.error_fraction - 0.002).abs() < 1e-12);
+        assert!((burn.budget_fraction - 0.001).abs() < 1e-12);
+        assert!((burn.burn_multiplier - 2.0).abs() < 1e-6);
+
+        let idle = store.error_budget_burn("/nowhere", 1_000, 0.999).unwrap();
+        assert_eq!((idle.window_end_ns, idle.total), (None, 0));
+        assert_eq!(idle.burn_multiplier, 0.0);
+
+        assert!(store.error_budget_burn("/checkout", 0, 0.999).is_err());
+        assert!(store.error_budget_burn("/checkout", 1_000, 1.0).is_err());
+    }
+
+    #[test]
+    fn materialized_cluster_summary_matches_fresh_computation() {
+        let store = memory_store();
+        for s in [
+            span("a", Some("corr-1"), 100, 150),
+            SpanRecord {
+                http_status_code: Some(503),
+                ..span("b", Some("corr-1"), 120, 400)
+            },
+            SpanRecord {
+                status_code: Some("ERROR".to_string()),
+                ..span("c", Some("corr-1"), 90, 95)
+            },
+            span("other", Some("corr-2"), 0, 1_000),
+        ] {
+            store.upsert_span(&s).unwrap();
+        }
+        snapshot_with_sheet(&store, "s1", Some("corr-1"));
+        snapshot_with_sheet(&store, "s2", Some("corr-1"));
+
+        assert_eq!(store.load_cluster_summary("corr-1").unwrap(), None);
+        store.materialize_cluster_summary("corr-1").unwrap();
+        let stored = store.load_cluster_summary("corr-1").unwrap().unwrap();
+        assert_eq!(stored, store.compute_cluster_summary("corr-1").unwrap());
+        assert_eq!(
+            stored,
+            ClusterSummary {
+                correlation_id: "corr-1".to_string(),
+                span_count: 3,
+                error_count: 2,
+                total_duration_ns: 50 + 280 + 5,
+                earliest_ns: Some(90),
+                latest_ns: Some(400),
+                dom_sheet_count: 2,
+            }
+        );
+
+        // Stale until re-materialized.
+        store.upsert_span(&span("d", Some("corr-1"), 500, 510)).unwrap();
+        assert_eq!(store.load_cluster_summary("corr-1").unwrap().unwrap().span_count, 3);
+        store.materialize_cluster_summary("corr-1").unwrap();
+        let refreshed = store.load_cluster_summary("corr-1").unwrap().unwrap();
+        assert_eq!(refreshed, store.compute_cluster_summary("corr-1").unwrap());
+        assert_eq!(refreshed.latest_ns, Some(510));
+
+        let empty = store.compute_cluster_summary("unknown").unwrap();
+        assert_eq!((empty.span_count, empty.earliest_ns), (0, None));
+    }
+
+    #[test]
+    fn dedup_ingest_is_idempotent() {
+        let store = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            dedup_ingest: true,
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        let raw = json!({
+            "span_id": "s1",
+            "trace_id": "trace-1",
+            "name": "GET /cart",
+            "start_time_ns": 100,
+            "end_time_ns": 250,
+            "attributes": {"http.route": "/cart"}
+        });
+        assert_eq!(
+            ingest_otel_span(&store, &raw.to_string()).unwrap(),
+            IngestOutcome::Stored
+        );
+
+        let mut retried = raw.clone();
+        retried["attributes"]["http.route"] = json!("/cart-v2");
+        assert_eq!(
+            ingest_otel_span(&store, &retried.to_string()).unwrap(),
+            IngestOutcome::AlreadyPresent
+        );
+
+        let stored = store.find_slow_spans_with_dom(0, 10).unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].0.http_route.as_deref(), Some("/cart"));
+    }
+
+    #[test]
+    fn ingest_extracts_rpc_and_messaging_columns() {
+        let store = memory_store();
+        let spans = [
+            json!({
+                "span_id": "grpc", "trace_id": "t", "start_time_ns": 1, "end_time_ns": 2,
+                "attributes": {"rpc.system": "grpc", "rpc.method": "GetCart"}
+            }),
+            json!({
+                "span_id": "kafka", "trace_id": "t", "start_time_ns": 3, "end_time_ns": 4,
+                "attributes": {"messaging.system": "kafka", "messaging.destination.name": "orders"}
+            }),
+            json!({
+                "span_id": "http", "trace_id": "t", "start_time_ns": 5, "end_time_ns": 6,
+                "attributes": {"http.method": "GET", "http.route": "/cart"}
+            }),
+        ];
+        for raw in &spans {
+            ingest_otel_span(&store, &raw.to_string()).unwrap();
+        }
+
+        let grpc = store.find_spans_by_rpc_method("GetCart").unwrap();
+        assert_eq!(grpc.len(), 1);
+        assert_eq!(grpc[0].span_id, "grpc");
+        assert_eq!(grpc[0].messaging_destination, None);
+
+        let kafka = store.find_spans_by_messaging_destination("orders").unwrap();
+        assert_eq!(kafka.len(), 1);
+        assert_eq!(kafka[0].span_id, "kafka");
+        assert_eq!(kafka[0].rpc_method, None);
+
+        let http = store.find_slow_spans_with_dom(0, 10).unwrap();
+        let http = http.iter().find(|(s, _)| s.span_id == "http").unwrap();
+        assert_eq!(
+            (
+                http.0.rpc_method.as_deref(),
+                http.0.messaging_destination.as_deref()
+            ),
+            (None, None)
+        );
+    }
+
+    #[test]
+    fn ingest_rejects_inverted_and_out_of_range_timestamps() {
+        let store = memory_store();
+        let raw = |start: Value, end: Value| {
+            json!({
+                "span_id": "s1",
+                "trace_id": "trace-1",
+                "start_time_unix_nano": start,
+                "end_time_unix_nano": end
+            })
+            .to_string()
+        };
+
+        let inverted = ingest_otel_span(&store, &raw(json!(200), json!(100))).unwrap_err();
+        assert!(
+            inverted.to_string().contains("precedes start_time_ns"),
+            "{}",
+            inverted
+        );
+
+        let far_future =
+            ingest_otel_span(&store, &raw(json!(0), json!(8_000_000_000_000_000_000i64)));
+        assert!(far_future
+            .unwrap_err()
+            .to_string()
+            .contains("outside the supported range"));
+        let overflow = ingest_otel_span(&store, &raw(json!("99999999999999999999"), json!(0)));
+        assert!(overflow.unwrap_err().to_string().contains("not an i64"));
+        let negative = ingest_otel_span(&store, &raw(json!(-5), json!(10)));
+        assert!(negative.is_err());
+
+        assert!(store.find_slow_spans_with_dom(0, 10).unwrap().is_empty());
+        ingest_otel_span(&store, &raw(json!("100"), json!(100))).unwrap();
+    }
+
+    #[test]
+    fn dom_grid_maps_cells_to_dominant_role() {
+        let store = memory_store();
+        let bbox = |x: f64, y: f64, width: f64, height: f64| {
+            json!({"x": x, "y": y, "width": width, "height": height})
+        };
+        let geo = DomSnapshotRecord {
+            snapshot_id: "snap-geo".to_string(),
+            trace_id: None,
+            correlation_id: Some("corr-ui".to_string()),
+            captured_at_ns: 0,
+            raw_dom: json!({"tag": "body", "bbox": bbox(0.0, 0.0, 1280.0, 800.0), "children": [
+                {"tag": "button", "bbox": bbox(10.0, 10.0, 80.0, 30.0)},
+                {"tag": "a", "bbox": bbox(120.0, 10.0, 200.0, 20.0)},
+                {"tag": "div", "role": "navigation", "bbox": bbox(0.0, 100.0, 300.0, 100.0),
+                 "children": [{"tag": "input", "bbox": bbox(0.0, 100.0, 50.0, 50.0)}]}
+            ]}),
+        };
+        let flat = DomSnapshotRecord {
+            snapshot_id: "snap-flat".to_string(),
+            raw_dom: json!({"tag": "body", "children": [{"tag": "button"}]}),
+            ..geo.clone()
+        };
+        for snap in [&geo, &flat] {
+            store.insert_dom_snapshot(snap).unwrap();
+            let sheet_id = snap.snapshot_id.replace("snap", "sheet");
+            derive_dom_sheet_from_snapshot(&store, &sheet_id, &snap.snapshot_id, snap).unwrap();
+        }
+
+        let sheets = store.load_virtual_object_cluster("corr-ui").unwrap().dom_sheets;
+        let grid_of = |id: &str| {
+            let sheet = sheets.iter().find(|s| s.sheet_id == id).unwrap();
+            sheet.dom_tree["grid"]["cells"].as_array().unwrap().clone()
+        };
+        assert!(grid_of("sheet-flat").is_empty());
+        let cells: Vec<(i64, i64, String)> = grid_of("sheet-geo")
+            .iter()
+            .map(|c| {
+                (
+                    c["row"].as_i64().unwrap(),
+                    c["col"].as_i64().unwrap(),
+                    c["role"].as_str().unwrap().to_string(),
+                )
+            })
+            .collect();
+        let expected = [
+            (0, 0, "button"),
+            (0, 1, "link"),
+            (0, 2, "link"),
+            (0, 3, "link"),
+            (1, 0, "navigation"),
+            (1, 1, "navigation"),
+            (1, 2, "navigation"),
+        ];
+        assert_eq!(
+            cells,
+            expected
+                .iter()
+                .map(|(r, c, role)| (*r, *c, role.to_string()))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(store.sheets_with_element_at(0, 0, "button").unwrap(), ["sheet-geo"]);
+        assert_eq!(store.sheets_with_element_at(1, 0, "navigation").unwrap(), ["sheet-geo"]);
+        // The input is outweighed by the navigation region around it.
+        assert!(store.sheets_with_element_at(1, 0, "input").unwrap().is_empty());
+        assert!(store.sheets_with_element_at(5, 5, "button").unwrap().is_empty());
+    }
+
+    #[test]
+    fn dynamic_id_patterns_flag_uuids_but_not_numbered_sections() {
+        let store = memory_store();
+        let dom = json!({"tag": "body", "children": [
+            {"tag": "section", "id": "section2", "children": [
+                {"tag": "button", "id": "3f2c9a1e-7b4d-4c8e-9f10-2a6b5c4d3e21"},
+                {"tag": "a", "id": "item-1717171717171"},
+                {"tag": "input", "id": "field3"},
+            ]}
+        ]});
+        assert_eq!(estimate_dynamic_ids(&dom, &store.dynamic_ids), 2);
+        assert_eq!(
+            interactive_selectors(&dom, &store.dynamic_ids),
+            [
+                "body > section#section2 > a",
+                "body > section#section2 > button",
+                "body > section#section2 > input#field3",
+            ]
+        );
+        for (id, dynamic) in [
+            ("3F2C9A1E-7B4D-4C8E-9F10-2A6B5C4D3E21", true),
+            ("row-deadbeef0042", true),
+            ("user_session", true),
+            ("section2", false),
+            ("h1", false),
+            ("step-2024", false),
+        ] {
+            assert_eq!(store.dynamic_ids.is_match(id), dynamic, "{}", id);
+        }
+
+        let numbered = JavaspectreStore::open(JavaspectreConfig {
+            path: ":memory:".to_string(),
+            wal_mode: false,
+            dynamic_id_patterns: vec!["[0-9]".to_string()],
+            ..JavaspectreConfig::default()
+        })
+        .unwrap();
+        assert_eq!(estimate_dynamic_ids(&dom, &numbered.dynamic_ids), 4);
+
+        let invalid = JavaspectreConfig {
+            dynamic_id_patterns: vec!["(unclosed".to_string()],
+            ..JavaspectreConfig::default()
+        };
+        match invalid.validate() {
+            Err(JavaspectreError::Config(msg)) => assert!(msg.contains("(unclosed"), "{}", msg),
+            other => panic!("expected Config error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn stable_selectors_keep_only_selectors_seen_in_every_sheet() {
+        let store = memory_store();
+        let capture = |id: &str, children: Value| {
+            let snap = DomSnapshotRecord {
+                snapshot_id: format!("snap-{}", id),
+                trace_id: None,
+                correlation_id: Some("corr-ui".to_string()),
+                captured_at_ns: 0,
+                raw_dom: json!({"tag": "body", "children": [
+                    {"tag": "form", "id": "login", "children": children}
+                ]}),
+            };
+            store.insert_dom_snapshot(&snap).unwrap();
+            derive_dom_sheet_from_snapshot(
+                &store,
+                &format!("sheet-{}", id),
+                &snap.snapshot_id,
+                &snap,
+            )
+            .unwrap();
+        };
+        let submit = json!({"tag": "button", "id": "submit"});
+        let promo = json!({"tag": "a", "id": "promo"});
+        capture(
+            "1",
+            json!([submit, promo, {"tag": "input", "id": "session-81"}]),
+        );
+        capture(
+            "2",
+            json!([submit, promo, {"tag": "input", "id": "session-93"}]),
+        );
+        capture("3", json!([submit]));
+
+        assert_eq!(
+            store.stable_selectors("corr-ui").unwrap(),
+            vec!["body > form#login > button#submit"]
+        );
+
+        // Sheets stored without the `paths` feature fall back to their snapshot.
+        store
+            .conn
+            .execute(
+                "UPDATE dom_sheets SET dom_tree = '{}' WHERE sheet_id = 'sheet-3'",
+                NO_PARAMS,
+            )
+            .unwrap();
+        assert_eq!(
+            store.stable_selectors("corr-ui").unwrap(),
+            vec!["body > form#login > button#submit"]
+        );
+        assert!(store.stable_selectors("corr-none").unwrap().is_empty());
+    }
+
+    #[test]
+    fn slow_span_thresholds_depend_on_span_kind() {
+        let store = memory_store();
+        let ms = 1_000_000;
+        let kinded = |id: &str, kind: Option<&str>, duration: i64| SpanRecord {
+            span_kind: kind.map(str::to_string),
+            ..span(id, None, 0, duration)
+        };
+        store
+            .upsert_span(&kinded("db", Some("client"), 50 * ms))
+            .unwrap();
+        store
+            .upsert_span(&kinded("http", Some("server"), 50 * ms))
+            .unwrap();
+        store.upsert_span(&kinded("job", None, 150 * ms)).unwrap();
+        store
+            .upsert_span(&kinded("queue", Some("consumer"), 90 * ms))
+            .unwrap();
+
+        let thresholds = HashMap::from([
+            ("client".to_string(), 20 * ms),
+            ("server".to_string(), 200 * ms),
+        ]);
+        let slow: Vec<String> = store
+            .find_slow_spans_by_kind(&thresholds, 100 * ms, 10)
+            .unwrap()
+            .into_iter()
+            .map(|(span, _)| span.span_id)
+            .collect();
+        assert_eq!(slow, vec!["job", "db"]);
+
+        let all = store
+            .find_slow_spans_by_kind(&HashMap::new(), 0, 10)
+            .unwrap();
+        assert_eq!(all.len(), 4);
+    }
+
+    #[test]
+    fn upsert_cluster_scores_writes_a_batch() {
+        let store = memory_store();
+        store.init_score_table().unwrap();
+        let score = |i: usize| ClusterScore {
+            correlation_id: format!("corr-{}", i),
+            stability_score: i as f64 / 100.0,
+            novelty_score: 0.5,
+            drift_score: 1.0 - i as f64 / 100.0,
+        };
+        store.upsert_cluster_score(&score(0), 1).unwrap();
+
+        let batch: Vec<(ClusterScore, i64)> = (0..100).map(|i| (score(i), 2)).collect();
+        assert_eq!(store.upsert_cluster_scores(&batch).unwrap(), 100);
+
+        for i in 0..100 {
+            let stored = store
+                .load_cluster_score(&format!("corr-{}", i))
+                .unwrap()
+                .unwrap();
+            assert_eq!(stored.stability_score, i as f64 / 100.0);
+            assert_eq!(stored.drift_score, 1.0 - i as f64 / 100.0);
+        }
+        let count: i64 = store
+            .conn
+            .query_row("SELECT COUNT(*) FROM cluster_scores", NO_PARAMS, |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 100);
+        assert_eq!(store.upsert_cluster_scores(&[]).unwrap(), 0);
+    }
+
+    #[test]
+    fn novel_span_names_only_reports_names_first_seen_after_cutoff() {
+        let store = memory_store();
+        let named = |id: &str, name: &str, start: i64| SpanRecord {
+            span_name: name.to_string(),
+            ..span(id, None, start, start + 10)
+        };
+        store.upsert_span(&named("a1", "GET /", 100)).unwrap();
+        store.upsert_span(&named("b1", "POST /login", 150)).unwrap();
+        store.upsert_span(&named("a2", "GET /", 300)).unwrap();
+        store
+            .upsert_span(&named("c1", "DELETE /user", 400))
+            .unwrap();
+        store
+            .upsert_span(&named("c2", "DELETE /user", 500))
+            .unwrap();
+        store.upsert_span(&named("d1", "GET /v2", 400)).unwrap();
+
+        assert_eq!(
+            store.novel_span_names(200).unwrap(),
+            vec![
+                ("DELETE /user".to_string(), 400),
+                ("GET /v2".to_string(), 400),
+            ]
+        );
+        assert!(store.novel_span_names(501).unwrap().is_empty());
+        let everything: Vec<String> = store
+            .novel_span_names(0)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(
+            everything,
+            vec!["GET /", "POST /login", "DELETE /user", "GET /v2"]
+        );
+    }
+}
@@ -21,6 +21,11 @@ pub struct BranchProtectionTemplate {
     pub require_multiple_reviewers: bool,
     pub enforce_code_owners: bool,
     pub require_status_checks: Option<Vec<String>>,
+    /// Paths (e.g. `/src/security/`) that must have a CODEOWNERS entry.
+    /// Omitted from the serialized template when empty so existing
+    /// config hashes are unchanged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_codeowner_paths: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +38,87 @@ pub struct GithubOrgGuardrailPlan {
     pub effective_config: HashMap<String, serde_json::Value>,
     /// Deterministic SHA-256 hash over effective_config for auditing.
     pub config_hash: String,
+    /// Arguments for steps that need them, keyed by step action.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub step_params: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum GuardrailConfigError {
+    #[error("team_review_matrix contains an empty or whitespace-only team key")]
+    EmptyTeamKey,
+    #[error("team_review_matrix key '{0}' is listed more than once")]
+    DuplicateTeam(String),
+    #[error("team '{0}' has no reviewers")]
+    EmptyReviewers(String),
+    #[error("team '{0}' has an empty or whitespace-only reviewer entry")]
+    BlankReviewer(String),
+    #[error("team '{team}' lists reviewer '{reviewer}' more than once")]
+    DuplicateReviewer { team: String, reviewer: String },
+    #[error("required CODEOWNERS path '{0}' must be non-empty and start with '/'")]
+    InvalidCodeownerPath(String),
+}
+
+/// Reject team review matrices that would silently produce broken guardrails.
+///
+/// Keys and reviewers are compared after trimming, so `"core"` and `" core"`
+/// count as the same team. Teams are checked in sorted order so the reported
+/// error is deterministic.
+pub fn validate_team_review_matrix(
+    matrix: &HashMap<String, Vec<String>>,
+) -> Result<(), GuardrailConfigError> {
+    let mut teams: Vec<&String> = matrix.keys().collect();
+    teams.sort();
+
+    let mut seen_teams = std::collections::HashSet::new();
+    for team in teams {
+        let key = team.trim();
+        if key.is_empty() {
+            return Err(GuardrailConfigError::EmptyTeamKey);
+        }
+        if !seen_teams.insert(key) {
+            return Err(GuardrailConfigError::DuplicateTeam(key.to_string()));
+        }
+
+        let reviewers = &matrix[team];
+        if reviewers.is_empty() {
+            return Err(GuardrailConfigError::EmptyReviewers(key.to_string()));
+        }
+        let mut seen_reviewers = std::collections::HashSet::new();
+        for reviewer in reviewers {
+            let reviewer = reviewer.trim();
+            if reviewer.is_empty() {
+                return Err(GuardrailConfigError::BlankReviewer(key.to_string()));
+            }
+            if !seen_reviewers.insert(reviewer) {
+                return Err(GuardrailConfigError::DuplicateReviewer {
+                    team: key.to_string(),
+                    reviewer: reviewer.to_string(),
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reject required CODEOWNERS paths that are blank or not rooted at the
+/// repository (`/src/security/`, not `src/security/`).
+pub fn validate_codeowner_paths(paths: &[String]) -> Result<(), GuardrailConfigError> {
+    match paths.iter().find(|p| !p.trim().starts_with('/')) {
+        Some(bad) => Err(GuardrailConfigError::InvalidCodeownerPath(bad.clone())),
+        None => Ok(()),
+    }
+}
+
+/// Validating variant of [`normalize_github_org_guardrail_options`].
+pub fn try_normalize_github_org_guardrail_options(
+    options: GithubOrgGuardrailOptions,
+) -> Result<GithubOrgGuardrailPlan, GuardrailConfigError> {
+    validate_team_review_matrix(&options.team_review_matrix)?;
+    if let Some(template) = &options.branch_protection_template {
+        validate_codeowner_paths(&template.required_codeowner_paths)?;
+    }
+    Ok(normalize_github_org_guardrail_options(options))
 }
 
 /// Normalize guardrail options into a deterministic plan.
@@ -43,6 +129,7 @@ pub fn normalize_github_org_guardrail_options(
     options: GithubOrgGuardrailOptions,
 ) -> GithubOrgGuardrailPlan {
     let mut steps = vec!["normalizeGithubOrgGuardrailOptions".to_string()];
+    let mut step_params = HashMap::new();
 
     // Billing mode handling
     match options.codespaces_billing {
@@ -65,6 +152,21 @@ pub fn normalize_github_org_guardrail_options(
         if template.enforce_code_owners {
             steps.push("apply_CODEOWNERS_enforcement".to_string());
         }
+        let mut codeowner_paths: Vec<&str> = template
+            .required_codeowner_paths
+            .iter()
+            .map(|p| p.trim())
+            .filter(|p| !p.is_empty())
+            .collect();
+        codeowner_paths.sort_unstable();
+        codeowner_paths.dedup();
+        if !codeowner_paths.is_empty() {
+            steps.push("apply_CODEOWNERS_path_requirements".to_string());
+            step_params.insert(
+                "apply_CODEOWNERS_path_requirements".to_string(),
+                serde_json::json!({ "paths": codeowner_paths }),
+            );
+        }
         if template.require_status_checks.as_ref().map(|v| !v.is_empty()).unwrap_or(false) {
             steps.push("apply_status_checks".to_string());
         }
@@ -124,6 +226,7 @@ pub fn normalize_github_org_guardrail_options(
         steps,
         effective_config: effective_config_map,
         config_hash,
+        step_params,
     }
 }
 
@@ -135,7 +238,7 @@ pub fn normalize_github_org_guardrail_options(
 ///    - Base step: "normalizeGithubOrgGuardrailOptions".
 ///    - Billing: always contributes 2 steps (either OrgPaid or UserPaidOnly).
 ///    - Branch protection: contributes ≥1 ("branch_protection_template" or
-///      "no_branch_protection_template_defined") plus up to 3 more.
+///      "no_branch_protection_template_defined") plus up to 4 more.
 ///    - Pages: contributes 2 steps ("enable_github_pages"+policy or
 ///      "skip_pages"+policy).
 ///    - Team matrix: contributes 2 steps.
@@ -154,6 +257,214 @@ pub fn verify_plan_completeness(plan: &GithubOrgGuardrailPlan) -> bool {
         && plan.config_hash.len() == 64
 }
 
+/// Named set of step actions a plan must (and must not) contain, e.g.
+/// `required_steps = ["apply_status_checks", "apply_CODEOWNERS_enforcement"]`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GuardrailPolicy {
+    pub name: String,
+    #[serde(default)]
+    pub required_steps: Vec<String>,
+    #[serde(default)]
+    pub forbidden_steps: Vec<String>,
+}
+
+/// Check a normalized plan against a policy.
+///
+/// Returns every unmet requirement (missing required steps first, then
+/// forbidden steps that are present) so callers can report them all at once.
+pub fn verify_against_policy(
+    plan: &GithubOrgGuardrailPlan,
+    policy: &GuardrailPolicy,
+) -> Result<(), Vec<String>> {
+    let has_step = |action: &String| plan.steps.iter().any(|s| s == action);
+
+    let mut unmet: Vec<String> = policy
+        .required_steps
+        .iter()
+        .filter(|action| !has_step(action))
+        .map(|action| {
+            format!(
+                "policy '{}': missing required step '{}'",
+                policy.name, action
+            )
+        })
+        .collect();
+    unmet.extend(
+        policy
+            .forbidden_steps
+            .iter()
+            .filter(|action| has_step(action))
+            .map(|action| {
+                format!(
+                    "policy '{}': forbidden step '{}' present",
+                    policy.name, action
+                )
+            }),
+    );
+
+    if unmet.is_empty() {
+        Ok(())
+    } else {
+        Err(unmet)
+    }
+}
+
+/// One plan step handed to an executor, with its position in the plan and
+/// its entry from `step_params` (`null` when the step takes none).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardrailStep {
+    pub index: usize,
+    pub action: String,
+    #[serde(default)]
+    pub params: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StepStatus {
+    /// The executor applied the step against the org.
+    Applied,
+    /// The executor recorded the step without touching the org.
+    Planned,
+    /// The executor refused or failed the step.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StepOutcome {
+    pub step: GuardrailStep,
+    pub status: StepStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ExecError {
+    #[error("step '{0}' is not supported by this executor")]
+    Unsupported(String),
+    #[error("step '{action}' failed: {reason}")]
+    Failed { action: String, reason: String },
+}
+
+/// Backend that turns plan steps into org changes (GitHub REST/GraphQL, an
+/// IDE agent, or a recorder for previews).
+pub trait GuardrailExecutor {
+    fn execute_step(&mut self, step: &GuardrailStep) -> Result<StepOutcome, ExecError>;
+}
+
+/// What [`apply_plan_with`] does after a step fails.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnStepError {
+    #[default]
+    Stop,
+    Continue,
+}
+
+/// Run every plan step in order, stopping at the first failure.
+pub fn apply_plan(
+    plan: &GithubOrgGuardrailPlan,
+    exec: &mut dyn GuardrailExecutor,
+) -> Vec<StepOutcome> {
+    apply_plan_with(plan, exec, OnStepError::Stop)
+}
+
+/// Run plan steps in order. A failed step is recorded as
+/// [`StepStatus::Failed`]; with [`OnStepError::Stop`] no later steps run.
+pub fn apply_plan_with(
+    plan: &GithubOrgGuardrailPlan,
+    exec: &mut dyn GuardrailExecutor,
+    on_error: OnStepError,
+) -> Vec<StepOutcome> {
+    let mut outcomes = Vec::with_capacity(plan.steps.len());
+    for (index, action) in plan.steps.iter().enumerate() {
+        let step = GuardrailStep {
+            index,
+            action: action.clone(),
+            params: plan.step_params.get(action).cloned().unwrap_or_default(),
+        };
+        match exec.execute_step(&step) {
+            Ok(outcome) => outcomes.push(outcome),
+            Err(e) => {
+                outcomes.push(StepOutcome {
+                    step,
+                    status: StepStatus::Failed(e.to_string()),
+                });
+                if on_error == OnStepError::Stop {
+                    break;
+                }
+            }
+        }
+    }
+    outcomes
+}
+
+/// Executor that changes nothing and records the steps it was asked to run.
+#[derive(Debug, Default)]
+pub struct DryRunExecutor {
+    pub recorded: Vec<GuardrailStep>,
+}
+
+impl GuardrailExecutor for DryRunExecutor {
+    fn execute_step(&mut self, step: &GuardrailStep) -> Result<StepOutcome, ExecError> {
+        self.recorded.push(step.clone());
+        Ok(StepOutcome {
+            step: step.clone(),
+            status: StepStatus::Planned,
+        })
+    }
+}
+
+/// Preview of what replacing `old` with `new` would change.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanDiff {
+    /// Steps in `new` that `old` does not have, in `new`'s order.
+    pub added_steps: Vec<String>,
+    /// Steps in `old` that `new` drops, in `old`'s order.
+    pub removed_steps: Vec<String>,
+    /// effective_config keys that were added, removed or changed value (sorted).
+    pub changed_config_keys: Vec<String>,
+    pub config_hash_changed: bool,
+}
+
+impl PlanDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added_steps.is_empty()
+            && self.removed_steps.is_empty()
+            && self.changed_config_keys.is_empty()
+            && !self.config_hash_changed
+    }
+}
+
+/// Compare a currently-applied plan with a proposed one.
+pub fn diff_plans(old: &GithubOrgGuardrailPlan, new: &GithubOrgGuardrailPlan) -> PlanDiff {
+    let added_steps = new
+        .steps
+        .iter()
+        .filter(|s| !old.steps.contains(s))
+        .cloned()
+        .collect();
+    let removed_steps = old
+        .steps
+        .iter()
+        .filter(|s| !new.steps.contains(s))
+        .cloned()
+        .collect();
+
+    let mut changed_config_keys: Vec<String> = old
+        .effective_config
+        .keys()
+        .chain(new.effective_config.keys())
+        .filter(|k| old.effective_config.get(*k) != new.effective_config.get(*k))
+        .cloned()
+        .collect();
+    changed_config_keys.sort();
+    changed_config_keys.dedup();
+
+    PlanDiff {
+        added_steps,
+        removed_steps,
+        changed_config_keys,
+        config_hash_changed: old.config_hash != new.config_hash,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,6 +491,7 @@ mod tests {
                     "ci/smoke".to_string(),
                     "ci/security-scan".to_string(),
                 ]),
+                required_codeowner_paths: vec![],
             }),
             enable_pages: true,
             team_review_matrix: matrix,
@@ -221,6 +533,7 @@ mod tests {
                     "ci/smoke".to_string(),
                     "ci/security-scan".to_string(),
                 ]),
+                required_codeowner_paths: vec![],
             }),
             enable_pages: true,
             team_review_matrix: matrix2,
@@ -268,16 +581,341 @@ mod tests {
             .chars()
             .all(|c| c.is_ascii_hexdigit()));
     }
+
+    fn strict_review_policy() -> GuardrailPolicy {
+        GuardrailPolicy {
+            name: "strict-review".to_string(),
+            required_steps: vec![
+                "apply_status_checks".to_string(),
+                "apply_CODEOWNERS_enforcement".to_string(),
+            ],
+            forbidden_steps: vec!["no_branch_protection_template_defined".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_policy_satisfied_by_full_template() {
+        let options = GithubOrgGuardrailOptions {
+            codespaces_billing: BillingMode::OrgPaid,
+            branch_protection_template: Some(BranchProtectionTemplate {
+                require_multiple_reviewers: false,
+                enforce_code_owners: true,
+                require_status_checks: Some(vec!["ci/smoke".to_string()]),
+                required_codeowner_paths: vec![],
+            }),
+            enable_pages: false,
+            team_review_matrix: HashMap::new(),
+        };
+
+        let plan = normalize_github_org_guardrail_options(options);
+        assert_eq!(
+            verify_against_policy(&plan, &strict_review_policy()),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_policy_reports_missing_required_step() {
+        let options = GithubOrgGuardrailOptions {
+            codespaces_billing: BillingMode::OrgPaid,
+            branch_protection_template: Some(BranchProtectionTemplate {
+                require_multiple_reviewers: true,
+                enforce_code_owners: true,
+                require_status_checks: None,
+                required_codeowner_paths: vec![],
+            }),
+            enable_pages: false,
+            team_review_matrix: HashMap::new(),
+        };
+
+        let plan = normalize_github_org_guardrail_options(options);
+        let unmet = verify_against_policy(&plan, &strict_review_policy()).unwrap_err();
+        assert_eq!(
+            unmet,
+            vec!["policy 'strict-review': missing required step 'apply_status_checks'".to_string()]
+        );
+    }
+
+    fn options_with_matrix(entries: Vec<(&str, Vec<&str>)>) -> GithubOrgGuardrailOptions {
+        GithubOrgGuardrailOptions {
+            codespaces_billing: BillingMode::OrgPaid,
+            branch_protection_template: None,
+            enable_pages: false,
+            team_review_matrix: entries
+                .into_iter()
+                .map(|(team, reviewers)| {
+                    (
+                        team.to_string(),
+                        reviewers.into_iter().map(String::from).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_try_normalize_accepts_valid_matrix() {
+        let options = options_with_matrix(vec![("core", vec!["security-team", "platform-team"])]);
+        assert!(try_normalize_github_org_guardrail_options(options).is_ok());
+    }
+
+    #[test]
+    fn test_try_normalize_rejects_empty_team_key() {
+        let options = options_with_matrix(vec![("  ", vec!["dev-team"])]);
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::EmptyTeamKey
+        );
+    }
+
+    #[test]
+    fn test_try_normalize_rejects_duplicate_team_after_trim() {
+        let options = options_with_matrix(vec![("core", vec!["a"]), (" core ", vec!["b"])]);
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::DuplicateTeam("core".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_normalize_rejects_empty_reviewer_list() {
+        let options = options_with_matrix(vec![("infra", vec![])]);
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::EmptyReviewers("infra".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_normalize_rejects_blank_reviewer() {
+        let options = options_with_matrix(vec![("infra", vec!["infra-team", " "])]);
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::BlankReviewer("infra".to_string())
+        );
+    }
+
+    #[test]
+    fn test_try_normalize_rejects_duplicate_reviewer() {
+        let options = options_with_matrix(vec![("infra", vec!["infra-team", "infra-team "])]);
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::DuplicateReviewer {
+                team: "infra".to_string(),
+                reviewer: "infra-team".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_dry_run_executor_records_plan_steps_in_order() {
+        let options = options_with_matrix(vec![("core", vec!["security-team"])]);
+        let plan = normalize_github_org_guardrail_options(options);
+
+        let mut exec = DryRunExecutor::default();
+        let outcomes = apply_plan(&plan, &mut exec);
+
+        let recorded: Vec<&str> = exec.recorded.iter().map(|s| s.action.as_str()).collect();
+        assert_eq!(
+            recorded,
+            vec![
+                "normalizeGithubOrgGuardrailOptions",
+                "configure_org_billing_and_spend_limit",
+                "enable_org_codespaces_usage_telemetry",
+                "no_branch_protection_template_defined",
+                "skip_pages",
+                "disable_org_level_pages_deployment",
+                "define_team_based_review_matrix",
+                "enforce_team_review_overrides_for_critical_repos",
+            ]
+        );
+        assert_eq!(outcomes.len(), plan.steps.len());
+        assert!(outcomes.iter().all(|o| o.status == StepStatus::Planned));
+    }
+
+    struct FailingExecutor {
+        fail_on: &'static str,
+        seen: usize,
+    }
+
+    impl GuardrailExecutor for FailingExecutor {
+        fn execute_step(&mut self, step: &GuardrailStep) -> Result<StepOutcome, ExecError> {
+            self.seen += 1;
+            if step.action == self.fail_on {
+                return Err(ExecError::Unsupported(step.action.clone()));
+            }
+            Ok(StepOutcome {
+                step: step.clone(),
+                status: StepStatus::Applied,
+            })
+        }
+    }
+
+    #[test]
+    fn test_apply_plan_stops_or_continues_on_error() {
+        let plan = normalize_github_org_guardrail_options(options_with_matrix(vec![]));
+
+        let mut stop = FailingExecutor {
+            fail_on: "skip_pages",
+            seen: 0,
+        };
+        let outcomes = apply_plan(&plan, &mut stop);
+        assert_eq!(outcomes.len(), 5);
+        assert!(matches!(outcomes[4].status, StepStatus::Failed(_)));
+
+        let mut cont = FailingExecutor {
+            fail_on: "skip_pages",
+            seen: 0,
+        };
+        let outcomes = apply_plan_with(&plan, &mut cont, OnStepError::Continue);
+        assert_eq!(cont.seen, plan.steps.len());
+        assert_eq!(
+            outcomes
+                .iter()
+                .filter(|o| matches!(o.status, StepStatus::Failed(_)))
+                .count(),
+            1
+        );
+    }
+    #[test]
+    fn test_diff_plans_reports_enabled_pages() {
+        let old_options = options_with_matrix(vec![("core", vec!["security-team"])]);
+        let mut new_options = old_options.clone();
+        new_options.enable_pages = true;
+
+        let old = normalize_github_org_guardrail_options(old_options);
+        let new = normalize_github_org_guardrail_options(new_options);
+        let diff = diff_plans(&old, &new);
+
+        assert_eq!(
+            diff.added_steps,
+            vec![
+                "enable_github_pages".to_string(),
+                "enforce_pages_source_from_main_or_docs".to_string(),
+            ]
+        );
+        assert_eq!(
+            diff.removed_steps,
+            vec![
+                "skip_pages".to_string(),
+                "disable_org_level_pages_deployment".to_string(),
+            ]
+        );
+        assert_eq!(diff.changed_config_keys, vec!["enable_pages".to_string()]);
+        assert!(diff.config_hash_changed);
+        assert!(diff_plans(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn test_required_codeowner_paths_emit_step_with_params() {
+        let mut options = options_with_matrix(vec![]);
+        options.branch_protection_template = Some(BranchProtectionTemplate {
+            require_multiple_reviewers: false,
+            enforce_code_owners: true,
+            require_status_checks: None,
+            required_codeowner_paths: vec![
+                " /src/security/ ".to_string(),
+                "/docs/".to_string(),
+                "/src/security/".to_string(),
+            ],
+        });
+
+        let plan = try_normalize_github_org_guardrail_options(options.clone()).unwrap();
+        let at = plan
+            .steps
+            .iter()
+            .position(|s| s == "apply_CODEOWNERS_path_requirements")
+            .expect("path requirements step");
+        assert_eq!(plan.steps[at - 1], "apply_CODEOWNERS_enforcement");
+        let expected = serde_json::json!({ "paths": ["/docs/", "/src/security/"] });
+        assert_eq!(
+            plan.step_params.get("apply_CODEOWNERS_path_requirements"),
+            Some(&expected)
+        );
+
+        let mut exec = DryRunExecutor::default();
+        apply_plan(&plan, &mut exec);
+        assert_eq!(exec.recorded[at].params, expected);
+        assert!(exec.recorded[0].params.is_null());
+
+        if let Some(template) = options.branch_protection_template.as_mut() {
+            template.required_codeowner_paths.push("src/x".to_string());
+        }
+        assert_eq!(
+            try_normalize_github_org_guardrail_options(options).unwrap_err(),
+            GuardrailConfigError::InvalidCodeownerPath("src/x".to_string())
+        );
+    }
+
+    #[test]
+    fn implementation_digest_ignores_only_the_constant_value() {
+        let source = |value: &str, body: &str| {
+            [
+                "// header",
+                "pub const IMPLEMENTATION_HASH: &str =",
+                &format!("    \"{}\";", value),
+                body,
+                "",
+            ]
+            .join("\n")
+        };
+        let blank = source("", "fn body() {}");
+        let expected = hex::encode(Sha256::digest(blank.as_bytes()));
+
+        let stamped = source(&"a".repeat(64), "fn body() {}");
+        assert_eq!(implementation_digest(&stamped), Some(expected.clone()));
+        assert_eq!(implementation_digest(&blank), Some(expected.clone()));
+        assert_ne!(
+            implementation_digest(&source(&"a".repeat(64), "fn body() { 1; }")),
+            Some(expected)
+        );
+        assert_eq!(implementation_digest("fn no_constant() {}"), None);
+        assert!(!verify_implementation_hash(&stamped));
+    }
+
+    #[test]
+    fn implementation_hash_matches_this_file() {
+        let source = include_str!("github_org_guardrail.rs");
+        assert!(
+            verify_implementation_hash(source),
+            "IMPLEMENTATION_HASH is stale; expected {:?}",
+            implementation_digest(source)
+        );
+    }
 }
 
-/// Validity hash: SHA256(complete implementation)
+/// Validity hash: SHA-256 of this file with the constant's own value
+/// blanked to `""` (a file cannot contain its own plain hash). Checked by
+/// `verify_implementation_hash`; the `implementation_hash_matches_this_file`
+/// test fails whenever the file changes without it.
 ///
-/// For a sanitized, reproducible stamp, compute:
-/// `sha256sum src/github_org_guardrail.rs`
-/// Example placeholder (replace with real value once in repo):
-/// IMPLEMENTATION_HASH = "e9bf0b3f29f489326998f80a19e78c94b213ac80e52337f0dabe547416fd86ee"[file:10]
+/// To regenerate after editing this file, run from the crate root:
+/// `sed -E '/^pub const IMPLEMENTATION_HASH/,/;/ s/"[0-9a-f]*"/""/' src/github_org_guardrail.rs | sha256sum`
+/// and paste the digest below.
 pub const IMPLEMENTATION_HASH: &str =
-    "e9bf0b3f29f489326998f80a19e78c94b213ac80e52337f0dabe547416fd86ee";
+    "70237a0286c699e55efcee39194e610de113b88ce8bc02e099a789a9b872cc22";
+
+/// Line that opens the `IMPLEMENTATION_HASH` definition.
+const IMPLEMENTATION_HASH_DECL: &str = "\npub const IMPLEMENTATION_HASH: &str =";
+
+/// SHA-256 (hex) of `source` with the string value of its
+/// `IMPLEMENTATION_HASH` definition blanked, or `None` if `source` has no
+/// such definition.
+pub fn implementation_digest(source: &str) -> Option<String> {
+    let decl = source.find(IMPLEMENTATION_HASH_DECL)? + IMPLEMENTATION_HASH_DECL.len();
+    let open = decl + source[decl..].find('"')? + 1;
+    let close = open + source[open..].find('"')?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(&source.as_bytes()[..open]);
+    hasher.update(&source.as_bytes()[close..]);
+    Some(hex::encode(hasher.finalize()))
+}
+
+/// Whether `source` (this file's text) still hashes to `IMPLEMENTATION_HASH`.
+pub fn verify_implementation_hash(source: &str) -> bool {
+    implementation_digest(source).as_deref() == Some(IMPLEMENTATION_HASH)
+}
 
 #[no_mangle]
 pub extern "C" fn github_org_guardrail_plan(
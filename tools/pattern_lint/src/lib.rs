@@ -0,0 +1,315 @@
+use regex::Regex;
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+#[derive(Debug, Deserialize)]
+struct LintConfig {
+    forbidden_terms: Vec<String>,
+    ignore_paths: Vec<String>,
+}
+
+fn default_config() -> LintConfig {
+    LintConfig {
+        forbidden_terms: vec![
+            "Cell".into(),
+            "JavaSpectre".into(),
+            "CyberCore".into(),
+            "CEM".into(),
+            "AU.ET".into(),
+            "CSP".into(),
+        ],
+        ignore_paths: vec![
+            ".git".into(),
+            "target".into(),
+            "node_modules".into(),
+            ".github".into(),
+        ],
+    }
+}
+
+/// Name of the config file looked up in the scan root when no `--config`
+/// is given.
+pub const DEFAULT_CONFIG_FILE: &str = "pattern_lint.config.json";
+
+/// An explicitly requested config file that could not be used.
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+    /// Every entry of `forbidden_terms` is empty or whitespace.
+    BlankTerms {
+        path: PathBuf,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(f, "cannot read lint config {}: {}", path.display(), source)
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "invalid lint config {}: {}", path.display(), source)
+            }
+            ConfigError::BlankTerms { path } => write!(
+                f,
+                "invalid lint config {}: every forbidden term is empty or whitespace",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => Some(source),
+            ConfigError::Parse { source, .. } => Some(source),
+            ConfigError::BlankTerms { .. } => None,
+        }
+    }
+}
+
+fn read_config(path: &Path) -> Result<LintConfig, ConfigError> {
+    let content = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    let mut cfg: LintConfig =
+        serde_json::from_str(&content).map_err(|source| ConfigError::Parse {
+            path: path.to_path_buf(),
+            source,
+        })?;
+    drop_blank_terms(path, &mut cfg)?;
+    Ok(cfg)
+}
+
+/// Remove empty and whitespace-only forbidden terms, warning about each: they
+/// would compile to a bare `\b\b` that matches at every word boundary. A
+/// config whose terms are all blank is an error rather than a silently
+/// disabled lint.
+fn drop_blank_terms(path: &Path, cfg: &mut LintConfig) -> Result<(), ConfigError> {
+    let before = cfg.forbidden_terms.len();
+    cfg.forbidden_terms.retain(|term| !term.trim().is_empty());
+    let dropped = before - cfg.forbidden_terms.len();
+    if dropped == 0 {
+        return Ok(());
+    }
+    if cfg.forbidden_terms.is_empty() {
+        return Err(ConfigError::BlankTerms {
+            path: path.to_path_buf(),
+        });
+    }
+    eprintln!(
+        "pattern_lint: warning: ignoring {} empty forbidden term(s) in {}",
+        dropped,
+        path.display()
+    );
+    Ok(())
+}
+
+/// The config at `explicit` if given, failing if it is missing or invalid.
+/// Otherwise `DEFAULT_CONFIG_FILE` in `root`, falling back to the built-in
+/// defaults only when that file does not exist; one that is present but
+/// unreadable or invalid is still an error.
+fn load_config(root: &Path, explicit: Option<&Path>) -> Result<LintConfig, ConfigError> {
+    if let Some(path) = explicit {
+        return read_config(path);
+    }
+    match read_config(&root.join(DEFAULT_CONFIG_FILE)) {
+        Err(ConfigError::Read { source, .. }) if source.kind() == io::ErrorKind::NotFound => {
+            Ok(default_config())
+        }
+        result => result,
+    }
+}
+
+fn is_ignored(path: &Path, cfg: &LintConfig) -> bool {
+    cfg.ignore_paths
+        .iter()
+        .any(|p| path.to_string_lossy().contains(p))
+}
+
+/// Scan text-like files under `root_path` for the configured forbidden terms and
+/// return one message per (term, file) hit. `config` overrides the default
+/// config lookup (see `load_config`).
+pub fn lint(root_path: &Path, config: Option<&Path>) -> Result<Vec<String>, ConfigError> {
+    let cfg = load_config(root_path, config)?;
+
+    let forbidden_regexes: Vec<Regex> = cfg
+        .forbidden_terms
+        .iter()
+        .filter_map(|term| Regex::new(&format!(r"\b{}\b", regex::escape(term))).ok())
+        .collect();
+
+    let mut violations = Vec::new();
+
+    for entry in WalkDir::new(root_path).into_iter().filter_map(Result::ok) {
+        let path = entry.path();
+
+        if path.is_dir() || is_ignored(path, &cfg) {
+            continue;
+        }
+
+        // Only scan text-like files
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            let ext = ext.to_ascii_lowercase();
+            if !["md", "rs", "json", "aln", "toml", "yml", "yaml", "txt"].contains(&ext.as_str()) {
+                continue;
+            }
+        }
+
+        let content = match fs::read_to_string(path) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        for re in &forbidden_regexes {
+            if re.is_match(&content) {
+                violations.push(format!(
+                    "Forbidden term '{}' in file: {}",
+                    re.as_str(),
+                    path.display()
+                ));
+            }
+        }
+    }
+
+    Ok(violations)
+}
+
+/// Lint `root` and print the result; returns whether no violations were found.
+pub fn run(root: &Path, config: Option<&Path>) -> Result<bool, ConfigError> {
+    let violations = lint(root, config)?;
+    if !violations.is_empty() {
+        eprintln!("pattern_lint found violations:");
+        for v in &violations {
+            eprintln!("  - {}", v);
+        }
+        Ok(false)
+    } else {
+        println!("pattern_lint: no forbidden terms found.");
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(tag: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pattern-lint-{}-{}", tag, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn explicit_config_overrides_the_scan_root_lookup() {
+        let root = scratch_dir("explicit");
+        fs::write(root.join("notes.md"), "uses Widget and CSP").unwrap();
+        let central = scratch_dir("explicit-central").join("lint.json");
+        fs::write(
+            &central,
+            r#"{"forbidden_terms": ["Widget"], "ignore_paths": []}"#,
+        )
+        .unwrap();
+
+        let hits = lint(&root, Some(&central)).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].contains("Widget"), "{:?}", hits);
+        let defaults = lint(&root, None).unwrap();
+        assert!(
+            defaults.iter().all(|h| !h.contains("Widget")),
+            "{:?}",
+            defaults
+        );
+    }
+
+    #[test]
+    fn explicit_config_must_exist_and_parse() {
+        let root = scratch_dir("missing");
+        let missing = root.join("nope.json");
+        match lint(&root, Some(&missing)) {
+            Err(ConfigError::Read { path, source }) => {
+                assert_eq!(path, missing);
+                assert_eq!(source.kind(), io::ErrorKind::NotFound);
+            }
+            other => panic!("expected a read error, got {:?}", other),
+        }
+
+        let typo = root.join("typo.json");
+        fs::write(
+            &typo,
+            r#"{"forbidden_term": ["Widget"], "ignore_paths": []}"#,
+        )
+        .unwrap();
+        let err = lint(&root, Some(&typo)).unwrap_err();
+        assert!(matches!(err, ConfigError::Parse { .. }));
+        assert!(
+            err.to_string().starts_with("invalid lint config"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn blank_terms_are_skipped_but_cannot_make_up_the_whole_config() {
+        let root = scratch_dir("blank-terms");
+        fs::write(root.join("notes.md"), "a Widget here").unwrap();
+        let configs = scratch_dir("blank-terms-configs");
+        let mixed = configs.join("mixed.json");
+        fs::write(
+            &mixed,
+            r#"{"forbidden_terms": ["", "Widget", "  "], "ignore_paths": []}"#,
+        )
+        .unwrap();
+        let hits = lint(&root, Some(&mixed)).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].contains("Widget"), "{:?}", hits);
+
+        let blank = configs.join("blank.json");
+        fs::write(
+            &blank,
+            r#"{"forbidden_terms": ["", " \t"], "ignore_paths": []}"#,
+        )
+        .unwrap();
+        let err = lint(&root, Some(&blank)).unwrap_err();
+        assert!(
+            matches!(&err, ConfigError::BlankTerms { path } if *path == blank),
+            "{:?}",
+            err
+        );
+    }
+
+    #[test]
+    fn default_config_falls_back_only_when_absent() {
+        let root = scratch_dir("default-config");
+        fs::write(root.join("notes.md"), "a Widget here").unwrap();
+        assert!(lint(&root, None).is_ok());
+
+        let default_path = root.join(DEFAULT_CONFIG_FILE);
+        fs::write(&default_path, r#"{"forbidden_terms": ["Widget""#).unwrap();
+        assert!(matches!(lint(&root, None), Err(ConfigError::Parse { .. })));
+
+        fs::write(
+            &default_path,
+            r#"{"forbidden_terms": [" "], "ignore_paths": []}"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            lint(&root, None),
+            Err(ConfigError::BlankTerms { .. })
+        ));
+    }
+}
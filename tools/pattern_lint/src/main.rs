@@ -1,104 +1,48 @@
-use regex::Regex;
-use serde::Deserialize;
 use std::env;
-use std::fs;
-use std::path::Path;
-use walkdir::WalkDir;
-
-#[derive(Debug, Deserialize)]
-struct LintConfig {
-    forbidden_terms: Vec<String>,
-    ignore_paths: Vec<String>,
-}
-
-fn default_config() -> LintConfig {
-    LintConfig {
-        forbidden_terms: vec![
-            "Cell".into(),
-            "JavaSpectre".into(),
-            "CyberCore".into(),
-            "CEM".into(),
-            "AU.ET".into(),
-            "CSP".into(),
-        ],
-        ignore_paths: vec![
-            ".git".into(),
-            "target".into(),
-            "node_modules".into(),
-            ".github".into(),
-        ],
-    }
-}
-
-fn load_config(root: &Path) -> LintConfig {
-    let cfg_path = root.join("pattern_lint.config.json");
-    if cfg_path.exists() {
-        match fs::read_to_string(&cfg_path) {
-            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|_| default_config()),
-            Err(_) => default_config(),
-        }
-    } else {
-        default_config()
-    }
-}
-
-fn is_ignored(path: &Path, cfg: &LintConfig) -> bool {
-    cfg.ignore_paths
-        .iter()
-        .any(|p| path.to_string_lossy().contains(p))
-}
-
-fn main() {
-    let root = env::args().nth(1).unwrap_or_else(|| ".".into());
-    let root_path = Path::new(&root);
-    let cfg = load_config(root_path);
-
-    let forbidden_regexes: Vec<Regex> = cfg
-        .forbidden_terms
-        .iter()
-        .filter_map(|term| Regex::new(&format!(r"\b{}\b", regex::escape(term))).ok())
-        .collect();
-
-    let mut violations = Vec::new();
-
-    for entry in WalkDir::new(root_path).into_iter().filter_map(Result::ok) {
-        let path = entry.path();
-
-        if path.is_dir() || is_ignored(path, &cfg) {
-            continue;
-        }
-
-        // Only scan text-like files
-        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-            let ext = ext.to_ascii_lowercase();
-            if !["md", "rs", "json", "aln", "toml", "yml", "yaml", "txt"].contains(&ext.as_str()) {
-                continue;
-            }
-        }
-
-        let content = match fs::read_to_string(path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-
-        for re in &forbidden_regexes {
-            if re.is_match(&content) {
-                violations.push(format!(
-                    "Forbidden term '{}' in file: {}",
-                    re.as_str(),
-                    path.display()
-                ));
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: pattern_lint [--config <path>] [root]";
+
+// Exit codes follow the workspace convention (see `aln_orchestrator::exit`):
+// 0 clean, 1 violations found, 2 usage error, 3 config file unreadable,
+// 4 config file malformed.
+fn main() -> ExitCode {
+    let mut root = None;
+    let mut config = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => match args.next() {
+                Some(path) => config = Some(PathBuf::from(path)),
+                None => {
+                    eprintln!("pattern_lint: --config needs a path\n{}", USAGE);
+                    return ExitCode::from(2);
+                }
+            },
+            _ if root.is_none() => root = Some(arg),
+            _ => {
+                eprintln!("pattern_lint: unexpected argument {}\n{}", arg, USAGE);
+                return ExitCode::from(2);
             }
         }
     }
-
-    if !violations.is_empty() {
-        eprintln!("pattern_lint found violations:");
-        for v in &violations {
-            eprintln!("  - {}", v);
+    let root = root.unwrap_or_else(|| ".".into());
+    let root = Path::new(&root);
+    if !root.is_dir() {
+        eprintln!("pattern_lint: {} is not a directory", root.display());
+        return ExitCode::from(2);
+    }
+    match pattern_lint::run(root, config.as_deref()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(1),
+        Err(e) => {
+            eprintln!("pattern_lint: {}", e);
+            match e {
+                pattern_lint::ConfigError::Read { .. } => ExitCode::from(3),
+                pattern_lint::ConfigError::Parse { .. }
+                | pattern_lint::ConfigError::BlankTerms { .. } => ExitCode::from(4),
+            }
         }
-        std::process::exit(1);
-    } else {
-        println!("pattern_lint: no forbidden terms found.");
     }
 }